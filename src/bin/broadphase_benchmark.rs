@@ -0,0 +1,200 @@
+extern crate black_grimoire;
+
+use black_grimoire::ecs::components::rigid_body::broadphase::BroadphaseMode;
+use black_grimoire::ecs::components::rigid_body::{RigidBodyBuilder, RigidBodySystem};
+use black_grimoire::ecs::components::transformation::{
+    TransformationBuilder, TransformationSystem,
+};
+use black_grimoire::ecs::{Entity, EntityManager};
+use black_grimoire::gamemath::{Quat, Vec3};
+use black_grimoire::gameprng::prng_traits::{PrngGeneration, PrngSeeding};
+use black_grimoire::gameprng::xorshift128plus::XorShift128Plus;
+use std::env::args;
+use std::f32::consts::TAU;
+use std::str::FromStr;
+use std::time::Instant;
+
+const TIME_STEP: f32 = 1.0 / 60.0;
+
+struct Scene {
+    entity_manager: EntityManager,
+    transformation_system: TransformationSystem,
+    rigid_body_system: RigidBodySystem,
+    statics: Vec<Entity>,
+}
+
+fn random_vector(prng: &mut XorShift128Plus, min: f32, max: f32) -> Vec3<f32> {
+    Vec3::new(
+        prng.range(min, max),
+        prng.range(min, max),
+        prng.range(min, max),
+    )
+}
+
+fn random_rotation(prng: &mut XorShift128Plus) -> Quat {
+    let axis = random_vector(prng, -1.0, 1.0) + Vec3::new(0.0, 0.01, 0.0);
+
+    Quat::rotation(prng.range(0.0, TAU), axis.normalized())
+}
+
+fn random_body(prng: &mut XorShift128Plus) -> RigidBodyBuilder {
+    let builder = RigidBodyBuilder::new()
+        .with_offset(random_vector(prng, -0.2, 0.2))
+        .with_collision_layer(1 << prng.range(0u32, 3u32))
+        .with_collision_mask(prng.range(1u32, 15u32));
+
+    match prng.range(0u32, 3u32) {
+        0 => builder.with_extents(random_vector(prng, 0.2, 1.0)),
+        1 => builder.with_oriented_extents(random_vector(prng, 0.2, 1.0)),
+        2 => builder.with_sphere(prng.range(0.2, 1.0)),
+        _ => builder.with_capsule(prng.range(0.2, 0.6), prng.range(0.1, 0.8)),
+    }
+}
+
+fn build_scene(seed: u64, dynamic_count: usize, static_count: usize, size: f32) -> Scene {
+    let mut prng = XorShift128Plus::new(seed);
+    let mut scene = Scene {
+        entity_manager: EntityManager::new(),
+        transformation_system: TransformationSystem::new(),
+        rigid_body_system: RigidBodySystem::new(),
+        statics: Vec::new(),
+    };
+
+    for i in 0..(dynamic_count + static_count) {
+        let entity = scene.entity_manager.create_new_entity();
+        let is_static = i >= dynamic_count;
+        let mut body = random_body(&mut prng);
+
+        if !is_static {
+            body = body
+                .with_mass(prng.range(0.5, 5.0))
+                .with_velocity(random_vector(&mut prng, -2.0, 2.0));
+        }
+
+        scene.transformation_system.add_transformation_to_entity(
+            &entity,
+            TransformationBuilder::new()
+                .at_position(random_vector(&mut prng, -size, size))
+                .with_rotation(random_rotation(&mut prng)),
+        );
+        scene.rigid_body_system.add_rigid_body_to_entity(
+            &entity,
+            body,
+            &scene.transformation_system,
+        );
+
+        if is_static {
+            scene.statics.push(entity);
+        }
+    }
+
+    scene
+}
+
+// Runs a randomized scene in sweep and prune mode and checks every step that the contacts match
+// the ones found by testing every pair.
+fn verify(seed: u64) -> usize {
+    let mut prng = XorShift128Plus::new(seed ^ 0x5eed);
+    let mut scene = build_scene(seed, 60, 20, 6.0);
+    let mut contact_count = 0;
+
+    for step in 0..120 {
+        // Moves a static body now and then, the cached static bounds have to notice.
+        if step % 10 == 5 {
+            let entity = scene.statics[prng.range(0, scene.statics.len() as u32 - 1) as usize];
+
+            if let Some(position) = scene.transformation_system.get_position_mut(&entity) {
+                *position += random_vector(&mut prng, -1.0, 1.0);
+            }
+        }
+
+        let swept = scene
+            .rigid_body_system
+            .find_contacts(&mut scene.transformation_system);
+
+        scene
+            .rigid_body_system
+            .set_broadphase_mode(BroadphaseMode::BruteForce);
+
+        let brute = scene
+            .rigid_body_system
+            .find_contacts(&mut scene.transformation_system);
+
+        scene
+            .rigid_body_system
+            .set_broadphase_mode(BroadphaseMode::SweepAndPrune);
+
+        if swept != brute {
+            panic!(
+                "Contacts differ in scene {} at step {}: {} with sweep and prune, {} brute force!",
+                seed,
+                step,
+                swept.len(),
+                brute.len()
+            );
+        }
+
+        contact_count += swept.len();
+        scene
+            .rigid_body_system
+            .update(TIME_STEP, &mut scene.transformation_system);
+    }
+
+    contact_count
+}
+
+fn benchmark(mode: BroadphaseMode, body_count: usize, steps: usize) -> f64 {
+    let mut scene = build_scene(
+        1,
+        body_count,
+        body_count / 4,
+        (body_count as f32).cbrt() * 2.0,
+    );
+    scene.rigid_body_system.set_broadphase_mode(mode);
+
+    let start = Instant::now();
+
+    for _ in 0..steps {
+        scene
+            .rigid_body_system
+            .update(TIME_STEP, &mut scene.transformation_system);
+    }
+
+    start.elapsed().as_secs_f64() * 1000.0 / steps as f64
+}
+
+fn main() {
+    let mut args = args();
+
+    if args.len() > 2 {
+        println!("usage: broadphase_benchmark [body count]!");
+        return;
+    }
+
+    let body_count = match args.nth(1) {
+        Some(count) => match usize::from_str(count.as_str()) {
+            Ok(count) => count,
+            Err(e) => {
+                eprintln!("Failed to parse body count: {}", e);
+                return;
+            }
+        },
+        None => 1000,
+    };
+
+    for seed in 1..=20 {
+        let contact_count = verify(seed);
+        println!("Scene {}: {} contacts, identical", seed, contact_count);
+    }
+
+    let steps = 10;
+
+    for mode in [BroadphaseMode::BruteForce, BroadphaseMode::SweepAndPrune] {
+        println!(
+            "{:?} with {} bodies: {:.3} ms per step",
+            mode,
+            body_count,
+            benchmark(mode, body_count, steps)
+        );
+    }
+}
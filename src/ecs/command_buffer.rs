@@ -0,0 +1,289 @@
+use super::super::renderer::Renderer;
+use super::components::character_controller::CharacterControllerBuilder;
+use super::components::drawable::DrawableBuilder;
+use super::components::health::HealthBuilder;
+use super::components::name::NameBuilder;
+use super::components::particle_emitter::ParticleEmitterBuilder;
+use super::components::rigid_body::RigidBodyBuilder;
+use super::components::text::TextBuilder;
+use super::components::transformation::TransformationBuilder;
+use super::world::World;
+use super::{Entity, EntityManager};
+
+type CustomCommand<'a> = Box<dyn FnOnce(&mut World) + 'a>;
+
+enum Command<'a> {
+    CreateEntity(Entity),
+    DestroyEntity(Entity),
+    SetEntityIsActive(Entity, bool),
+    AddTransformation(Entity, TransformationBuilder),
+    AddRigidBody(Entity, RigidBodyBuilder),
+    AddCharacterController(Entity, CharacterControllerBuilder),
+    AddHealth(Entity, HealthBuilder),
+    AddDrawable(Entity, DrawableBuilder<'a>),
+    AddText(Entity, TextBuilder<'a>),
+    AddParticleEmitter(Entity, ParticleEmitterBuilder),
+    AddName(Entity, NameBuilder),
+    RemoveTransformation(Entity),
+    RemoveRigidBody(Entity),
+    RemoveCharacterController(Entity),
+    RemoveHealth(Entity),
+    RemoveDrawable(Entity),
+    RemoveText(Entity),
+    RemoveParticleEmitter(Entity),
+    RemoveName(Entity),
+    Custom(CustomCommand<'a>),
+}
+
+// Records structural changes while systems or gameplay code are iterating, and replays them in
+// recording order when `apply` is called. Entities created through a buffer that is dropped
+// without being applied or cleared keep their slots as inactive entities without components.
+pub struct CommandBuffer<'a> {
+    commands: Vec<Command<'a>>,
+}
+
+impl<'a> CommandBuffer<'a> {
+    pub fn new() -> CommandBuffer<'a> {
+        CommandBuffer {
+            commands: Vec::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.commands.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+    }
+
+    // Drops every recorded command and releases the entities the buffer would have created.
+    pub fn clear(&mut self, entity_manager: &mut EntityManager) {
+        for command in self.commands.drain(..) {
+            if let Command::CreateEntity(e) = command {
+                entity_manager.release_reserved_entity(&e).ok();
+            }
+        }
+    }
+
+    // The returned handle is valid right away and can be used in later commands, but the entity
+    // only becomes active once the buffer is applied.
+    pub fn create_entity(&mut self, entity_manager: &EntityManager) -> Entity {
+        let entity = entity_manager.reserve_entity();
+        self.commands.push(Command::CreateEntity(entity));
+
+        entity
+    }
+
+    pub fn destroy_entity(&mut self, entity: &Entity) {
+        self.commands.push(Command::DestroyEntity(*entity));
+    }
+
+    pub fn set_entity_is_active(&mut self, entity: &Entity, active: bool) {
+        self.commands
+            .push(Command::SetEntityIsActive(*entity, active));
+    }
+
+    pub fn add_transformation(&mut self, entity: &Entity, transformation: TransformationBuilder) {
+        self.commands
+            .push(Command::AddTransformation(*entity, transformation));
+    }
+
+    pub fn add_rigid_body(&mut self, entity: &Entity, rigid_body: RigidBodyBuilder) {
+        self.commands
+            .push(Command::AddRigidBody(*entity, rigid_body));
+    }
+
+    pub fn add_character_controller(
+        &mut self,
+        entity: &Entity,
+        controller: CharacterControllerBuilder,
+    ) {
+        self.commands
+            .push(Command::AddCharacterController(*entity, controller));
+    }
+
+    pub fn add_health(&mut self, entity: &Entity, health: HealthBuilder) {
+        self.commands.push(Command::AddHealth(*entity, health));
+    }
+
+    pub fn add_drawable(&mut self, entity: &Entity, drawable: DrawableBuilder<'a>) {
+        self.commands.push(Command::AddDrawable(*entity, drawable));
+    }
+
+    pub fn add_text(&mut self, entity: &Entity, text: TextBuilder<'a>) {
+        self.commands.push(Command::AddText(*entity, text));
+    }
+
+    pub fn add_particle_emitter(&mut self, entity: &Entity, emitter: ParticleEmitterBuilder) {
+        self.commands
+            .push(Command::AddParticleEmitter(*entity, emitter));
+    }
+
+    pub fn add_name(&mut self, entity: &Entity, name: NameBuilder) {
+        self.commands.push(Command::AddName(*entity, name));
+    }
+
+    pub fn remove_transformation(&mut self, entity: &Entity) {
+        self.commands.push(Command::RemoveTransformation(*entity));
+    }
+
+    pub fn remove_rigid_body(&mut self, entity: &Entity) {
+        self.commands.push(Command::RemoveRigidBody(*entity));
+    }
+
+    pub fn remove_character_controller(&mut self, entity: &Entity) {
+        self.commands
+            .push(Command::RemoveCharacterController(*entity));
+    }
+
+    pub fn remove_health(&mut self, entity: &Entity) {
+        self.commands.push(Command::RemoveHealth(*entity));
+    }
+
+    pub fn remove_drawable(&mut self, entity: &Entity) {
+        self.commands.push(Command::RemoveDrawable(*entity));
+    }
+
+    pub fn remove_text(&mut self, entity: &Entity) {
+        self.commands.push(Command::RemoveText(*entity));
+    }
+
+    pub fn remove_particle_emitter(&mut self, entity: &Entity) {
+        self.commands.push(Command::RemoveParticleEmitter(*entity));
+    }
+
+    pub fn remove_name(&mut self, entity: &Entity) {
+        self.commands.push(Command::RemoveName(*entity));
+    }
+
+    // For custom component storages living outside the world.
+    pub fn custom<F>(&mut self, command: F)
+    where
+        F: FnOnce(&mut World) + 'a,
+    {
+        self.commands.push(Command::Custom(Box::new(command)));
+    }
+
+    // The sync point: replays every recorded command in order, then lets all systems drain the
+    // entities that were destroyed along the way. Drawables, texts and particle emitters need a
+    // renderer to be built and are dropped when `renderer` is `None`.
+    pub fn apply(&mut self, world: &mut World, mut renderer: Option<&mut Renderer<'a>>) {
+        for command in self.commands.drain(..) {
+            // Commands for entities that died before the buffer was applied are dropped.
+            let alive = match &command {
+                Command::CreateEntity(_) | Command::Custom(_) => true,
+                Command::DestroyEntity(e)
+                | Command::SetEntityIsActive(e, _)
+                | Command::AddTransformation(e, _)
+                | Command::AddRigidBody(e, _)
+                | Command::AddCharacterController(e, _)
+                | Command::AddHealth(e, _)
+                | Command::AddDrawable(e, _)
+                | Command::AddText(e, _)
+                | Command::AddParticleEmitter(e, _)
+                | Command::AddName(e, _)
+                | Command::RemoveTransformation(e)
+                | Command::RemoveRigidBody(e)
+                | Command::RemoveCharacterController(e)
+                | Command::RemoveHealth(e)
+                | Command::RemoveDrawable(e)
+                | Command::RemoveText(e)
+                | Command::RemoveParticleEmitter(e)
+                | Command::RemoveName(e) => world.entity_manager.entity_is_alive(e),
+            };
+
+            if !alive {
+                continue;
+            }
+
+            match command {
+                Command::CreateEntity(e) => {
+                    world.entity_manager.create_reserved_entity(&e).ok();
+                }
+                Command::DestroyEntity(e) => {
+                    world.entity_manager.destroy_entity(&e).ok();
+                }
+                Command::SetEntityIsActive(e, active) => {
+                    world.entity_manager.set_entity_is_active(&e, active).ok();
+                }
+                Command::AddTransformation(e, t) => world
+                    .transformation_system
+                    .add_transformation_to_entity(&e, t),
+                Command::AddRigidBody(e, rb) => world.rigid_body_system.add_rigid_body_to_entity(
+                    &e,
+                    rb,
+                    &world.transformation_system,
+                ),
+                Command::AddCharacterController(e, c) => world
+                    .character_controller_system
+                    .add_character_controller_to_entity(&e, c, &world.transformation_system),
+                Command::AddHealth(e, h) => world.health_system.add_health_to_entity(&e, h),
+                //TODO: Add error logging/printing here when there is no renderer!
+                Command::AddDrawable(e, d) => {
+                    if let Some(r) = renderer.as_deref_mut() {
+                        world.drawable_system.add_drawable_to_entity(
+                            &e,
+                            &world.transformation_system,
+                            r,
+                            d,
+                        );
+                    }
+                }
+                Command::AddText(e, t) => {
+                    if let Some(r) = renderer.as_deref_mut() {
+                        world.text_system.add_text_to_entity(
+                            &e,
+                            &world.transformation_system,
+                            r,
+                            t,
+                        );
+                    }
+                }
+                Command::AddParticleEmitter(e, pe) => {
+                    if let Some(r) = renderer.as_deref_mut() {
+                        world
+                            .particle_emitter_system
+                            .add_particle_emitter_to_entity(
+                                &e,
+                                pe,
+                                r,
+                                &mut world.entity_manager,
+                                &mut world.transformation_system,
+                                &mut world.rigid_body_system,
+                                &mut world.drawable_system,
+                            );
+                    }
+                }
+                Command::AddName(e, n) => {
+                    world.name_system.add_name_to_entity(&e, n);
+                }
+                Command::RemoveTransformation(e) => world
+                    .transformation_system
+                    .remove_transformation_from_entity(&e),
+                Command::RemoveRigidBody(e) => {
+                    world.rigid_body_system.remove_rigid_body_from_entity(&e)
+                }
+                Command::RemoveCharacterController(e) => world
+                    .character_controller_system
+                    .remove_character_controller_from_entity(&e),
+                Command::RemoveHealth(e) => world.health_system.remove_health_from_entity(&e),
+                Command::RemoveDrawable(e) => world.drawable_system.remove_drawable_from_entity(&e),
+                Command::RemoveText(e) => world.text_system.remove_text_from_entity(&e),
+                Command::RemoveParticleEmitter(e) => world
+                    .particle_emitter_system
+                    .remove_particle_emitter_from_entity(&e, &mut world.entity_manager),
+                Command::RemoveName(e) => world.name_system.remove_name_from_entity(&e),
+                Command::Custom(command) => command(world),
+            }
+        }
+
+        world.remove_destroyed_entities();
+    }
+}
+
+impl<'a> Default for CommandBuffer<'a> {
+    fn default() -> CommandBuffer<'a> {
+        CommandBuffer::new()
+    }
+}
@@ -0,0 +1,716 @@
+use super::super::super::utilities::quat_rotate_vector;
+use super::super::storage::{ComponentStorage, ComponentsMut};
+use super::super::{Entity, EntityManager};
+use super::rigid_body::shape::ColliderShape;
+use super::rigid_body::snapshot::PhysicsSnapshot;
+use super::rigid_body::{RayHit, RigidBodySystem, ALL_COLLISION_LAYERS};
+use super::transformation::TransformationSystem;
+use gamemath::{Quat, Vec3};
+
+// Gap kept between the shape and the surfaces it moves along, so the next cast doesn't start out
+// touching them.
+const SKIN: f32 = 0.01;
+// How many times a single move is cut short by a surface and continued along it.
+const MAX_SLIDES: usize = 4;
+// How far past a contact point the surface underneath is looked for, to tell the edge of a ledge
+// from a steep slope.
+const SURFACE_PROBE: f32 = 0.05;
+const EPSILON: f32 = 1.0e-6;
+
+pub struct CharacterControllerData {
+    shape: ColliderShape,
+    offset: Vec3<f32>,
+    step_height: f32,
+    // Surfaces whose normal points up at least this much are ground, the cosine of the max slope.
+    min_ground_normal_y: f32,
+    snap_distance: f32,
+    jump_speed: f32,
+    coyote_time: f32,
+    jump_buffer_time: f32,
+    gravity_scale: f32,
+    collision_mask: u32,
+    // Walking velocity set by the game, only the horizontal part is used.
+    movement: Vec3<f32>,
+    // Velocity from gravity and jumps, as well as what is left of a platform after leaving it.
+    velocity: Vec3<f32>,
+    ground: Option<(Entity, Vec3<f32>)>,
+    airborne_time: f32,
+    jump_timer: Option<f32>,
+    // The platform stood on, with the center of the shape relative to it and in world space.
+    platform: Option<(Entity, Vec3<f32>, Vec3<f32>)>,
+    platform_velocity: Vec3<f32>,
+}
+
+// Everything about a controller that changes while moving, together with the position of its
+// owner, see `CharacterControllerSystem::save_snapshot`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct CharacterState {
+    entity: Entity,
+    position: Vec3<f32>,
+    movement: Vec3<f32>,
+    velocity: Vec3<f32>,
+    ground: Option<(Entity, Vec3<f32>)>,
+    airborne_time: f32,
+    jump_timer: Option<f32>,
+    platform: Option<(Entity, Vec3<f32>, Vec3<f32>)>,
+    platform_velocity: Vec3<f32>,
+}
+
+pub struct CharacterControllerSystem {
+    data: ComponentStorage<CharacterControllerData>,
+}
+
+pub struct CharacterControllerBuilder {
+    shape: Option<ColliderShape>,
+    offset: Option<Vec3<f32>>,
+    step_height: Option<f32>,
+    max_slope: Option<f32>,
+    snap_distance: Option<f32>,
+    jump_speed: Option<f32>,
+    coyote_time: Option<f32>,
+    jump_buffer_time: Option<f32>,
+    gravity_scale: Option<f32>,
+    collision_mask: Option<u32>,
+}
+
+// What a controller moves through during an update.
+struct Surroundings<'a> {
+    rigid_body_system: &'a RigidBodySystem,
+    transformation_system: &'a TransformationSystem,
+    shape: (ColliderShape, Quat),
+    filter: (Entity, u32),
+}
+
+impl CharacterControllerData {
+    pub fn get_shape(&self) -> ColliderShape {
+        self.shape
+    }
+
+    pub fn is_grounded(&self) -> bool {
+        self.ground.is_some()
+    }
+
+    // Normal of the walkable surface stood on.
+    pub fn get_ground_normal(&self) -> Option<Vec3<f32>> {
+        self.ground.map(|g| g.1)
+    }
+
+    pub fn get_ground_entity(&self) -> Option<Entity> {
+        self.ground.map(|g| g.0)
+    }
+
+    // Velocity besides walking, from gravity, jumps and platforms.
+    pub fn get_velocity(&self) -> Vec3<f32> {
+        self.velocity
+    }
+
+    pub fn get_movement(&self) -> Vec3<f32> {
+        self.movement
+    }
+
+    fn update(
+        &mut self,
+        dt: f32,
+        gravity: Vec3<f32>,
+        position: Vec3<f32>,
+        surroundings: &Surroundings,
+    ) -> Vec3<f32> {
+        let zero = Vec3::new(0.0, 0.0, 0.0);
+        let mut center = position + self.offset;
+
+        // Platforms carry whoever stands on them, turning included.
+        self.platform_velocity = zero;
+
+        if let Some((platform, local, world)) = self.platform {
+            if let Some((position, rotation)) =
+                surroundings.transformation_system.get_world_pose(&platform)
+            {
+                let carried = position + quat_rotate_vector(&rotation.normalized(), local) - world;
+                center += carried;
+
+                if dt > 0.0 {
+                    self.platform_velocity = carried * (1.0 / dt);
+                }
+            }
+        }
+
+        center = surroundings.depenetrate(center);
+
+        let was_grounded = self.ground.is_some();
+
+        if self.jump_timer.is_some() && (was_grounded || self.airborne_time <= self.coyote_time) {
+            self.velocity.y = self.jump_speed;
+            self.jump_timer = None;
+            // No second jump off the same ledge.
+            self.airborne_time = f32::MAX;
+            self.ground = None;
+        }
+
+        if self.ground.is_some() {
+            self.velocity = zero;
+        } else {
+            self.velocity += gravity * (self.gravity_scale * dt);
+        }
+
+        let walk = Vec3::new(
+            self.movement.x + self.velocity.x,
+            0.0,
+            self.movement.z + self.velocity.z,
+        ) * dt;
+        center = self.slide(center, walk, self.ground.is_some(), surroundings);
+
+        let fall = Vec3::new(0.0, self.velocity.y * dt, 0.0);
+        let fallen = self.slide(center, fall, false, surroundings);
+
+        // Bumping into a ceiling ends the jump.
+        if self.velocity.y > 0.0 && fallen.y - center.y < fall.y - EPSILON {
+            self.velocity.y = 0.0;
+        }
+
+        center = fallen;
+        self.ground = None;
+
+        if self.velocity.y <= 0.0 {
+            let distance = if was_grounded {
+                self.snap_distance.max(SKIN * 2.0)
+            } else {
+                SKIN * 2.0
+            };
+
+            if let Some(hit) = surroundings.cast(center, Vec3::new(0.0, -distance, 0.0)) {
+                if let Some(normal) = self.get_walkable_normal(&hit, center, surroundings) {
+                    center.y -= (hit.get_distance() - SKIN).max(0.0);
+                    self.ground = Some((hit.get_entity(), normal));
+                    self.velocity = zero;
+                }
+            }
+        }
+
+        match self.ground {
+            Some((platform, _)) => {
+                self.airborne_time = 0.0;
+                self.platform = surroundings
+                    .transformation_system
+                    .get_world_pose(&platform)
+                    .map(|(position, rotation)| {
+                        let rotation = rotation.normalized();
+                        let d = center - position;
+                        let local = Vec3::new(
+                            d.dot(quat_rotate_vector(&rotation, Vec3::new(1.0, 0.0, 0.0))),
+                            d.dot(quat_rotate_vector(&rotation, Vec3::new(0.0, 1.0, 0.0))),
+                            d.dot(quat_rotate_vector(&rotation, Vec3::new(0.0, 0.0, 1.0))),
+                        );
+
+                        (platform, local, center)
+                    });
+            }
+            None => {
+                // Leaving a platform keeps its momentum.
+                if was_grounded {
+                    self.velocity += self.platform_velocity;
+                }
+
+                self.airborne_time += dt;
+                self.platform = None;
+            }
+        }
+
+        if let Some(timer) = self.jump_timer {
+            self.jump_timer = if timer > dt { Some(timer - dt) } else { None };
+        }
+
+        center - self.offset
+    }
+
+    // Moves the center along `motion`, continuing along the surfaces in the way. Walking into
+    // something too steep climbs it if it's low enough, or slides along it without going up.
+    fn slide(
+        &self,
+        mut center: Vec3<f32>,
+        mut motion: Vec3<f32>,
+        walking: bool,
+        surroundings: &Surroundings,
+    ) -> Vec3<f32> {
+        for _ in 0..MAX_SLIDES {
+            let length = motion.length();
+
+            if length < EPSILON {
+                break;
+            }
+
+            let hit = match surroundings.cast(center, motion) {
+                Some(hit) => hit,
+                None => {
+                    center += motion;
+                    break;
+                }
+            };
+
+            let travel = (hit.get_distance() - SKIN).max(0.0);
+            center += motion * (travel / length);
+            motion *= (length - travel) / length;
+
+            let mut normal = hit.get_normal();
+
+            if walking && normal.y < self.min_ground_normal_y {
+                if let Some(stepped) = self.step_up(center, motion, &hit, surroundings) {
+                    return stepped;
+                }
+
+                let flat = Vec3::new(normal.x, 0.0, normal.z);
+
+                if flat.length_squared() > EPSILON {
+                    normal = flat.normalized();
+                }
+            }
+
+            let into = motion.dot(normal);
+
+            if into < 0.0 {
+                motion -= normal * into;
+            }
+        }
+
+        center
+    }
+
+    // Lifts the shape onto the walkable top of an obstacle no higher than the step height, then
+    // moves on with the rest of `motion`.
+    fn step_up(
+        &self,
+        center: Vec3<f32>,
+        motion: Vec3<f32>,
+        hit: &RayHit,
+        surroundings: &Surroundings,
+    ) -> Option<Vec3<f32>> {
+        let flat = Vec3::new(hit.get_normal().x, 0.0, hit.get_normal().z);
+
+        if self.step_height <= 0.0 || flat.length_squared() < EPSILON {
+            return None;
+        }
+
+        let foot = center.y - self.shape.get_extents().y;
+        let point = hit.get_point() - flat.normalized() * SURFACE_PROBE;
+        let origin = Vec3::new(point.x, foot + self.step_height + SKIN, point.z);
+        let top =
+            surroundings.ray_cast(origin, Vec3::new(0.0, -1.0, 0.0), self.step_height + SKIN)?;
+
+        // A ray starting inside the obstacle means it's higher than a step.
+        if top.get_distance() <= 0.0 || top.get_normal().y < self.min_ground_normal_y {
+            return None;
+        }
+
+        let rise = top.get_point().y - foot + SKIN;
+
+        if rise <= SKIN {
+            return None;
+        }
+
+        let up = Vec3::new(0.0, rise, 0.0);
+
+        if surroundings
+            .cast(center, up * ((rise + SKIN) / rise))
+            .is_some()
+        {
+            return None;
+        }
+
+        let mut raised = center + up;
+        let length = motion.length();
+
+        match surroundings.cast(raised, motion) {
+            Some(hit) if hit.get_distance() <= SKIN => return None,
+            Some(hit) => raised += motion * ((hit.get_distance() - SKIN) / length),
+            None => raised += motion,
+        }
+
+        let landing = surroundings.cast(raised, Vec3::new(0.0, -(rise + SKIN), 0.0))?;
+        raised.y -= (landing.get_distance() - SKIN).max(0.0);
+
+        Some(raised)
+    }
+
+    // Edges of ledges are stood on like the surface behind them, which catches the rounded
+    // bottom of a shape hanging over the edge.
+    fn get_walkable_normal(
+        &self,
+        hit: &RayHit,
+        center: Vec3<f32>,
+        surroundings: &Surroundings,
+    ) -> Option<Vec3<f32>> {
+        if hit.get_normal().y >= self.min_ground_normal_y {
+            return Some(hit.get_normal());
+        }
+
+        let inward = Vec3::new(
+            center.x - hit.get_point().x,
+            0.0,
+            center.z - hit.get_point().z,
+        );
+
+        if inward.length_squared() < EPSILON {
+            return None;
+        }
+
+        let origin = hit.get_point() - inward.normalized() * SURFACE_PROBE
+            + Vec3::new(0.0, SURFACE_PROBE, 0.0);
+        let surface =
+            surroundings.ray_cast(origin, Vec3::new(0.0, -1.0, 0.0), SURFACE_PROBE * 2.0)?;
+
+        if surface.get_normal().y >= self.min_ground_normal_y {
+            Some(surface.get_normal())
+        } else {
+            None
+        }
+    }
+}
+
+impl CharacterControllerBuilder {
+    pub fn new() -> CharacterControllerBuilder {
+        CharacterControllerBuilder {
+            shape: None,
+            offset: None,
+            step_height: None,
+            max_slope: None,
+            snap_distance: None,
+            jump_speed: None,
+            coyote_time: None,
+            jump_buffer_time: None,
+            gravity_scale: None,
+            collision_mask: None,
+        }
+    }
+
+    // The capsule stands upright, it never turns with the entity.
+    pub fn with_capsule(mut self, radius: f32, half_height: f32) -> CharacterControllerBuilder {
+        self.shape = Some(ColliderShape::Capsule {
+            radius,
+            half_height,
+        });
+        self
+    }
+
+    pub fn with_sphere(mut self, radius: f32) -> CharacterControllerBuilder {
+        self.shape = Some(ColliderShape::Sphere(radius));
+        self
+    }
+
+    // Offset of the center of the shape from the position of the entity.
+    pub fn with_offset(mut self, offset: Vec3<f32>) -> CharacterControllerBuilder {
+        self.offset = Some(offset);
+        self
+    }
+
+    // Obstacles up to this height are walked onto, like stairs.
+    pub fn with_step_height(mut self, height: f32) -> CharacterControllerBuilder {
+        self.step_height = Some(height);
+        self
+    }
+
+    // The steepest slope still walked on, in radians.
+    pub fn with_max_slope(mut self, angle: f32) -> CharacterControllerBuilder {
+        self.max_slope = Some(angle);
+        self
+    }
+
+    // How far the controller is pulled down to stay on the ground, like when walking down stairs.
+    pub fn with_snap_distance(mut self, distance: f32) -> CharacterControllerBuilder {
+        self.snap_distance = Some(distance);
+        self
+    }
+
+    pub fn with_jump_speed(mut self, speed: f32) -> CharacterControllerBuilder {
+        self.jump_speed = Some(speed);
+        self
+    }
+
+    // How long after walking off a ledge a jump is still allowed.
+    pub fn with_coyote_time(mut self, time: f32) -> CharacterControllerBuilder {
+        self.coyote_time = Some(time);
+        self
+    }
+
+    // How long a jump pressed in the air is remembered for, it happens on landing.
+    pub fn with_jump_buffer_time(mut self, time: f32) -> CharacterControllerBuilder {
+        self.jump_buffer_time = Some(time);
+        self
+    }
+
+    pub fn with_gravity_scale(mut self, scale: f32) -> CharacterControllerBuilder {
+        self.gravity_scale = Some(scale);
+        self
+    }
+
+    // Only bodies on a layer in `mask` block the controller.
+    pub fn with_collision_mask(mut self, mask: u32) -> CharacterControllerBuilder {
+        self.collision_mask = Some(mask);
+        self
+    }
+
+    fn build(self) -> CharacterControllerData {
+        CharacterControllerData {
+            shape: match self.shape {
+                Some(s) => s,
+                None => ColliderShape::Capsule {
+                    radius: 0.4,
+                    half_height: 0.5,
+                },
+            },
+            offset: match self.offset {
+                Some(o) => o,
+                None => Vec3::new(0.0, 0.0, 0.0),
+            },
+            step_height: match self.step_height {
+                Some(h) => h.max(0.0),
+                None => 0.3,
+            },
+            min_ground_normal_y: match self.max_slope {
+                Some(a) => a.clamp(0.0, std::f32::consts::FRAC_PI_2).cos(),
+                None => std::f32::consts::FRAC_PI_4.cos(),
+            },
+            snap_distance: match self.snap_distance {
+                Some(d) => d.max(0.0),
+                None => 0.3,
+            },
+            jump_speed: self.jump_speed.unwrap_or(5.0),
+            coyote_time: self.coyote_time.unwrap_or(0.1),
+            jump_buffer_time: self.jump_buffer_time.unwrap_or(0.1),
+            gravity_scale: self.gravity_scale.unwrap_or(1.0),
+            collision_mask: match self.collision_mask {
+                Some(m) => m,
+                None => ALL_COLLISION_LAYERS,
+            },
+            movement: Vec3::new(0.0, 0.0, 0.0),
+            velocity: Vec3::new(0.0, 0.0, 0.0),
+            ground: None,
+            airborne_time: f32::MAX,
+            jump_timer: None,
+            platform: None,
+            platform_velocity: Vec3::new(0.0, 0.0, 0.0),
+        }
+    }
+}
+
+impl Surroundings<'_> {
+    fn cast(&self, center: Vec3<f32>, motion: Vec3<f32>) -> Option<RayHit> {
+        self.rigid_body_system.shape_cast(
+            self.shape,
+            (center, motion),
+            motion.length(),
+            self.transformation_system,
+            self.filter,
+        )
+    }
+
+    fn ray_cast(&self, origin: Vec3<f32>, direction: Vec3<f32>, distance: f32) -> Option<RayHit> {
+        self.rigid_body_system.ray_cast(
+            (origin, direction),
+            distance,
+            self.transformation_system,
+            self.filter.0,
+            self.filter.1,
+        )
+    }
+
+    // Pushes the shape out of whatever it overlaps, like a platform that moved into it.
+    fn depenetrate(&self, mut center: Vec3<f32>) -> Vec3<f32> {
+        for _ in 0..MAX_SLIDES {
+            let contacts = self.rigid_body_system.find_shape_contacts(
+                self.shape,
+                center,
+                self.transformation_system,
+                self.filter,
+            );
+
+            if contacts.is_empty() {
+                break;
+            }
+
+            for (_, manifold) in contacts.iter() {
+                center -= manifold.get_normal() * (manifold.get_penetration() + SKIN);
+            }
+        }
+
+        center
+    }
+}
+
+impl CharacterControllerSystem {
+    pub fn new() -> CharacterControllerSystem {
+        CharacterControllerSystem {
+            data: ComponentStorage::new(),
+        }
+    }
+
+    pub fn add_character_controller_to_entity(
+        &mut self,
+        entity: &Entity,
+        controller_builder: CharacterControllerBuilder,
+        transformation_system: &TransformationSystem,
+    ) {
+        if self.data.contains(entity) {
+            //TODO: Add error logging/printing here!
+        } else if transformation_system.entity_has_transformation(entity) {
+            self.data.insert(entity, controller_builder.build());
+        } else {
+            //TODO: Add error logging/printing here!
+        }
+    }
+
+    pub fn remove_character_controller_from_entity(&mut self, entity: &Entity) {
+        self.data.remove(entity);
+    }
+
+    pub fn remove_destroyed_entities(&mut self, entity_manager: &EntityManager) {
+        self.data.remove_destroyed(entity_manager);
+    }
+
+    pub fn entity_has_character_controller(&self, entity: &Entity) -> bool {
+        self.data.contains(entity)
+    }
+
+    pub fn components(&self) -> &ComponentStorage<CharacterControllerData> {
+        &self.data
+    }
+
+    pub fn components_mut(&mut self) -> ComponentsMut<'_, CharacterControllerData> {
+        ComponentsMut::new(&mut self.data)
+    }
+
+    // Walking velocity in world space, kept until it is set again.
+    pub fn set_movement(&mut self, entity: &Entity, movement: Vec3<f32>) {
+        if let Some(controller) = self.data.get_mut(entity) {
+            controller.movement = Vec3::new(movement.x, 0.0, movement.z);
+        }
+    }
+
+    // Jumps during the next update the controller is on the ground, or has just left it.
+    pub fn jump(&mut self, entity: &Entity) {
+        if let Some(controller) = self.data.get_mut(entity) {
+            controller.jump_timer = Some(controller.jump_buffer_time.max(0.0));
+        }
+    }
+
+    // Replaces the velocity besides walking, like for a launch pad. An upwards velocity leaves
+    // the ground.
+    pub fn set_velocity(&mut self, entity: &Entity, velocity: Vec3<f32>) {
+        if let Some(controller) = self.data.get_mut(entity) {
+            controller.velocity = velocity;
+
+            if velocity.y > 0.0 {
+                controller.ground = None;
+                controller.airborne_time = f32::MAX;
+            }
+        }
+    }
+
+    pub fn is_grounded(&self, entity: &Entity) -> bool {
+        self.data
+            .get(entity)
+            .is_some_and(|controller| controller.is_grounded())
+    }
+
+    pub fn get_ground_normal(&self, entity: &Entity) -> Option<Vec3<f32>> {
+        self.data.get(entity)?.get_ground_normal()
+    }
+
+    pub fn get_ground_entity(&self, entity: &Entity) -> Option<Entity> {
+        self.data.get(entity)?.get_ground_entity()
+    }
+
+    // Stores the state of every controller and the positions of their owners in `snapshot`, next
+    // to what `RigidBodySystem::save_snapshot` stores there. Only the characters saved before are
+    // replaced, so the two can be saved in any order.
+    pub fn save_snapshot(
+        &self,
+        transformation_system: &TransformationSystem,
+        snapshot: &mut PhysicsSnapshot,
+    ) {
+        snapshot.characters.clear();
+
+        for (entity, controller) in self.data.iter() {
+            let position = match transformation_system.get_world_position(entity) {
+                Some(p) => p,
+                None => continue,
+            };
+
+            snapshot.characters.push(CharacterState {
+                entity: *entity,
+                position,
+                movement: controller.movement,
+                velocity: controller.velocity,
+                ground: controller.ground,
+                airborne_time: controller.airborne_time,
+                jump_timer: controller.jump_timer,
+                platform: controller.platform,
+                platform_velocity: controller.platform_velocity,
+            });
+        }
+    }
+
+    // Puts the controllers back into the state saved in `snapshot`. Controllers added since keep
+    // their state and the owners snap to their positions rather than being interpolated there.
+    pub fn restore_snapshot(
+        &mut self,
+        snapshot: &PhysicsSnapshot,
+        transformation_system: &mut TransformationSystem,
+    ) {
+        for state in snapshot.characters.iter() {
+            let controller = match self.data.get_mut(&state.entity) {
+                Some(c) => c,
+                None => continue,
+            };
+
+            controller.movement = state.movement;
+            controller.velocity = state.velocity;
+            controller.ground = state.ground;
+            controller.airborne_time = state.airborne_time;
+            controller.jump_timer = state.jump_timer;
+            controller.platform = state.platform;
+            controller.platform_velocity = state.platform_velocity;
+
+            transformation_system.set_world_position(&state.entity, state.position);
+            transformation_system.reset_interpolation(&state.entity);
+        }
+    }
+
+    // Moves every controller through the rigid bodies, which are all treated as immovable.
+    // Entities with a rigid body of their own are not blocked by it.
+    pub fn update(
+        &mut self,
+        dt: f32,
+        transformation_system: &mut TransformationSystem,
+        rigid_body_system: &RigidBodySystem,
+    ) {
+        let gravity = rigid_body_system.get_config().gravity;
+
+        for (owner, controller) in self.data.iter_mut() {
+            let position = match transformation_system.get_world_position(owner) {
+                Some(p) => p,
+                None => continue,
+            };
+
+            let surroundings = Surroundings {
+                rigid_body_system,
+                transformation_system,
+                shape: (controller.shape, Quat::identity()),
+                filter: (*owner, controller.collision_mask),
+            };
+            let position = controller.update(dt, gravity, position, &surroundings);
+
+            transformation_system.set_world_position(owner, position);
+        }
+    }
+}
+
+impl Default for CharacterControllerBuilder {
+    fn default() -> CharacterControllerBuilder {
+        CharacterControllerBuilder::new()
+    }
+}
+
+impl Default for CharacterControllerSystem {
+    fn default() -> CharacterControllerSystem {
+        CharacterControllerSystem::new()
+    }
+}
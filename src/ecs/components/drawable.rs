@@ -1,26 +1,25 @@
 use super::super::super::renderer::model::ModelInfo;
-use super::super::super::renderer::{RenderJob, Renderer};
+use super::super::super::renderer::{ModelRenderJob, Renderer};
+use super::super::query::query;
+use super::super::storage::{ComponentStorage, ComponentsMut};
 use super::super::{Entity, EntityManager};
 use super::transformation::TransformationSystem;
-use fnv::FnvHashMap;
 use gamemath::Vec2;
 use gamemath::Vec4;
 use gl::types::GLuint;
 
-struct DrawableData {
-    owner: Entity,
+pub struct DrawableData {
     shader: GLuint,
     model: ModelInfo,
     texture_set: usize,
-    uv_scale: Vec2<f32>,
-    uv_offset: Vec2<f32>,
-    tint: Vec4<f32>,
-    emissive_tint: Vec4<f32>,
+    pub uv_scale: Vec2<f32>,
+    pub uv_offset: Vec2<f32>,
+    pub tint: Vec4<f32>,
+    pub emissive_tint: Vec4<f32>,
 }
 
 pub struct DrawableSystem {
-    map: FnvHashMap<Entity, usize>,
-    data: Vec<DrawableData>,
+    data: ComponentStorage<DrawableData>,
 }
 
 pub struct DrawableBuilder<'a> {
@@ -81,9 +80,8 @@ impl<'a> DrawableBuilder<'a> {
         self
     }
 
-    fn build(self, owner: Entity, renderer: &mut Renderer<'a>) -> DrawableData {
+    fn build(self, renderer: &mut Renderer<'a>) -> DrawableData {
         DrawableData {
-            owner,
             shader: match self.shader {
                 Some(s) => renderer.get_shader(s).unwrap(),
                 None => renderer.get_shader("sprite").unwrap(),
@@ -119,8 +117,7 @@ impl<'a> DrawableBuilder<'a> {
 impl<'a> DrawableSystem {
     pub fn new() -> DrawableSystem {
         DrawableSystem {
-            map: FnvHashMap::with_capacity_and_hasher(1, Default::default()),
-            data: Vec::new(),
+            data: ComponentStorage::new(),
         }
     }
 
@@ -131,59 +128,38 @@ impl<'a> DrawableSystem {
         renderer: &mut Renderer<'a>,
         initial_data: DrawableBuilder<'a>,
     ) {
-        match self.map.contains_key(entity) {
-            true => (), //TODO: Add error logging/printing here!
-            false => {
-                match transformation_system.entity_has_transformation(entity) {
-                    true => {
-                        self.data.push(initial_data.build(*entity, renderer));
-                        self.map.insert(entity.clone(), self.data.len() - 1);
-                    }
-                    false => (), //TODO: Add error logging/printing here!
-                }
-            }
+        if self.data.contains(entity) {
+            //TODO: Add error logging/printing here!
+        } else if transformation_system.entity_has_transformation(entity) {
+            self.data.insert(entity, initial_data.build(renderer));
+        } else {
+            //TODO: Add error logging/printing here!
         }
     }
 
     pub fn remove_drawable_from_entity(&mut self, entity: &Entity) {
-        let mut swapped = (false, 0);
-        let mut removed = false;
-
-        if *entity != Entity::null() {
-            match self.map.get(entity) {
-                Some(index) => {
-                    self.data.swap_remove(*index);
-                    removed = true;
-
-                    if self.data.is_empty() == false {
-                        swapped = (true, *index);
-                    }
-                }
-                None => (),
-            }
-        }
-
-        if removed == true {
-            self.map.remove(entity);
-        }
+        self.data.remove(entity);
+    }
 
-        if swapped.0 == true && swapped.1 != self.data.len() {
-            *self.map.get_mut(&self.data[swapped.1].owner).unwrap() = swapped.1;
-        }
+    pub fn remove_destroyed_entities(&mut self, entity_manager: &EntityManager) {
+        self.data.remove_destroyed(entity_manager);
     }
 
     pub fn entity_has_drawable(&self, entity: &Entity) -> bool {
-        self.map.contains_key(entity)
+        self.data.contains(entity)
+    }
+
+    pub fn components(&self) -> &ComponentStorage<DrawableData> {
+        &self.data
+    }
+
+    pub fn components_mut(&mut self) -> ComponentsMut<'_, DrawableData> {
+        ComponentsMut::new(&mut self.data)
     }
 
     pub fn set_entity_tint_color(&mut self, entity: &Entity, color: Vec4<f32>) {
-        if *entity != Entity::null() {
-            match self.map.get(entity) {
-                Some(index) => {
-                    self.data[*index].tint = color;
-                }
-                None => (),
-            }
+        if let Some(drawable) = self.data.get_mut(entity) {
+            drawable.tint = color;
         }
     }
 
@@ -191,28 +167,25 @@ impl<'a> DrawableSystem {
         &self,
         entity_manager: &EntityManager,
         transformation_system: &TransformationSystem,
+        interpolation_factor: f32,
         renderer: &mut Renderer,
     ) {
-        for drawable in self.data.iter() {
-            if entity_manager.entity_is_active(&drawable.owner) == true {
-                let t = transformation_system
-                    .get_transformation_data(&drawable.owner)
-                    .unwrap();
-
-                renderer.add_render_job(RenderJob {
-                    model: drawable.model,
-                    shader: drawable.shader,
-                    textures: drawable.texture_set,
-                    scale: t.scale,
-                    uv_size: drawable.uv_scale,
-                    uv_offset: drawable.uv_offset,
-                    position: t.position,
-                    pivot: t.pivot,
-                    rotation: t.rotation,
-                    tint: drawable.tint,
-                    emissive_tint: drawable.emissive_tint,
-                });
-            }
+        let drawables = query(
+            entity_manager,
+            (&self.data, transformation_system.components()),
+        );
+
+        for (_, (drawable, t)) in drawables {
+            renderer.add_model_render_job(ModelRenderJob {
+                model: drawable.model,
+                shader: drawable.shader,
+                textures: drawable.texture_set,
+                model_matrix: t.get_interpolated_model_matrix(interpolation_factor),
+                uv_size: drawable.uv_scale,
+                uv_offset: drawable.uv_offset,
+                tint: drawable.tint,
+                emissive_tint: drawable.emissive_tint,
+            });
         }
     }
 }
@@ -1,15 +1,15 @@
+use super::super::storage::{ComponentStorage, ComponentsMut};
 use super::super::{Entity, EntityManager};
-use fnv::FnvHashMap;
+use super::rigid_body::event::CollisionEventKind;
+use super::rigid_body::RigidBodySystem;
 use std::f32;
 
-struct HealthData {
-    owner: Entity,
-    hitpoints: (f32, f32),
+pub struct HealthData {
+    pub hitpoints: (f32, f32),
 }
 
 pub struct HealthSystem {
-    map: FnvHashMap<Entity, usize>,
-    data: Vec<HealthData>,
+    data: ComponentStorage<HealthData>,
 }
 
 pub struct HealthBuilder {
@@ -26,9 +26,8 @@ impl HealthBuilder {
         self
     }
 
-    fn build(self, owner: Entity) -> HealthData {
+    fn build(self) -> HealthData {
         HealthData {
-            owner,
             hitpoints: match self.hitpoints {
                 Some(hp) => hp,
                 None => (1.0, 1.0),
@@ -40,94 +39,96 @@ impl HealthBuilder {
 impl HealthSystem {
     pub fn new() -> HealthSystem {
         HealthSystem {
-            map: FnvHashMap::with_capacity_and_hasher(1, Default::default()),
-            data: Vec::new(),
+            data: ComponentStorage::new(),
         }
     }
 
     pub fn add_health_to_entity(&mut self, entity: &Entity, initial_health: HealthBuilder) {
-        if *entity != Entity::null() {
-            match self.entity_has_health(entity) {
-                true => (), //TODO: Add error logging/printing here!
-                false => {
-                    self.data.push(initial_health.build(*entity));
-                    self.map.insert(entity.clone(), self.data.len() - 1);
-                }
-            }
-        }
+        //TODO: Add error logging/printing here if the entity already has health!
+        self.data.insert(entity, initial_health.build());
     }
 
     pub fn remove_health_from_entity(&mut self, entity: &Entity) {
-        let mut swapped = (false, 0);
-        let mut removed = false;
-
-        if *entity != Entity::null() {
-            match self.map.get(entity) {
-                Some(index) => {
-                    self.data.swap_remove(*index);
-                    removed = true;
-
-                    if self.data.is_empty() == false {
-                        swapped = (true, *index);
-                    }
-                }
-                None => (),
-            }
-        }
+        self.data.remove(entity);
+    }
 
-        if removed == true {
-            self.map.remove(entity);
-        }
+    pub fn components(&self) -> &ComponentStorage<HealthData> {
+        &self.data
+    }
 
-        if swapped.0 == true && swapped.1 != self.data.len() {
-            *self.map.get_mut(&self.data[swapped.1].owner).unwrap() = swapped.1;
-        }
+    pub fn components_mut(&mut self) -> ComponentsMut<'_, HealthData> {
+        ComponentsMut::new(&mut self.data)
+    }
+
+    pub fn get_hitpoints(&self, entity: &Entity) -> Option<(f32, f32)> {
+        self.data.get(entity).map(|health| health.hitpoints)
     }
 
     pub fn heal(&mut self, entity: &Entity, amount: f32) {
-        if *entity != Entity::null() {
-            match self.map.get(entity) {
-                Some(index) => {
-                    self.data[*index].hitpoints.0 += amount;
-                }
-                None => (),
-            }
+        if let Some(health) = self.data.get_mut(entity) {
+            health.hitpoints.0 += amount;
         }
     }
 
     pub fn harm(&mut self, entity: &Entity, amount: f32) {
-        if *entity != Entity::null() {
-            match self.map.get(entity) {
-                Some(index) => {
-                    self.data[*index].hitpoints.0 -= amount;
-                }
-                None => (),
-            }
+        if let Some(health) = self.data.get_mut(entity) {
+            health.hitpoints.0 -= amount;
         }
     }
 
     pub fn kill_entity(&mut self, entity: &Entity) {
-        if *entity != Entity::null() {
-            match self.map.get(entity) {
-                Some(index) => {
-                    self.data[*index].hitpoints.0 = f32::MIN;
-                }
-                None => (),
-            }
+        if let Some(health) = self.data.get_mut(entity) {
+            health.hitpoints.0 = f32::MIN;
         }
     }
 
     pub fn update(&mut self, entity_manager: &mut EntityManager) {
-        for health in self.data.iter_mut() {
+        for (owner, health) in self.data.iter_mut() {
             if health.hitpoints.0 > health.hitpoints.1 {
                 health.hitpoints.0 = health.hitpoints.1;
             } else if health.hitpoints.0 <= 0.0 {
-                entity_manager.destroy_entity(&health.owner);
+                // Fails harmlessly if the entity was already destroyed earlier this frame.
+                entity_manager.destroy_entity(owner).ok();
+            }
+        }
+    }
+
+    // Harms entities touching bodies that deal damage during the last physics update, and kills the
+    // ones whose bodies die on collision. Trigger contacts count as well.
+    pub fn apply_collision_damage(&mut self, rigid_body_system: &RigidBodySystem) {
+        let rigid_bodies = rigid_body_system.components();
+
+        for event in rigid_body_system.get_collision_events() {
+            if event.get_kind() == CollisionEventKind::End {
+                continue;
+            }
+
+            let entities = event.get_entities();
+
+            for (victim, attacker) in [(entities.0, entities.1), (entities.1, entities.0)] {
+                if !self.entity_has_health(&victim) {
+                    continue;
+                }
+
+                if let Some(body) = rigid_bodies.get(&attacker) {
+                    self.harm(&victim, body.get_damage());
+                }
+
+                if rigid_bodies
+                    .get(&victim)
+                    .is_some_and(|body| body.dies_on_collision())
+                {
+                    self.kill_entity(&victim);
+                }
             }
         }
     }
 
+    pub fn remove_destroyed_entities(&mut self, entity_manager: &EntityManager) {
+        self.data.remove_destroyed(entity_manager);
+    }
+
     pub fn entity_has_health(&self, entity: &Entity) -> bool {
-        self.map.contains_key(entity)
+        self.data.contains(entity)
     }
 }
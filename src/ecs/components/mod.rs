@@ -1,5 +1,7 @@
+pub mod character_controller;
 pub mod drawable;
 pub mod health;
+pub mod name;
 pub mod particle_emitter;
 pub mod rigid_body;
 pub mod text;
@@ -0,0 +1,228 @@
+use super::super::storage::ComponentStorage;
+use super::super::{Entity, EntityManager};
+use fnv::FnvHashMap;
+
+pub struct NameData {
+    name: Option<String>,
+    tags: Vec<String>,
+}
+
+impl NameData {
+    pub fn get_name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    pub fn get_tags(&self) -> &[String] {
+        &self.tags
+    }
+}
+
+// Names are unique per system, tags are shared. Entities carrying a tag are kept in tagging order,
+// so iterating them is deterministic.
+pub struct NameSystem {
+    data: ComponentStorage<NameData>,
+    names: FnvHashMap<String, Entity>,
+    tagged: FnvHashMap<String, Vec<Entity>>,
+}
+
+pub struct NameBuilder {
+    name: Option<String>,
+    tags: Vec<String>,
+}
+
+impl NameBuilder {
+    pub fn new() -> NameBuilder {
+        NameBuilder {
+            name: None,
+            tags: Vec::new(),
+        }
+    }
+
+    pub fn with_name(mut self, name: &str) -> NameBuilder {
+        self.name = Some(name.to_string());
+        self
+    }
+
+    pub fn with_tag(mut self, tag: &str) -> NameBuilder {
+        self.tags.push(tag.to_string());
+        self
+    }
+}
+
+impl NameSystem {
+    pub fn new() -> NameSystem {
+        NameSystem {
+            data: ComponentStorage::new(),
+            names: FnvHashMap::default(),
+            tagged: FnvHashMap::default(),
+        }
+    }
+
+    // Returns false if the name is already taken by another entity, the tags are added either way.
+    pub fn add_name_to_entity(&mut self, entity: &Entity, init_data: NameBuilder) -> bool {
+        let mut result = true;
+
+        if let Some(name) = init_data.name {
+            result = self.set_name(entity, &name);
+        }
+
+        for tag in init_data.tags.iter() {
+            self.add_tag(entity, tag);
+        }
+
+        result
+    }
+
+    pub fn remove_name_from_entity(&mut self, entity: &Entity) {
+        if let Some(data) = self.data.remove(entity) {
+            if let Some(name) = data.name {
+                self.names.remove(&name);
+            }
+
+            for tag in data.tags.iter() {
+                self.untag(entity, tag);
+            }
+        }
+    }
+
+    pub fn remove_destroyed_entities(&mut self, entity_manager: &EntityManager) {
+        for entity in entity_manager.get_destroyed_entities() {
+            self.remove_name_from_entity(entity);
+        }
+    }
+
+    pub fn entity_has_name(&self, entity: &Entity) -> bool {
+        self.get_name(entity).is_some()
+    }
+
+    pub fn components(&self) -> &ComponentStorage<NameData> {
+        &self.data
+    }
+
+    pub fn get_name(&self, entity: &Entity) -> Option<&str> {
+        self.data.get(entity).and_then(|data| data.get_name())
+    }
+
+    // Returns false without renaming if another entity already goes by `name`.
+    pub fn set_name(&mut self, entity: &Entity, name: &str) -> bool {
+        if *entity == Entity::null() {
+            return false;
+        }
+
+        match self.names.get(name) {
+            Some(owner) if owner != entity => return false,
+            Some(_) => return true,
+            None => (),
+        }
+
+        self.clear_name(entity);
+        self.names.insert(name.to_string(), *entity);
+        self.get_or_insert(entity).name = Some(name.to_string());
+
+        true
+    }
+
+    pub fn clear_name(&mut self, entity: &Entity) {
+        if let Some(data) = self.data.get_mut(entity) {
+            if let Some(name) = data.name.take() {
+                self.names.remove(&name);
+            }
+        }
+    }
+
+    // Destroyed entities keep their names until `remove_destroyed_entities` runs, so they are
+    // filtered out here.
+    pub fn find_entity_by_name(
+        &self,
+        name: &str,
+        entity_manager: &EntityManager,
+    ) -> Option<Entity> {
+        self.names
+            .get(name)
+            .copied()
+            .filter(|entity| entity_manager.entity_is_alive(entity))
+    }
+
+    pub fn add_tag(&mut self, entity: &Entity, tag: &str) {
+        if *entity == Entity::null() || self.entity_has_tag(entity, tag) {
+            return;
+        }
+
+        self.get_or_insert(entity).tags.push(tag.to_string());
+        self.tagged
+            .entry(tag.to_string())
+            .or_default()
+            .push(*entity);
+    }
+
+    pub fn remove_tag(&mut self, entity: &Entity, tag: &str) {
+        if let Some(data) = self.data.get_mut(entity) {
+            let count = data.tags.len();
+            data.tags.retain(|t| t != tag);
+
+            if data.tags.len() != count {
+                self.untag(entity, tag);
+            }
+        }
+    }
+
+    pub fn entity_has_tag(&self, entity: &Entity, tag: &str) -> bool {
+        match self.data.get(entity) {
+            Some(data) => data.tags.iter().any(|t| t == tag),
+            None => false,
+        }
+    }
+
+    // The living entities carrying `tag`, in tagging order.
+    pub fn get_entities_with_tag<'a>(
+        &'a self,
+        tag: &str,
+        entity_manager: &'a EntityManager,
+    ) -> impl Iterator<Item = Entity> + 'a {
+        let entities: &[Entity] = match self.tagged.get(tag) {
+            Some(entities) => entities,
+            None => &[],
+        };
+
+        entities
+            .iter()
+            .copied()
+            .filter(move |entity| entity_manager.entity_is_alive(entity))
+    }
+
+    fn get_or_insert(&mut self, entity: &Entity) -> &mut NameData {
+        if !self.data.contains(entity) {
+            self.data.insert(
+                entity,
+                NameData {
+                    name: None,
+                    tags: Vec::new(),
+                },
+            );
+        }
+
+        self.data.get_mut(entity).unwrap()
+    }
+
+    fn untag(&mut self, entity: &Entity, tag: &str) {
+        if let Some(entities) = self.tagged.get_mut(tag) {
+            entities.retain(|e| e != entity);
+
+            if entities.is_empty() {
+                self.tagged.remove(tag);
+            }
+        }
+    }
+}
+
+impl Default for NameBuilder {
+    fn default() -> NameBuilder {
+        NameBuilder::new()
+    }
+}
+
+impl Default for NameSystem {
+    fn default() -> NameSystem {
+        NameSystem::new()
+    }
+}
@@ -1,10 +1,10 @@
 use super::super::super::range::Range;
 use super::super::super::renderer::Renderer;
+use super::super::storage::{ComponentStorage, ComponentsMut};
 use super::super::{Entity, EntityManager};
 use super::drawable::{DrawableBuilder, DrawableSystem};
 use super::rigid_body::{RigidBodyBuilder, RigidBodySystem};
 use super::transformation::{TransformationBuilder, TransformationSystem};
-use fnv::FnvHashMap;
 use gamemath::Vec3;
 use gameprng::prng_traits::PrngGeneration;
 use gameprng::xorshift128plus::XorShift128Plus;
@@ -18,8 +18,7 @@ pub struct ParticleEmitterData {
 }
 
 pub struct ParticleEmitterSystem {
-    map: FnvHashMap<Entity, usize>,
-    data: Vec<ParticleEmitterData>,
+    data: ComponentStorage<ParticleEmitterData>,
 }
 
 pub struct ParticleEmitterBuilder {}
@@ -55,7 +54,7 @@ impl ParticleEmitterBuilder {
 
         for _ in 0..count {
             let p = entity_manager.create_new_entity();
-            entity_manager.set_entity_is_active(&p, false);
+            entity_manager.set_entity_is_active(&p, false).ok();
 
             transformation_system.add_transformation_to_entity(
                 &p,
@@ -118,11 +117,20 @@ impl ParticleEmitterData {
                 particle.1 += dt;
 
                 if particle.1 >= particle.2 {
-                    entity_manager.set_entity_is_active(&particle.0, false);
+                    entity_manager.set_entity_is_active(&particle.0, false).ok();
                 }
             }
         }
 
+        // Without a transformation on the emitter there is nowhere to spawn particles from.
+        let position = match transformation_system.get_world_position(&self.owner) {
+            Some(p) => p,
+            None => {
+                self.emission_timer.0 = 0.0;
+                return;
+            }
+        };
+
         while self.emission_timer.0 >= self.emission_timer.1 {
             self.emission_timer.0 -= self.emission_timer.1;
 
@@ -132,7 +140,6 @@ impl ParticleEmitterData {
                         self.particle_lifetime.get_min(),
                         self.particle_lifetime.get_max(),
                     );
-                    let p = transformation_system.get_position(&self.owner).unwrap();
                     let v = Vec3::new(
                         prng.range(
                             self.particle_velocity.0.get_min(),
@@ -149,8 +156,9 @@ impl ParticleEmitterData {
                     );
 
                     rigid_body_system.set_velocity(&particle.0, v);
-                    transformation_system.set_position(&particle.0, p);
-                    entity_manager.set_entity_is_active(&particle.0, true);
+                    transformation_system.set_position(&particle.0, position);
+                    transformation_system.reset_interpolation(&particle.0);
+                    entity_manager.set_entity_is_active(&particle.0, true).ok();
                     particle.1 = 0.0;
                     particle.2 = t;
                     break;
@@ -163,8 +171,7 @@ impl ParticleEmitterData {
 impl ParticleEmitterSystem {
     pub fn new() -> ParticleEmitterSystem {
         ParticleEmitterSystem {
-            map: FnvHashMap::with_capacity_and_hasher(1, Default::default()),
-            data: Vec::new(),
+            data: ComponentStorage::new(),
         }
     }
 
@@ -178,55 +185,52 @@ impl ParticleEmitterSystem {
         rigid_body_system: &mut RigidBodySystem,
         drawable_system: &mut DrawableSystem,
     ) {
-        match self.entity_has_particle_emitter(entity) {
-            true => (), //TODO: Add error logging/printing here!
-            false => {
-                self.data.push(init_data.build(
-                    *entity,
-                    renderer,
-                    entity_manager,
-                    transformation_system,
-                    rigid_body_system,
-                    drawable_system,
-                ));
+        if self.data.contains(entity) {
+            //TODO: Add error logging/printing here!
+        } else {
+            let emitter = init_data.build(
+                *entity,
+                renderer,
+                entity_manager,
+                transformation_system,
+                rigid_body_system,
+                drawable_system,
+            );
 
-                self.map.insert(entity.clone(), self.data.len() - 1);
-            }
+            self.data.insert(entity, emitter);
         }
     }
 
+    pub fn components(&self) -> &ComponentStorage<ParticleEmitterData> {
+        &self.data
+    }
+
+    pub fn components_mut(&mut self) -> ComponentsMut<'_, ParticleEmitterData> {
+        ComponentsMut::new(&mut self.data)
+    }
+
     pub fn remove_particle_emitter_from_entity(
         &mut self,
         entity: &Entity,
         entity_manager: &mut EntityManager,
     ) {
-        let mut swapped = (false, 0);
-        let mut removed = false;
-
-        if *entity != Entity::null() {
-            match self.map.get(entity) {
-                Some(index) => {
-                    for particle in self.data[*index].particles.iter() {
-                        entity_manager.destroy_entity(&particle.0);
-                    }
-
-                    self.data.swap_remove(*index);
-                    removed = true;
-
-                    if self.data.is_empty() == false {
-                        swapped = (true, *index);
-                    }
-                }
-                None => (),
+        if let Some(emitter) = self.data.remove(entity) {
+            // Particles that were destroyed on their own are already gone.
+            for particle in emitter.particles.iter() {
+                entity_manager.destroy_entity(&particle.0).ok();
             }
         }
+    }
 
-        if removed == true {
-            self.map.remove(entity);
-        }
+    // Has to run before the other systems drain the destroyed list, since removing an emitter
+    // destroys its particle entities as well.
+    pub fn remove_destroyed_entities(&mut self, entity_manager: &mut EntityManager) {
+        let mut i = 0;
 
-        if swapped.0 == true && swapped.1 != self.data.len() {
-            *self.map.get_mut(&self.data[swapped.1].owner).unwrap() = swapped.1;
+        while i < entity_manager.get_destroyed_entities().len() {
+            let entity = entity_manager.get_destroyed_entities()[i];
+            self.remove_particle_emitter_from_entity(&entity, entity_manager);
+            i += 1;
         }
     }
 
@@ -240,8 +244,8 @@ impl ParticleEmitterSystem {
         rigid_body_system: &mut RigidBodySystem,
         drawable_system: &mut DrawableSystem,
     ) {
-        for emitter in self.data.iter_mut() {
-            if entity_manager.entity_is_active(&emitter.owner) == true {
+        for (owner, emitter) in self.data.iter_mut() {
+            if entity_manager.entity_is_active(owner) {
                 emitter.update(
                     dt,
                     prng,
@@ -256,6 +260,6 @@ impl ParticleEmitterSystem {
     }
 
     pub fn entity_has_particle_emitter(&self, entity: &Entity) -> bool {
-        self.map.contains_key(entity)
+        self.data.contains(entity)
     }
 }
@@ -0,0 +1,183 @@
+use super::super::super::storage::ComponentStorage;
+use super::super::transformation::TransformationSystem;
+use super::parallel::WorkerPool;
+use super::RigidBody;
+use gamemath::Vec3;
+
+// Keeps touching shapes from slipping through the bounds test because of rounding.
+const BOUNDS_MARGIN: f32 = 1.0e-4;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BroadphaseMode {
+    // Tests every pair, only useful as a reference for the sweep and prune.
+    BruteForce,
+    SweepAndPrune,
+}
+
+struct Proxy {
+    row: usize,
+    is_static: bool,
+    min: Vec3<f32>,
+    max: Vec3<f32>,
+}
+
+impl Proxy {
+    fn overlaps(&self, other: &Proxy) -> bool {
+        self.min.x <= other.max.x
+            && self.max.x >= other.min.x
+            && self.min.y <= other.max.y
+            && self.max.y >= other.min.y
+            && self.min.z <= other.max.z
+            && self.max.z >= other.min.z
+    }
+}
+
+// Sweep and prune along the x axis. Static bodies are kept in their own sorted list that is only
+// rebuilt when bodies are added or removed or a static body has been moved, dynamic bodies are
+// re-sorted every step.
+pub struct SweepAndPrune {
+    statics: Vec<Proxy>,
+    statics_outdated: bool,
+    dynamics: Vec<Proxy>,
+}
+
+impl SweepAndPrune {
+    pub fn new() -> SweepAndPrune {
+        SweepAndPrune {
+            statics: Vec::new(),
+            statics_outdated: true,
+            dynamics: Vec::new(),
+        }
+    }
+
+    // Has to be called whenever rows are added or removed, since the cached statics refer to rows,
+    // and whenever a static body is moved or gets or loses its transformation.
+    pub fn invalidate_statics(&mut self) {
+        self.statics_outdated = true;
+    }
+
+    // Fills `pairs` with the rows of every pair of bodies whose bounds overlap, whose layers match
+    // and of which at least one is dynamic, sorted the same way as the brute force loop visits them.
+    // The work is spread over the threads of `workers`, which doesn't change the result.
+    pub(super) fn find_pairs(
+        &mut self,
+        rigid_bodies: &ComponentStorage<RigidBody>,
+        transformation_system: &TransformationSystem,
+        workers: &WorkerPool,
+        pairs: &mut Vec<(usize, usize)>,
+    ) {
+        pairs.clear();
+
+        if self.statics_outdated {
+            self.statics =
+                SweepAndPrune::collect(rigid_bodies, transformation_system, true, workers);
+            self.statics_outdated = false;
+        }
+
+        self.dynamics = SweepAndPrune::collect(rigid_bodies, transformation_system, false, workers);
+
+        // Merges both lists, every proxy is then tested against the ones after it that start
+        // before it ends along the x axis.
+        let mut sorted = Vec::with_capacity(self.statics.len() + self.dynamics.len());
+        let mut next = (0, 0);
+
+        while next.0 < self.statics.len() || next.1 < self.dynamics.len() {
+            let take_static = match (self.statics.get(next.0), self.dynamics.get(next.1)) {
+                (Some(s), Some(d)) => s.min.x <= d.min.x,
+                (Some(_), None) => true,
+                _ => false,
+            };
+
+            if take_static {
+                sorted.push(&self.statics[next.0]);
+                next.0 += 1;
+            } else {
+                sorted.push(&self.dynamics[next.1]);
+                next.1 += 1;
+            }
+        }
+
+        let bodies = rigid_bodies.as_slice();
+        let found = workers.map_ranges(0..sorted.len(), |range| {
+            let mut found = Vec::new();
+
+            for i in range {
+                let proxy = sorted[i];
+
+                for other in sorted[i + 1..].iter() {
+                    if other.min.x > proxy.max.x {
+                        break;
+                    }
+
+                    if (!proxy.is_static || !other.is_static)
+                        && proxy.overlaps(other)
+                        && bodies[proxy.row].collides_with(&bodies[other.row])
+                    {
+                        found.push((proxy.row.min(other.row), proxy.row.max(other.row)));
+                    }
+                }
+            }
+
+            found
+        });
+
+        for found in found.iter() {
+            pairs.extend_from_slice(found);
+        }
+
+        pairs.sort_unstable();
+    }
+
+    // Proxies of either the static or the dynamic bodies sorted along the x axis.
+    fn collect(
+        rigid_bodies: &ComponentStorage<RigidBody>,
+        transformation_system: &TransformationSystem,
+        is_static: bool,
+        workers: &WorkerPool,
+    ) -> Vec<Proxy> {
+        let chunks = workers.map_ranges(0..rigid_bodies.len(), |rows| {
+            let mut proxies = Vec::new();
+
+            for row in rows {
+                let body = &rigid_bodies.as_slice()[row];
+
+                if (body.inv_mass == 0.0) != is_static {
+                    continue;
+                }
+
+                // Bodies without a transformation take no part in collisions until they get one.
+                if let Some(owner_pose) =
+                    transformation_system.get_world_pose(&rigid_bodies.entities()[row])
+                {
+                    let pose = body.get_shape_pose(owner_pose.0, owner_pose.1);
+                    let extents = body.shape.get_bounding_extents(&pose.1)
+                        + Vec3::new(BOUNDS_MARGIN, BOUNDS_MARGIN, BOUNDS_MARGIN);
+
+                    proxies.push(Proxy {
+                        row,
+                        is_static,
+                        min: pose.0 - extents,
+                        max: pose.0 + extents,
+                    });
+                }
+            }
+
+            proxies
+        });
+
+        let mut proxies = Vec::new();
+
+        for p in chunks {
+            proxies.extend(p);
+        }
+
+        proxies.sort_unstable_by(|a, b| a.min.x.total_cmp(&b.min.x));
+        proxies
+    }
+}
+
+impl Default for SweepAndPrune {
+    fn default() -> SweepAndPrune {
+        SweepAndPrune::new()
+    }
+}
@@ -0,0 +1,49 @@
+use gamemath::Vec3;
+
+// Settings of a `RigidBodySystem`, they can be swapped at any time with `set_config`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PhysicsConfig {
+    // Length of a physics step in seconds.
+    pub fixed_step: f32,
+    // Steps run by a single update at most, time beyond that is dropped so a slow frame can't
+    // make the next one even slower.
+    pub max_substeps: u32,
+    pub gravity: Vec3<f32>,
+    // Damping per second, applied to the velocities of the bodies every step.
+    pub linear_damping: f32,
+    pub angular_damping: f32,
+    // How many times per step the contacts are solved, more iterations make stacks stiffer.
+    pub solver_iterations: usize,
+    // Threads the integration and the broadphase are spread over, 0 uses every core. Results are
+    // the same for any count, 1 keeps everything on the calling thread for debugging.
+    pub threads: usize,
+    // Makes the results depend on nothing but the bodies and their inputs, not on the order the
+    // bodies were added in or the platform, and records a state hash after every step.
+    pub deterministic: bool,
+}
+
+impl PhysicsConfig {
+    pub fn new() -> PhysicsConfig {
+        PhysicsConfig {
+            fixed_step: 1.0 / 60.0,
+            max_substeps: 8,
+            gravity: Vec3::new(0.0, -9.82, 0.0),
+            linear_damping: 0.0,
+            angular_damping: 0.0,
+            solver_iterations: 8,
+            threads: 0,
+            deterministic: false,
+        }
+    }
+
+    // The factor velocities are scaled by each step for a damping per second.
+    pub(super) fn get_damping_factor(&self, damping: f32) -> f32 {
+        1.0 / (1.0 + self.fixed_step * damping.max(0.0))
+    }
+}
+
+impl Default for PhysicsConfig {
+    fn default() -> PhysicsConfig {
+        PhysicsConfig::new()
+    }
+}
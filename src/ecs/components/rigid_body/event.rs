@@ -0,0 +1,84 @@
+use super::super::super::Entity;
+use super::joint::JointHandle;
+use super::shape::CollisionManifold;
+use gamemath::Vec3;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CollisionEventKind {
+    // The bodies started touching during this step.
+    Begin,
+    // The bodies were already touching during the previous step.
+    Stay,
+    // The bodies stopped touching, the manifold is the last one they had.
+    End,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CollisionEvent {
+    pub(super) kind: CollisionEventKind,
+    pub(super) entities: (Entity, Entity),
+    pub(super) manifold: CollisionManifold,
+    pub(super) trigger: bool,
+}
+
+impl CollisionEvent {
+    pub fn get_kind(&self) -> CollisionEventKind {
+        self.kind
+    }
+
+    // The normal of the manifold points from the first entity towards the second one.
+    pub fn get_entities(&self) -> (Entity, Entity) {
+        self.entities
+    }
+
+    // The other entity of the pair, if `entity` is part of it.
+    pub fn get_other(&self, entity: &Entity) -> Option<Entity> {
+        if self.entities.0 == *entity {
+            Some(self.entities.1)
+        } else if self.entities.1 == *entity {
+            Some(self.entities.0)
+        } else {
+            None
+        }
+    }
+
+    pub fn get_manifold(&self) -> CollisionManifold {
+        self.manifold
+    }
+
+    pub fn get_normal(&self) -> Vec3<f32> {
+        self.manifold.get_normal()
+    }
+
+    pub fn get_penetration(&self) -> f32 {
+        self.manifold.get_penetration()
+    }
+
+    // Whether at least one of the bodies is a trigger, in which case the contact wasn't resolved.
+    pub fn is_trigger(&self) -> bool {
+        self.trigger
+    }
+}
+
+// A joint that took more than its breaking force and was removed.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct JointBreakEvent {
+    pub(super) joint: JointHandle,
+    pub(super) entities: (Entity, Option<Entity>),
+    pub(super) force: f32,
+}
+
+impl JointBreakEvent {
+    pub fn get_joint(&self) -> JointHandle {
+        self.joint
+    }
+
+    pub fn get_entities(&self) -> (Entity, Option<Entity>) {
+        self.entities
+    }
+
+    // The force the joint was holding with when it broke.
+    pub fn get_force(&self) -> f32 {
+        self.force
+    }
+}
@@ -0,0 +1,509 @@
+use super::super::super::super::utilities::portable_atan2;
+use super::super::super::Entity;
+use super::config::PhysicsConfig;
+use super::solver::{mul_inertia, tangents};
+use super::RigidBody;
+use gamemath::Vec3;
+
+// Fraction of the error of a joint corrected every step.
+const ERROR_CORRECTION: f32 = 0.2;
+pub(super) const MAX_ROWS: usize = 7;
+const EPSILON: f32 = 1.0e-6;
+
+// Slots of the rows of hinges and sliders that only exist with limits or a motor.
+const LIMIT_ROW: usize = 5;
+const MOTOR_ROW: usize = 6;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum JointKind {
+    // Keeps the anchors at the distance they start out at, or within the limits if there are any.
+    Distance,
+    // Keeps the anchors together, the bodies turn freely around them.
+    Ball,
+    // Keeps the anchors together, the bodies only turn around the axis given in world space.
+    Hinge(Vec3<f32>),
+    // The bodies only move apart along the axis given in world space, without turning.
+    Slider(Vec3<f32>),
+    // Keeps the bodies together as they are.
+    Fixed,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct JointHandle(u64);
+
+pub struct JointBuilder {
+    kind: JointKind,
+    anchors: Option<(Vec3<f32>, Vec3<f32>)>,
+    limits: Option<(f32, f32)>,
+    motor: Option<(f32, f32)>,
+    break_force: Option<f32>,
+}
+
+pub struct Joint {
+    handle: JointHandle,
+    // The second entity is `None` for joints holding on to the world.
+    entities: (Entity, Option<Entity>),
+    kind: JointKind,
+    limits: Option<(f32, f32)>,
+    motor: Option<(f32, f32)>,
+    break_force: Option<f32>,
+    // Anchors and axes of the joint in the local space of each body, around its center of mass.
+    // The world is a body at the origin that never turns.
+    local_anchors: (Vec3<f32>, Vec3<f32>),
+    local_axes: ([Vec3<f32>; 3], [Vec3<f32>; 3]),
+    // Accumulated by the rows in the last step, to warm start the next one.
+    impulses: [f32; MAX_ROWS],
+}
+
+// Center of mass and rotated unit axes of a body.
+pub(super) type Frame = (Vec3<f32>, [Vec3<f32>; 3]);
+
+// A single constraint along one direction, between the bodies at two rows. `None` is the world.
+pub(super) struct JointRow {
+    pub(super) joint: usize,
+    slot: usize,
+    rows: (Option<usize>, Option<usize>),
+    // Applied along `linear` to the second body and against it to the first one.
+    linear: Vec3<f32>,
+    angular: (Vec3<f32>, Vec3<f32>),
+    inv_masses: (f32, f32),
+    inv_inertias: ([Vec3<f32>; 3], [Vec3<f32>; 3]),
+    mass: f32,
+    bias: f32,
+    bounds: (f32, f32),
+    pub(super) impulse: f32,
+}
+
+impl JointHandle {
+    pub(super) fn new(id: u64) -> JointHandle {
+        JointHandle(id)
+    }
+}
+
+impl JointBuilder {
+    // Without anchors, the anchors of a distance joint are the centers of the bodies, the other
+    // joints are anchored at the center of the first body.
+    pub fn new(kind: JointKind) -> JointBuilder {
+        JointBuilder {
+            kind,
+            anchors: None,
+            limits: None,
+            motor: None,
+            break_force: None,
+        }
+    }
+
+    // Anchors both bodies at the same point in world space.
+    pub fn at_anchor(mut self, anchor: Vec3<f32>) -> JointBuilder {
+        self.anchors = Some((anchor, anchor));
+        self
+    }
+
+    // Anchor points of each body in world space, the one of the world when there's no second body.
+    pub fn with_anchors(mut self, anchor: Vec3<f32>, other_anchor: Vec3<f32>) -> JointBuilder {
+        self.anchors = Some((anchor, other_anchor));
+        self
+    }
+
+    // The range of distances of a distance joint, translations along a slider or angles of a
+    // hinge, in radians. Translations and angles are the ones of the first body relative to the
+    // second one, starting out at zero.
+    pub fn with_limits(mut self, min: f32, max: f32) -> JointBuilder {
+        self.limits = Some((min.min(max), min.max(max)));
+        self
+    }
+
+    // Drives the first body of a hinge or slider at `speed` relative to the second one, with up to
+    // `max_force`, a torque for hinges.
+    pub fn with_motor(mut self, speed: f32, max_force: f32) -> JointBuilder {
+        self.motor = Some((speed, max_force.max(0.0)));
+        self
+    }
+
+    // The joint breaks when holding the bodies together takes more than `force` in a step.
+    pub fn breaking_at(mut self, force: f32) -> JointBuilder {
+        self.break_force = Some(force);
+        self
+    }
+
+    pub(super) fn build(
+        self,
+        handle: JointHandle,
+        entities: (Entity, Option<Entity>),
+        frames: (Frame, Frame),
+    ) -> Joint {
+        let anchors = match (self.anchors, self.kind) {
+            (Some(anchors), _) => anchors,
+            (None, JointKind::Distance) => (frames.0 .0, frames.1 .0),
+            (None, _) => (frames.0 .0, frames.0 .0),
+        };
+        let axes = match self.kind {
+            JointKind::Hinge(axis) | JointKind::Slider(axis) if axis.length_squared() > EPSILON => {
+                let axis = axis.normalized();
+                let [t1, t2] = tangents(axis);
+
+                [axis, t1, t2]
+            }
+            _ => [
+                Vec3::new(1.0, 0.0, 0.0),
+                Vec3::new(0.0, 1.0, 0.0),
+                Vec3::new(0.0, 0.0, 1.0),
+            ],
+        };
+        let limits = match (self.limits, self.kind) {
+            (None, JointKind::Distance) => {
+                let length = (anchors.1 - anchors.0).length();
+
+                Some((length, length))
+            }
+            (limits, _) => limits,
+        };
+
+        Joint {
+            handle,
+            entities,
+            kind: self.kind,
+            limits,
+            motor: self.motor,
+            break_force: self.break_force,
+            local_anchors: (
+                to_local(anchors.0 - frames.0 .0, &frames.0 .1),
+                to_local(anchors.1 - frames.1 .0, &frames.1 .1),
+            ),
+            local_axes: (
+                axes.map(|a| to_local(a, &frames.0 .1)),
+                axes.map(|a| to_local(a, &frames.1 .1)),
+            ),
+            impulses: [0.0; MAX_ROWS],
+        }
+    }
+}
+
+impl Joint {
+    pub fn get_handle(&self) -> JointHandle {
+        self.handle
+    }
+
+    pub fn get_kind(&self) -> JointKind {
+        self.kind
+    }
+
+    pub fn get_entities(&self) -> (Entity, Option<Entity>) {
+        self.entities
+    }
+
+    pub fn get_limits(&self) -> Option<(f32, f32)> {
+        self.limits
+    }
+
+    pub fn get_break_force(&self) -> Option<f32> {
+        self.break_force
+    }
+
+    pub fn get_motor(&self) -> Option<(f32, f32)> {
+        self.motor
+    }
+
+    pub(super) fn set_motor(&mut self, motor: Option<(f32, f32)>) {
+        self.motor = motor.map(|(speed, max_force)| (speed, max_force.max(0.0)));
+    }
+
+    // Appends the rows the joint needs this step, with the bodies at `body_rows` in `frames`.
+    pub(super) fn add_rows(
+        &self,
+        index: usize,
+        bodies: &[RigidBody],
+        body_rows: (Option<usize>, Option<usize>),
+        frames: (Frame, Frame),
+        config: &PhysicsConfig,
+        rows: &mut Vec<JointRow>,
+    ) {
+        let step = config.fixed_step;
+        let (ca, axes_a) = frames.0;
+        let (cb, axes_b) = frames.1;
+        let ra = to_world(self.local_anchors.0, &axes_a);
+        let rb = to_world(self.local_anchors.1, &axes_b);
+        let d = (cb + rb) - (ca + ra);
+        let fa = self.local_axes.0.map(|a| to_world(a, &axes_a));
+        let fb = self.local_axes.1.map(|a| to_world(a, &axes_b));
+        let world = [
+            Vec3::new(1.0, 0.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            Vec3::new(0.0, 0.0, 1.0),
+        ];
+        // Moving along a direction turning with the first body.
+        let arm_a = (cb + rb) - ca;
+
+        let mut builder = RowBuilder {
+            index,
+            bodies,
+            body_rows,
+            step,
+            rows,
+            impulses: &self.impulses,
+        };
+
+        match self.kind {
+            JointKind::Distance => {
+                let length = d.length();
+                let normal = if length > EPSILON {
+                    d * (1.0 / length)
+                } else {
+                    fa[0]
+                };
+
+                if let Some(bounds) = limit_bounds(length, self.limits) {
+                    builder.add(0, (normal, (-ra.cross(normal), rb.cross(normal))), bounds);
+                }
+            }
+            JointKind::Ball | JointKind::Hinge(_) | JointKind::Fixed => {
+                for (slot, e) in world.iter().enumerate() {
+                    builder.add(
+                        slot,
+                        (*e, (-ra.cross(*e), rb.cross(*e))),
+                        (d.dot(*e), f32::MIN, f32::MAX),
+                    );
+                }
+            }
+            JointKind::Slider(_) => {
+                for (slot, t) in fa[1..].iter().enumerate() {
+                    builder.add(
+                        slot,
+                        (*t, (-arm_a.cross(*t), rb.cross(*t))),
+                        (d.dot(*t), f32::MIN, f32::MAX),
+                    );
+                }
+            }
+        }
+
+        let zero = Vec3::new(0.0, 0.0, 0.0);
+
+        match self.kind {
+            JointKind::Hinge(_) => {
+                let axis_error = fa[0].cross(fb[0]);
+
+                for (slot, t) in fa[1..].iter().enumerate() {
+                    builder.add(
+                        3 + slot,
+                        (zero, (-*t, *t)),
+                        (axis_error.dot(*t), f32::MIN, f32::MAX),
+                    );
+                }
+
+                let (sin, cos) = (fb[1].cross(fa[1]).dot(fa[0]), fa[1].dot(fb[1]));
+                let angle = if config.deterministic {
+                    portable_atan2(sin, cos)
+                } else {
+                    sin.atan2(cos)
+                };
+                let jacobian = (zero, (fa[0], -fa[0]));
+
+                if let Some(bounds) = limit_bounds(angle, self.limits) {
+                    builder.add(LIMIT_ROW, jacobian, bounds);
+                }
+
+                if let Some(motor) = self.motor {
+                    builder.add_motor(jacobian, motor);
+                }
+            }
+            JointKind::Slider(_) | JointKind::Fixed => {
+                // Half the sum of the crosses of matching axes is the small rotation between
+                // the bodies.
+                let mut rotation_error = zero;
+
+                for i in 0..3 {
+                    rotation_error += fa[i].cross(fb[i]) * 0.5;
+                }
+
+                let first = if self.kind == JointKind::Fixed { 3 } else { 2 };
+
+                for (slot, e) in world.iter().enumerate() {
+                    builder.add(
+                        first + slot,
+                        (zero, (-*e, *e)),
+                        (rotation_error.dot(*e), f32::MIN, f32::MAX),
+                    );
+                }
+
+                if let JointKind::Slider(_) = self.kind {
+                    let jacobian = (-fa[0], (arm_a.cross(fa[0]), -rb.cross(fa[0])));
+
+                    if let Some(bounds) = limit_bounds(-d.dot(fa[0]), self.limits) {
+                        builder.add(LIMIT_ROW, jacobian, bounds);
+                    }
+
+                    if let Some(motor) = self.motor {
+                        builder.add_motor(jacobian, motor);
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+
+    pub(super) fn get_impulses(&self) -> [f32; MAX_ROWS] {
+        self.impulses
+    }
+
+    pub(super) fn set_impulses(&mut self, impulses: [f32; MAX_ROWS]) {
+        self.impulses = impulses;
+    }
+
+    // The force the joint held the bodies together with in the last step, motors aside.
+    pub(super) fn get_force(&self, step: f32) -> f32 {
+        let mut sum = 0.0;
+
+        for (slot, impulse) in self.impulses.iter().enumerate() {
+            if slot != MOTOR_ROW {
+                sum += impulse * impulse;
+            }
+        }
+
+        sum.sqrt() / step
+    }
+
+    pub(super) fn store_impulses(&mut self, rows: &[JointRow]) {
+        self.impulses = [0.0; MAX_ROWS];
+
+        for row in rows {
+            self.impulses[row.slot] = row.impulse;
+        }
+    }
+}
+
+impl JointRow {
+    pub(super) fn warm_start(&self, bodies: &mut [RigidBody]) {
+        self.apply(bodies, self.impulse);
+    }
+
+    pub(super) fn solve(&mut self, bodies: &mut [RigidBody]) {
+        if self.mass == 0.0 {
+            return;
+        }
+
+        let mut velocity = 0.0;
+
+        if let Some(a) = self.rows.0 {
+            velocity += self.angular.0.dot(bodies[a].angular_velocity)
+                - self.linear.dot(bodies[a].velocity);
+        }
+
+        if let Some(b) = self.rows.1 {
+            velocity += self.angular.1.dot(bodies[b].angular_velocity)
+                + self.linear.dot(bodies[b].velocity);
+        }
+
+        let previous = self.impulse;
+        self.impulse =
+            (previous - (velocity + self.bias) / self.mass).clamp(self.bounds.0, self.bounds.1);
+        self.apply(bodies, self.impulse - previous);
+    }
+
+    pub(super) fn get_rows(&self) -> (Option<usize>, Option<usize>) {
+        self.rows
+    }
+
+    fn apply(&self, bodies: &mut [RigidBody], impulse: f32) {
+        if let Some(a) = self.rows.0 {
+            bodies[a].velocity -= self.linear * (impulse * self.inv_masses.0);
+            bodies[a].angular_velocity +=
+                mul_inertia(&self.inv_inertias.0, self.angular.0) * impulse;
+        }
+
+        if let Some(b) = self.rows.1 {
+            bodies[b].velocity += self.linear * (impulse * self.inv_masses.1);
+            bodies[b].angular_velocity +=
+                mul_inertia(&self.inv_inertias.1, self.angular.1) * impulse;
+        }
+    }
+}
+
+struct RowBuilder<'a> {
+    index: usize,
+    bodies: &'a [RigidBody],
+    body_rows: (Option<usize>, Option<usize>),
+    step: f32,
+    rows: &'a mut Vec<JointRow>,
+    impulses: &'a [f32; MAX_ROWS],
+}
+
+impl RowBuilder<'_> {
+    //jacobian(linear, (angular_a, angular_b)), limit(error, min_impulse, max_impulse)
+    fn add(
+        &mut self,
+        slot: usize,
+        jacobian: (Vec3<f32>, (Vec3<f32>, Vec3<f32>)),
+        limit: (f32, f32, f32),
+    ) {
+        let mut row = self.new_row(slot, jacobian, (limit.1, limit.2));
+        row.bias = limit.0 * ERROR_CORRECTION / self.step;
+        self.rows.push(row);
+    }
+
+    //motor(speed, max_force)
+    fn add_motor(&mut self, jacobian: (Vec3<f32>, (Vec3<f32>, Vec3<f32>)), motor: (f32, f32)) {
+        let max_impulse = motor.1 * self.step;
+        let mut row = self.new_row(MOTOR_ROW, jacobian, (-max_impulse, max_impulse));
+        row.bias = -motor.0;
+        self.rows.push(row);
+    }
+
+    fn new_row(
+        &self,
+        slot: usize,
+        jacobian: (Vec3<f32>, (Vec3<f32>, Vec3<f32>)),
+        bounds: (f32, f32),
+    ) -> JointRow {
+        let static_inertia = [Vec3::new(0.0, 0.0, 0.0); 3];
+        let side = |row: Option<usize>| match row {
+            Some(row) if self.bodies[row].get_solver_inv_mass() > 0.0 => (
+                self.bodies[row].get_solver_inv_mass(),
+                self.bodies[row].world_inv_inertia,
+            ),
+            _ => (0.0, static_inertia),
+        };
+        let (a, b) = (side(self.body_rows.0), side(self.body_rows.1));
+        let (linear, angular) = jacobian;
+        let mass = (a.0 + b.0) * linear.length_squared()
+            + angular.0.dot(mul_inertia(&a.1, angular.0))
+            + angular.1.dot(mul_inertia(&b.1, angular.1));
+
+        JointRow {
+            joint: self.index,
+            slot,
+            rows: self.body_rows,
+            linear,
+            angular,
+            inv_masses: (a.0, b.0),
+            inv_inertias: (a.1, b.1),
+            mass,
+            bias: 0.0,
+            bounds,
+            impulse: self.impulses[slot].clamp(bounds.0, bounds.1),
+        }
+    }
+}
+
+// Error and impulse bounds of a row keeping `value` within `limits`, if it's outside of them.
+// Equal limits hold the value in both directions.
+fn limit_bounds(value: f32, limits: Option<(f32, f32)>) -> Option<(f32, f32, f32)> {
+    let (min, max) = limits?;
+
+    if min == max {
+        Some((value - min, f32::MIN, f32::MAX))
+    } else if value < min {
+        Some((value - min, 0.0, f32::MAX))
+    } else if value > max {
+        Some((value - max, f32::MIN, 0.0))
+    } else {
+        None
+    }
+}
+
+fn to_local(v: Vec3<f32>, axes: &[Vec3<f32>; 3]) -> Vec3<f32> {
+    Vec3::new(v.dot(axes[0]), v.dot(axes[1]), v.dot(axes[2]))
+}
+
+fn to_world(v: Vec3<f32>, axes: &[Vec3<f32>; 3]) -> Vec3<f32> {
+    axes[0] * v.x + axes[1] * v.y + axes[2] * v.z
+}
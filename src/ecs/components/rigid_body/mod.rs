@@ -0,0 +1,2111 @@
+use fnv::FnvHasher;
+use std::f32;
+use std::hash::Hasher;
+
+pub mod broadphase;
+pub mod config;
+pub mod event;
+pub mod joint;
+mod parallel;
+pub mod shape;
+pub mod snapshot;
+pub mod solver;
+
+use self::broadphase::{BroadphaseMode, SweepAndPrune};
+use self::config::PhysicsConfig;
+use self::event::{CollisionEvent, CollisionEventKind, JointBreakEvent};
+use self::joint::{Frame, Joint, JointBuilder, JointHandle, JointRow};
+use self::parallel::WorkerPool;
+use self::shape::{ColliderShape, CollisionManifold};
+use self::snapshot::{BodyState, PhysicsSnapshot};
+use self::solver::{mul_inertia, CombineMode, ContactConstraint};
+use super::super::super::utilities::{quat_integrate, quat_integrate_portable, quat_rotate_vector};
+use super::super::storage::{ComponentStorage, ComponentsMut};
+use super::super::{Entity, EntityManager};
+use super::transformation::TransformationSystem;
+use gamemath::{Quat, Vec3};
+
+// Bodies start out on the first layer, colliding with every layer.
+pub const DEFAULT_COLLISION_LAYER: u32 = 1;
+pub const ALL_COLLISION_LAYERS: u32 = u32::MAX;
+
+// Bisection steps used to find where a shape cast first touches a body.
+const CAST_ITERATIONS: usize = 20;
+
+// How far a continuously colliding body is moved into whatever it was stopped by, so the contact
+// gets reported and resolved like any other.
+const CONTINUOUS_COLLISION_SKIN: f32 = 1.0e-3;
+
+// Bodies moving and turning slower than this for `SLEEP_DELAY` seconds fall asleep.
+const SLEEP_SPEED: f32 = 0.05;
+const SLEEP_DELAY: f32 = 0.5;
+
+// Contacts whose normal points at least this much upwards count as standing on something.
+const FOOTHOLD_MIN_NORMAL_Y: f32 = 0.7;
+
+pub struct RigidBodySystem {
+    timer: f32,
+    config: PhysicsConfig,
+    rigid_bodies: ComponentStorage<RigidBody>,
+    broadphase_mode: BroadphaseMode,
+    broadphase: SweepAndPrune,
+    pairs: Vec<(usize, usize)>,
+    moved: Vec<Entity>,
+    contacts: Vec<Contact>,
+    events: Vec<CollisionEvent>,
+    // Sorted by handle, handles only ever grow.
+    joints: Vec<Joint>,
+    next_joint: u64,
+    joint_events: Vec<JointBreakEvent>,
+    available_threads: usize,
+    workers: WorkerPool,
+    step_hashes: Vec<u64>,
+}
+
+// Velocity and pose a body ends a step with, `None` for the pose of a body that didn't move.
+struct Integration {
+    velocity: Vec3<f32>,
+    pose: Option<(Vec3<f32>, Option<Quat>)>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RayHit {
+    distance: f32,
+    point: Vec3<f32>,
+    normal: Vec3<f32>,
+    entity: Entity,
+}
+
+// Touching pair of the last step, ordered by entity bits so it can be looked up and reported in a
+// stable order.
+#[derive(Clone, Copy)]
+struct Contact {
+    entities: (Entity, Entity),
+    manifold: CollisionManifold,
+    trigger: bool,
+    normal_impulse: f32,
+    friction_impulse: Vec3<f32>,
+}
+
+pub struct RigidBodyBuilder {
+    offset: Option<Vec3<f32>>,
+    shape: Option<ColliderShape>,
+    velocity: Option<Vec3<f32>>,
+    angular_velocity: Option<Vec3<f32>>,
+    locked_rotation: bool,
+    elasticity: Option<f32>,
+    restitution_combine: Option<CombineMode>,
+    friction: Option<(f32, f32)>,
+    friction_combine: Option<CombineMode>,
+    inv_mass: Option<f32>,
+    gravity_immune: bool,
+    gravity_scale: Option<f32>,
+    trigger: bool,
+    continuous: bool,
+    never_sleeping: bool,
+    damage: Option<f32>,
+    die_on_collision: bool,
+    collision_layer: Option<u32>,
+    collision_mask: Option<u32>,
+}
+
+pub struct RigidBody {
+    owner: Entity,
+    offset: Vec3<f32>,
+    shape: ColliderShape,
+    pub velocity: Vec3<f32>,
+    // Radians per second around the world axes, following the right hand rule.
+    pub angular_velocity: Vec3<f32>,
+    pub locomotion: Vec3<f32>,
+    // Accumulated by `apply_force` and friends, applied during every step of the next update.
+    force: Vec3<f32>,
+    torque: Vec3<f32>,
+    locked_rotation: bool,
+    // Inverse principal moments of inertia around the local axes.
+    inv_inertia: Vec3<f32>,
+    // Rows of the inverse inertia tensor in world space and the center of mass, both as of the
+    // start of the current step.
+    world_inv_inertia: [Vec3<f32>; 3],
+    center: Vec3<f32>,
+    pub elasticity: f32,
+    restitution_combine: CombineMode,
+    static_friction: f32,
+    dynamic_friction: f32,
+    friction_combine: CombineMode,
+    inv_mass: f32,
+    pub gravity_immune: bool,
+    gravity_scale: f32,
+    trigger: bool,
+    continuous: bool,
+    never_sleeping: bool,
+    sleeping: bool,
+    sleep_timer: f32,
+    in_contact: bool,
+    foothold: bool,
+    damage: f32,
+    die_on_collision: bool,
+    collision_layer: u32,
+    collision_mask: u32,
+}
+
+impl RayHit {
+    // Distance from the origin of the ray, in the units of the world.
+    pub fn get_distance(&self) -> f32 {
+        self.distance
+    }
+
+    pub fn get_point(&self) -> Vec3<f32> {
+        self.point
+    }
+
+    // Surface normal of the body that was hit, pointing back towards the ray.
+    pub fn get_normal(&self) -> Vec3<f32> {
+        self.normal
+    }
+
+    pub fn get_entity(&self) -> Entity {
+        self.entity
+    }
+}
+
+impl RigidBody {
+    pub fn get_shape(&self) -> ColliderShape {
+        self.shape
+    }
+
+    // The center and rotation of the shape for an owner at `position` with `rotation`. The offset
+    // turns with the owner unless the shape is axis aligned.
+    pub fn get_shape_pose(&self, position: Vec3<f32>, rotation: Quat) -> (Vec3<f32>, Quat) {
+        if self.shape.uses_rotation() {
+            let rotation = rotation.normalized();
+
+            (
+                position + quat_rotate_vector(&rotation, self.offset),
+                rotation,
+            )
+        } else {
+            (position + self.offset, Quat::identity())
+        }
+    }
+
+    pub fn get_collision_layer(&self) -> u32 {
+        self.collision_layer
+    }
+
+    pub fn get_collision_mask(&self) -> u32 {
+        self.collision_mask
+    }
+
+    // Triggers report contacts but are never pushed apart from other bodies.
+    pub fn is_trigger(&self) -> bool {
+        self.trigger
+    }
+
+    pub fn get_damage(&self) -> f32 {
+        self.damage
+    }
+
+    pub fn dies_on_collision(&self) -> bool {
+        self.die_on_collision
+    }
+
+    pub fn is_sleeping(&self) -> bool {
+        self.sleeping
+    }
+
+    // Static bodies, axis aligned boxes and bodies with locked rotation never turn on their own.
+    pub fn can_rotate(&self) -> bool {
+        self.inv_inertia != Vec3::new(0.0, 0.0, 0.0)
+    }
+
+    fn update_inertia(&mut self) {
+        self.inv_inertia = Vec3::new(0.0, 0.0, 0.0);
+
+        if self.locked_rotation
+            || self.inv_mass == 0.0
+            || matches!(self.shape, ColliderShape::Box(_))
+        {
+            return;
+        }
+
+        let inertia = self.shape.get_inertia(1.0 / self.inv_mass);
+
+        if inertia.x > 0.0 && inertia.y > 0.0 && inertia.z > 0.0 {
+            self.inv_inertia = Vec3::new(1.0 / inertia.x, 1.0 / inertia.y, 1.0 / inertia.z);
+        }
+    }
+
+    // R * I^-1 * R^T for the rotation of the owner.
+    fn get_world_inv_inertia(&self, rotation: &Quat) -> [Vec3<f32>; 3] {
+        let mut result = [Vec3::new(0.0, 0.0, 0.0); 3];
+
+        if !self.can_rotate() {
+            return result;
+        }
+
+        let rotation = rotation.normalized();
+        let axes = [
+            quat_rotate_vector(&rotation, Vec3::new(1.0, 0.0, 0.0)),
+            quat_rotate_vector(&rotation, Vec3::new(0.0, 1.0, 0.0)),
+            quat_rotate_vector(&rotation, Vec3::new(0.0, 0.0, 1.0)),
+        ];
+
+        for (r, row) in result.iter_mut().enumerate() {
+            for (i, axis) in axes.iter().enumerate() {
+                *row += *axis * (self.inv_inertia[i] * axis[r]);
+            }
+        }
+
+        result
+    }
+
+    fn wake_up(&mut self) {
+        self.sleeping = false;
+        self.sleep_timer = 0.0;
+    }
+
+    // Sleeping bodies take part in contacts like static ones.
+    fn get_solver_inv_mass(&self) -> f32 {
+        if self.sleeping {
+            0.0
+        } else {
+            self.inv_mass
+        }
+    }
+
+    // Both bodies have to be on a layer the other one's mask accepts.
+    pub fn collides_with(&self, other: &RigidBody) -> bool {
+        self.collision_layer & other.collision_mask != 0
+            && other.collision_layer & self.collision_mask != 0
+    }
+
+    // Poses are (position, rotation) of the owners.
+    pub fn colliding(
+        &self,
+        other: &RigidBody,
+        poses: ((Vec3<f32>, Quat), (Vec3<f32>, Quat)),
+    ) -> Option<CollisionManifold> {
+        self.shape.colliding(
+            self.get_shape_pose(poses.0 .0, poses.0 .1),
+            &other.shape,
+            other.get_shape_pose(poses.1 .0, poses.1 .1),
+        )
+    }
+}
+
+impl RigidBodyBuilder {
+    pub fn new() -> RigidBodyBuilder {
+        RigidBodyBuilder {
+            offset: None,
+            shape: None,
+            velocity: None,
+            angular_velocity: None,
+            locked_rotation: false,
+            elasticity: None,
+            restitution_combine: None,
+            friction: None,
+            friction_combine: None,
+            inv_mass: None,
+            gravity_immune: false,
+            gravity_scale: None,
+            trigger: false,
+            continuous: false,
+            never_sleeping: false,
+            damage: None,
+            die_on_collision: false,
+            collision_layer: None,
+            collision_mask: None,
+        }
+    }
+
+    pub fn with_offset(mut self, offset: Vec3<f32>) -> RigidBodyBuilder {
+        self.offset = Some(offset);
+        self
+    }
+
+    // An axis aligned box.
+    pub fn with_extents(mut self, extents: Vec3<f32>) -> RigidBodyBuilder {
+        self.shape = Some(ColliderShape::Box(extents));
+        self
+    }
+
+    pub fn with_oriented_extents(mut self, extents: Vec3<f32>) -> RigidBodyBuilder {
+        self.shape = Some(ColliderShape::OrientedBox(extents));
+        self
+    }
+
+    pub fn with_sphere(mut self, radius: f32) -> RigidBodyBuilder {
+        self.shape = Some(ColliderShape::Sphere(radius));
+        self
+    }
+
+    pub fn with_capsule(mut self, radius: f32, half_height: f32) -> RigidBodyBuilder {
+        self.shape = Some(ColliderShape::Capsule {
+            radius,
+            half_height,
+        });
+        self
+    }
+
+    pub fn with_shape(mut self, shape: ColliderShape) -> RigidBodyBuilder {
+        self.shape = Some(shape);
+        self
+    }
+
+    pub fn with_velocity(mut self, velocity: Vec3<f32>) -> RigidBodyBuilder {
+        self.velocity = Some(velocity);
+        self
+    }
+
+    // Radians per second around the world axes.
+    pub fn with_angular_velocity(mut self, angular_velocity: Vec3<f32>) -> RigidBodyBuilder {
+        self.angular_velocity = Some(angular_velocity);
+        self
+    }
+
+    // Keeps contacts and off center forces from turning the body, e.g. for characters.
+    pub fn with_locked_rotation(mut self) -> RigidBodyBuilder {
+        self.locked_rotation = true;
+        self
+    }
+
+    pub fn with_elasticity(mut self, elasticity: f32) -> RigidBodyBuilder {
+        self.elasticity = Some(elasticity);
+        self
+    }
+
+    pub fn with_restitution_combine(mut self, mode: CombineMode) -> RigidBodyBuilder {
+        self.restitution_combine = Some(mode);
+        self
+    }
+
+    // Static friction holds resting bodies in place, dynamic friction slows down sliding ones.
+    pub fn with_friction(
+        mut self,
+        static_friction: f32,
+        dynamic_friction: f32,
+    ) -> RigidBodyBuilder {
+        self.friction = Some((static_friction, dynamic_friction));
+        self
+    }
+
+    pub fn with_friction_combine(mut self, mode: CombineMode) -> RigidBodyBuilder {
+        self.friction_combine = Some(mode);
+        self
+    }
+
+    pub fn is_never_sleeping(mut self) -> RigidBodyBuilder {
+        self.never_sleeping = true;
+        self
+    }
+
+    // Multiplies the gravity of the system for this body alone.
+    pub fn with_gravity_scale(mut self, scale: f32) -> RigidBodyBuilder {
+        self.gravity_scale = Some(scale);
+        self
+    }
+
+    pub fn is_gravity_immune(mut self) -> RigidBodyBuilder {
+        self.gravity_immune = true;
+        self
+    }
+
+    pub fn is_trigger(mut self) -> RigidBodyBuilder {
+        self.trigger = true;
+        self
+    }
+
+    // Sweeps the body along its motion every step, so it can't pass through thin bodies.
+    pub fn with_continuous_collision(mut self) -> RigidBodyBuilder {
+        self.continuous = true;
+        self
+    }
+
+    pub fn dealing_damage(mut self, damage: f32) -> RigidBodyBuilder {
+        self.damage = Some(damage);
+        self
+    }
+
+    pub fn dies_on_collision(mut self) -> RigidBodyBuilder {
+        self.die_on_collision = true;
+        self
+    }
+
+    // The layer bits the body is on.
+    pub fn with_collision_layer(mut self, layer: u32) -> RigidBodyBuilder {
+        self.collision_layer = Some(layer);
+        self
+    }
+
+    // The layer bits of the bodies this one collides with.
+    pub fn with_collision_mask(mut self, mask: u32) -> RigidBodyBuilder {
+        self.collision_mask = Some(mask);
+        self
+    }
+
+    pub fn with_mass(mut self, mass: f32) -> RigidBodyBuilder {
+        self.inv_mass = if mass > 0.0 {
+            Some(1.0 / mass)
+        } else {
+            Some(0.0)
+        };
+
+        self
+    }
+
+    fn build(self, owner: Entity) -> RigidBody {
+        let mut body = RigidBody {
+            owner,
+            offset: match self.offset {
+                Some(o) => o,
+                None => Vec3::new(0.0, 0.0, 0.0),
+            },
+            shape: match self.shape {
+                Some(s) => s,
+                None => ColliderShape::Box(Vec3::new(0.5, 0.5, 0.5)),
+            },
+            velocity: match self.velocity {
+                Some(v) => v,
+                None => Vec3::new(0.0, 0.0, 0.0),
+            },
+            angular_velocity: match self.angular_velocity {
+                Some(v) => v,
+                None => Vec3::new(0.0, 0.0, 0.0),
+            },
+            locomotion: Vec3::new(0.0, 0.0, 0.0),
+            force: Vec3::new(0.0, 0.0, 0.0),
+            torque: Vec3::new(0.0, 0.0, 0.0),
+            locked_rotation: self.locked_rotation,
+            inv_inertia: Vec3::new(0.0, 0.0, 0.0),
+            world_inv_inertia: [Vec3::new(0.0, 0.0, 0.0); 3],
+            center: Vec3::new(0.0, 0.0, 0.0),
+            elasticity: match self.elasticity {
+                Some(e) => e,
+                None => 0.0,
+            },
+            restitution_combine: match self.restitution_combine {
+                Some(c) => c,
+                None => CombineMode::Maximum,
+            },
+            static_friction: match self.friction {
+                Some(f) => f.0,
+                None => 0.0,
+            },
+            dynamic_friction: match self.friction {
+                Some(f) => f.1,
+                None => 0.0,
+            },
+            friction_combine: match self.friction_combine {
+                Some(c) => c,
+                None => CombineMode::Average,
+            },
+            inv_mass: match self.inv_mass {
+                Some(m) => m,
+                None => 0.0,
+            },
+            gravity_immune: self.gravity_immune,
+            gravity_scale: self.gravity_scale.unwrap_or(1.0),
+            trigger: self.trigger,
+            continuous: self.continuous,
+            never_sleeping: self.never_sleeping,
+            sleeping: false,
+            sleep_timer: 0.0,
+            in_contact: false,
+            foothold: false,
+            damage: match self.damage {
+                Some(d) => d,
+                None => 0.0,
+            },
+            die_on_collision: self.die_on_collision,
+            collision_layer: match self.collision_layer {
+                Some(l) => l,
+                None => DEFAULT_COLLISION_LAYER,
+            },
+            collision_mask: match self.collision_mask {
+                Some(m) => m,
+                None => ALL_COLLISION_LAYERS,
+            },
+        };
+
+        body.update_inertia();
+        body
+    }
+}
+
+impl RigidBodySystem {
+    pub fn new() -> RigidBodySystem {
+        RigidBodySystem {
+            timer: 0.0,
+            config: PhysicsConfig::new(),
+            rigid_bodies: ComponentStorage::new(),
+            broadphase_mode: BroadphaseMode::SweepAndPrune,
+            broadphase: SweepAndPrune::new(),
+            pairs: Vec::new(),
+            moved: Vec::new(),
+            contacts: Vec::new(),
+            events: Vec::new(),
+            joints: Vec::new(),
+            next_joint: 0,
+            joint_events: Vec::new(),
+            available_threads: parallel::available_threads(),
+            workers: WorkerPool::new(),
+            step_hashes: Vec::new(),
+        }
+    }
+
+    pub fn add_rigid_body_to_entity(
+        &mut self,
+        entity: &Entity,
+        collider_builder: RigidBodyBuilder,
+        transformation_system: &TransformationSystem,
+    ) {
+        if self.rigid_bodies.contains(entity) {
+            //TODO: Add error logging/printing here!
+        } else if transformation_system.entity_has_transformation(entity) {
+            self.rigid_bodies
+                .insert(entity, collider_builder.build(*entity));
+            self.broadphase.invalidate_statics();
+        } else {
+            //TODO: Add error logging/printing here!
+        }
+    }
+
+    pub fn remove_rigid_body_from_entity(&mut self, entity: &Entity) {
+        if self.rigid_bodies.remove(entity).is_some() {
+            self.broadphase.invalidate_statics();
+            self.remove_dangling_joints();
+        }
+    }
+
+    pub fn remove_destroyed_entities(&mut self, entity_manager: &EntityManager) {
+        let count = self.rigid_bodies.len();
+        self.rigid_bodies.remove_destroyed(entity_manager);
+
+        if self.rigid_bodies.len() != count {
+            self.broadphase.invalidate_statics();
+            self.remove_dangling_joints();
+        }
+    }
+
+    pub fn entity_has_rigid_body(&self, entity: &Entity) -> bool {
+        self.rigid_bodies.contains(entity)
+    }
+
+    pub fn components(&self) -> &ComponentStorage<RigidBody> {
+        &self.rigid_bodies
+    }
+
+    pub fn components_mut(&mut self) -> ComponentsMut<'_, RigidBody> {
+        ComponentsMut::new(&mut self.rigid_bodies)
+    }
+
+    // Local half extents of the collider shape, see `ColliderShape::get_extents`.
+    pub fn get_extents(&self, entity: &Entity) -> Option<Vec3<f32>> {
+        self.rigid_bodies
+            .get(entity)
+            .map(|body| body.shape.get_extents())
+    }
+
+    pub fn get_shape(&self, entity: &Entity) -> Option<ColliderShape> {
+        self.rigid_bodies.get(entity).map(|body| body.shape)
+    }
+
+    pub fn set_shape(&mut self, entity: &Entity, shape: ColliderShape) {
+        if let Some(body) = self.rigid_bodies.get_mut(entity) {
+            body.shape = shape;
+            body.update_inertia();
+            self.broadphase.invalidate_statics();
+        }
+    }
+
+    pub fn get_broadphase_mode(&self) -> BroadphaseMode {
+        self.broadphase_mode
+    }
+
+    pub fn set_broadphase_mode(&mut self, mode: BroadphaseMode) {
+        self.broadphase_mode = mode;
+        self.broadphase.invalidate_statics();
+    }
+
+    // Every contact at the current poses, as the next step would see them before resolving any.
+    pub fn find_contacts(
+        &mut self,
+        transformation_system: &mut TransformationSystem,
+    ) -> Vec<(Entity, Entity, CollisionManifold)> {
+        let mut pairs = std::mem::take(&mut self.pairs);
+        let mut contacts = Vec::new();
+
+        self.find_pairs(transformation_system, &mut pairs);
+
+        for (i, j) in pairs.iter() {
+            let rigid_bodies = self.rigid_bodies.as_slice();
+            let poses = match (
+                transformation_system.get_world_pose(&rigid_bodies[*i].owner),
+                transformation_system.get_world_pose(&rigid_bodies[*j].owner),
+            ) {
+                (Some(pose_1), Some(pose_2)) => (pose_1, pose_2),
+                _ => continue,
+            };
+
+            if let Some(manifold) = rigid_bodies[*i].colliding(&rigid_bodies[*j], poses) {
+                contacts.push((rigid_bodies[*i].owner, rigid_bodies[*j].owner, manifold));
+            }
+        }
+
+        self.pairs = pairs;
+        contacts
+    }
+
+    // Whether moving the entity moves a static body, either its own or one of its descendants'.
+    fn moves_static_body(
+        &self,
+        entity: &Entity,
+        transformation_system: &TransformationSystem,
+    ) -> bool {
+        let is_static = match self.rigid_bodies.get(entity) {
+            Some(body) => body.inv_mass == 0.0,
+            None => false,
+        };
+
+        is_static
+            || transformation_system
+                .get_children(entity)
+                .iter()
+                .any(|child| self.moves_static_body(child, transformation_system))
+    }
+
+    // Candidate pairs of rows, ordered as the brute force loop would visit them, or by entity in
+    // deterministic mode.
+    fn find_pairs(
+        &mut self,
+        transformation_system: &mut TransformationSystem,
+        pairs: &mut Vec<(usize, usize)>,
+    ) {
+        // Static bodies moved by gameplay code, e.g. platforms, have to be sorted in again.
+        let mut moved = std::mem::take(&mut self.moved);
+
+        if !transformation_system.drain_moved_entities(&mut moved)
+            || moved
+                .iter()
+                .any(|entity| self.moves_static_body(entity, transformation_system))
+        {
+            self.broadphase.invalidate_statics();
+        }
+
+        self.moved = moved;
+
+        match self.broadphase_mode {
+            BroadphaseMode::BruteForce => {
+                let rigid_bodies = self.rigid_bodies.as_slice();
+                pairs.clear();
+
+                for i in 0..rigid_bodies.len() {
+                    for j in (i + 1)..rigid_bodies.len() {
+                        if (rigid_bodies[i].inv_mass != 0.0 || rigid_bodies[j].inv_mass != 0.0)
+                            && rigid_bodies[i].collides_with(&rigid_bodies[j])
+                        {
+                            pairs.push((i, j));
+                        }
+                    }
+                }
+            }
+            BroadphaseMode::SweepAndPrune => self.broadphase.find_pairs(
+                &self.rigid_bodies,
+                transformation_system,
+                &self.workers,
+                pairs,
+            ),
+        }
+
+        // Rows change as bodies are removed, the entities owning them don't. The body of the lower
+        // entity goes first, so contact normals don't flip either.
+        if self.config.deterministic {
+            let entities = self.rigid_bodies.entities();
+
+            for pair in pairs.iter_mut() {
+                if entities[pair.0].to_bits() > entities[pair.1].to_bits() {
+                    *pair = (pair.1, pair.0);
+                }
+            }
+
+            pairs.sort_unstable_by_key(|&(i, j)| (entities[i].to_bits(), entities[j].to_bits()));
+        }
+    }
+
+    pub fn get_collision_layer(&self, entity: &Entity) -> Option<u32> {
+        self.rigid_bodies
+            .get(entity)
+            .map(|body| body.collision_layer)
+    }
+
+    pub fn set_collision_layer(&mut self, entity: &Entity, layer: u32) {
+        if let Some(body) = self.rigid_bodies.get_mut(entity) {
+            body.collision_layer = layer;
+        }
+    }
+
+    pub fn get_collision_mask(&self, entity: &Entity) -> Option<u32> {
+        self.rigid_bodies
+            .get(entity)
+            .map(|body| body.collision_mask)
+    }
+
+    pub fn set_collision_mask(&mut self, entity: &Entity, mask: u32) {
+        if let Some(body) = self.rigid_bodies.get_mut(entity) {
+            body.collision_mask = mask;
+        }
+    }
+
+    pub fn set_continuous_collision(&mut self, entity: &Entity, continuous: bool) {
+        if let Some(body) = self.rigid_bodies.get_mut(entity) {
+            body.continuous = continuous;
+        }
+    }
+
+    pub fn set_trigger(&mut self, entity: &Entity, trigger: bool) {
+        if let Some(body) = self.rigid_bodies.get_mut(entity) {
+            body.trigger = trigger;
+        }
+    }
+
+    // Contacts of every step run by the last call to `update`, in the order they happened.
+    pub fn get_collision_events(&self) -> &[CollisionEvent] {
+        &self.events
+    }
+
+    // Joints broken during the last update.
+    pub fn get_joint_break_events(&self) -> &[JointBreakEvent] {
+        &self.joint_events
+    }
+
+    // Joins `entity` with `other`, or with the world when there is no other entity. Both need a
+    // rigid body and a transformation.
+    pub fn add_joint(
+        &mut self,
+        entity: &Entity,
+        other: Option<&Entity>,
+        joint_builder: JointBuilder,
+        transformation_system: &TransformationSystem,
+    ) -> Option<JointHandle> {
+        if other == Some(entity) {
+            return None;
+        }
+
+        let frame = self.get_joint_frame(Some(entity), transformation_system)?.1;
+        let other_frame = self.get_joint_frame(other, transformation_system)?.1;
+        let handle = JointHandle::new(self.next_joint);
+        self.next_joint += 1;
+
+        self.joints.push(joint_builder.build(
+            handle,
+            (*entity, other.copied()),
+            (frame, other_frame),
+        ));
+
+        for e in [Some(entity), other].iter().flatten() {
+            if let Some(body) = self.rigid_bodies.get_mut(e) {
+                body.wake_up();
+            }
+        }
+
+        Some(handle)
+    }
+
+    pub fn remove_joint(&mut self, handle: JointHandle) -> bool {
+        match self
+            .joints
+            .binary_search_by_key(&handle, |j| j.get_handle())
+        {
+            Ok(index) => {
+                self.joints.remove(index);
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
+    pub fn get_joint(&self, handle: JointHandle) -> Option<&Joint> {
+        self.joints
+            .binary_search_by_key(&handle, |j| j.get_handle())
+            .ok()
+            .map(|index| &self.joints[index])
+    }
+
+    pub fn get_joints(&self) -> &[Joint] {
+        &self.joints
+    }
+
+    // Drives a hinge or slider at `speed` with up to `max_force`, `None` turns the motor off.
+    pub fn set_joint_motor(&mut self, handle: JointHandle, motor: Option<(f32, f32)>) {
+        if let Ok(index) = self
+            .joints
+            .binary_search_by_key(&handle, |j| j.get_handle())
+        {
+            self.joints[index].set_motor(motor);
+
+            let entities = self.joints[index].get_entities();
+
+            for e in [Some(entities.0), entities.1].iter().flatten() {
+                if let Some(body) = self.rigid_bodies.get_mut(e) {
+                    body.wake_up();
+                }
+            }
+        }
+    }
+
+    pub fn get_config(&self) -> &PhysicsConfig {
+        &self.config
+    }
+
+    pub fn set_config(&mut self, config: PhysicsConfig) {
+        self.config = config;
+    }
+
+    // Stores the state of every body, the poses of their owners and what the next step warm starts
+    // from in `snapshot`, reusing its memory.
+    pub fn save_snapshot(
+        &self,
+        transformation_system: &TransformationSystem,
+        snapshot: &mut PhysicsSnapshot,
+    ) {
+        snapshot.clear();
+        snapshot.timer = self.timer;
+
+        for (entity, body) in self.rigid_bodies.iter() {
+            let (position, rotation) = match transformation_system.get_world_pose(entity) {
+                Some(pose) => pose,
+                None => continue,
+            };
+
+            snapshot.bodies.push(BodyState {
+                entity: *entity,
+                position,
+                rotation,
+                velocity: body.velocity,
+                angular_velocity: body.angular_velocity,
+                locomotion: body.locomotion,
+                force: body.force,
+                torque: body.torque,
+                sleep_timer: body.sleep_timer,
+                sleeping: body.sleeping,
+                in_contact: body.in_contact,
+                foothold: body.foothold,
+            });
+        }
+
+        snapshot.contacts.extend_from_slice(&self.contacts);
+        snapshot.joints.extend(
+            self.joints
+                .iter()
+                .map(|joint| (joint.get_handle(), joint.get_impulses())),
+        );
+    }
+
+    // Puts the bodies back into the state saved in `snapshot`. Bodies added since keep their state
+    // and bodies removed or joints broken since stay gone. The owners snap to their poses rather than
+    // being interpolated there.
+    pub fn restore_snapshot(
+        &mut self,
+        snapshot: &PhysicsSnapshot,
+        transformation_system: &mut TransformationSystem,
+    ) {
+        self.timer = snapshot.timer;
+
+        for state in snapshot.bodies.iter() {
+            let body = match self.rigid_bodies.get_mut(&state.entity) {
+                Some(b) => b,
+                None => continue,
+            };
+
+            body.velocity = state.velocity;
+            body.angular_velocity = state.angular_velocity;
+            body.locomotion = state.locomotion;
+            body.force = state.force;
+            body.torque = state.torque;
+            body.sleep_timer = state.sleep_timer;
+            body.sleeping = state.sleeping;
+            body.in_contact = state.in_contact;
+            body.foothold = state.foothold;
+
+            transformation_system.set_world_position(&state.entity, state.position);
+            transformation_system.set_world_rotation(&state.entity, state.rotation);
+            transformation_system.reset_interpolation(&state.entity);
+        }
+
+        self.contacts.clear();
+        self.contacts.extend_from_slice(&snapshot.contacts);
+
+        for (handle, impulses) in snapshot.joints.iter() {
+            if let Ok(index) = self.joints.binary_search_by_key(handle, |j| j.get_handle()) {
+                self.joints[index].set_impulses(*impulses);
+            }
+        }
+    }
+
+    // Hashes of the state after each step of the last update, only recorded in deterministic mode.
+    // Two simulations fed the same inputs step in lockstep as long as these match.
+    pub fn get_step_hashes(&self) -> &[u64] {
+        &self.step_hashes
+    }
+
+    // A hash of the poses, velocities and sleep states of all bodies. It doesn't depend on the order
+    // of the rows and is the same on every platform.
+    pub fn get_state_hash(&self, transformation_system: &TransformationSystem) -> u64 {
+        let entities = self.rigid_bodies.entities();
+        let mut rows: Vec<usize> = (0..entities.len()).collect();
+        rows.sort_unstable_by_key(|row| entities[*row].to_bits());
+
+        let mut hasher = FnvHasher::default();
+
+        for row in rows {
+            let body = &self.rigid_bodies.as_slice()[row];
+            hasher.write(&entities[row].to_bits().to_le_bytes());
+            hasher.write_u8(body.sleeping as u8);
+
+            let (position, rotation) = match transformation_system.get_world_pose(&entities[row]) {
+                Some(pose) => pose,
+                None => (Vec3::new(0.0, 0.0, 0.0), Quat::identity()),
+            };
+            let (v, w) = (body.velocity, body.angular_velocity);
+
+            for value in [
+                position.x, position.y, position.z, rotation.x, rotation.y, rotation.z, rotation.w,
+                v.x, v.y, v.z, w.x, w.y, w.z,
+            ] {
+                hasher.write(&value.to_bits().to_le_bytes());
+            }
+        }
+
+        hasher.finish()
+    }
+
+    pub fn set_gravity(&mut self, gravity: Vec3<f32>) {
+        self.config.gravity = gravity;
+    }
+
+    pub fn set_gravity_scale(&mut self, entity: &Entity, scale: f32) {
+        if let Some(body) = self.rigid_bodies.get_mut(entity) {
+            body.gravity_scale = scale;
+        }
+    }
+
+    pub fn set_friction(&mut self, entity: &Entity, static_friction: f32, dynamic_friction: f32) {
+        if let Some(body) = self.rigid_bodies.get_mut(entity) {
+            body.static_friction = static_friction;
+            body.dynamic_friction = dynamic_friction;
+        }
+    }
+
+    pub fn is_sleeping(&self, entity: &Entity) -> bool {
+        match self.rigid_bodies.get(entity) {
+            Some(body) => body.sleeping,
+            None => false,
+        }
+    }
+
+    // Needed after moving a sleeping body by hand, changing its velocity through the setters below
+    // wakes it up on its own.
+    pub fn wake_up(&mut self, entity: &Entity) {
+        if let Some(body) = self.rigid_bodies.get_mut(entity) {
+            body.wake_up();
+        }
+    }
+
+    pub fn set_locomotion(&mut self, entity: &Entity, locomotion: Vec3<f32>) {
+        if let Some(body) = self.rigid_bodies.get_mut(entity) {
+            body.locomotion = locomotion;
+
+            if locomotion.length_squared() > 0.0 {
+                body.wake_up();
+            }
+        }
+    }
+
+    pub fn set_gravity_immunity(&mut self, entity: &Entity, immunity: bool) {
+        if let Some(body) = self.rigid_bodies.get_mut(entity) {
+            body.gravity_immune = immunity;
+        }
+    }
+
+    // Pushes the body at its center of mass with `force` newtons during every step of the next
+    // update.
+    pub fn apply_force(&mut self, entity: &Entity, force: Vec3<f32>) {
+        if let Some(body) = self.rigid_bodies.get_mut(entity) {
+            body.force += force;
+            body.wake_up();
+        }
+    }
+
+    // Like `apply_force`, but a force off the center of mass turns the body as well. `point` is in
+    // world space.
+    pub fn apply_force_at_point(
+        &mut self,
+        entity: &Entity,
+        force: Vec3<f32>,
+        point: Vec3<f32>,
+        transformation_system: &TransformationSystem,
+    ) {
+        let center = match self.get_center_of_mass(entity, transformation_system) {
+            Some(c) => c,
+            None => return,
+        };
+
+        if let Some(body) = self.rigid_bodies.get_mut(entity) {
+            body.force += force;
+            body.torque += (point - center).cross(force);
+            body.wake_up();
+        }
+    }
+
+    // Turns the body around its center of mass during every step of the next update.
+    pub fn apply_torque(&mut self, entity: &Entity, torque: Vec3<f32>) {
+        if let Some(body) = self.rigid_bodies.get_mut(entity) {
+            body.torque += torque;
+            body.wake_up();
+        }
+    }
+
+    // Changes the momentum of the body at once, unlike a force.
+    pub fn apply_impulse(&mut self, entity: &Entity, impulse: Vec3<f32>) {
+        if let Some(body) = self.rigid_bodies.get_mut(entity) {
+            body.velocity += impulse * body.inv_mass;
+            body.wake_up();
+        }
+    }
+
+    // See `apply_impulse` and `apply_force_at_point`.
+    pub fn apply_impulse_at_point(
+        &mut self,
+        entity: &Entity,
+        impulse: Vec3<f32>,
+        point: Vec3<f32>,
+        transformation_system: &TransformationSystem,
+    ) {
+        let (position, rotation) = match transformation_system.get_world_pose(entity) {
+            Some(pose) => pose,
+            None => return,
+        };
+
+        if let Some(body) = self.rigid_bodies.get_mut(entity) {
+            let arm = point - body.get_shape_pose(position, rotation).0;
+            let inv_inertia = body.get_world_inv_inertia(&rotation);
+
+            body.velocity += impulse * body.inv_mass;
+            body.angular_velocity += mul_inertia(&inv_inertia, arm.cross(impulse));
+            body.wake_up();
+        }
+    }
+
+    // The center of the collider shape, which bodies turn around.
+    pub fn get_center_of_mass(
+        &self,
+        entity: &Entity,
+        transformation_system: &TransformationSystem,
+    ) -> Option<Vec3<f32>> {
+        let body = self.rigid_bodies.get(entity)?;
+        let (position, rotation) = transformation_system.get_world_pose(entity)?;
+
+        Some(body.get_shape_pose(position, rotation).0)
+    }
+
+    pub fn get_angular_velocity(&self, entity: &Entity) -> Option<Vec3<f32>> {
+        self.rigid_bodies
+            .get(entity)
+            .map(|body| body.angular_velocity)
+    }
+
+    pub fn set_angular_velocity(&mut self, entity: &Entity, angular_velocity: Vec3<f32>) {
+        if let Some(body) = self.rigid_bodies.get_mut(entity) {
+            body.angular_velocity = angular_velocity;
+            body.wake_up();
+        }
+    }
+
+    pub fn set_locked_rotation(&mut self, entity: &Entity, locked: bool) {
+        if let Some(body) = self.rigid_bodies.get_mut(entity) {
+            body.locked_rotation = locked;
+            body.update_inertia();
+
+            if locked {
+                body.angular_velocity = Vec3::new(0.0, 0.0, 0.0);
+            }
+        }
+    }
+
+    pub fn apply_flight_force(&mut self, entity: &Entity, force: f32) {
+        if let Some(body) = self.rigid_bodies.get_mut(entity) {
+            if body.velocity.y < force {
+                body.velocity.y = force;
+                body.wake_up();
+            }
+        }
+    }
+
+    pub fn set_velocity(&mut self, entity: &Entity, velocity: Vec3<f32>) {
+        if let Some(body) = self.rigid_bodies.get_mut(entity) {
+            body.velocity = velocity;
+            body.wake_up();
+        }
+    }
+
+    pub fn entity_has_foothold(&self, entity: &Entity) -> bool {
+        match self.rigid_bodies.get(entity) {
+            Some(body) => body.foothold,
+            None => false,
+        }
+    }
+
+    //ray(origin, direction), only bodies on a layer in `mask` are hit and `user` is skipped
+    pub fn ray_cast(
+        &self,
+        ray: (Vec3<f32>, Vec3<f32>),
+        max_distance: f32,
+        transformation_system: &TransformationSystem,
+        user: Entity,
+        mask: u32,
+    ) -> Option<RayHit> {
+        let mut result: Option<RayHit> = None;
+
+        self.cast_ray(
+            ray,
+            max_distance,
+            transformation_system,
+            (user, mask),
+            |hit| {
+                if result.map_or(true, |r| hit.distance < r.distance) {
+                    result = Some(hit);
+                }
+            },
+        );
+
+        result
+    }
+
+    // Every body along the ray, nearest first.
+    pub fn ray_cast_all(
+        &self,
+        ray: (Vec3<f32>, Vec3<f32>),
+        max_distance: f32,
+        transformation_system: &TransformationSystem,
+        user: Entity,
+        mask: u32,
+    ) -> Vec<RayHit> {
+        let mut result = Vec::new();
+
+        self.cast_ray(
+            ray,
+            max_distance,
+            transformation_system,
+            (user, mask),
+            |hit| result.push(hit),
+        );
+
+        result.sort_by(|a, b| {
+            a.distance
+                .total_cmp(&b.distance)
+                .then(a.entity.to_bits().cmp(&b.entity.to_bits()))
+        });
+        result
+    }
+
+    // Moves a sphere from the origin of the ray along it, the distance of the hit is how far the
+    // sphere got before touching something.
+    pub fn sphere_cast(
+        &self,
+        radius: f32,
+        ray: (Vec3<f32>, Vec3<f32>),
+        max_distance: f32,
+        transformation_system: &TransformationSystem,
+        user: Entity,
+        mask: u32,
+    ) -> Option<RayHit> {
+        self.shape_cast(
+            (ColliderShape::Sphere(radius), Quat::identity()),
+            ray,
+            max_distance,
+            transformation_system,
+            (user, mask),
+        )
+    }
+
+    //shape(extents, rotation), see `sphere_cast`
+    pub fn box_cast(
+        &self,
+        shape: (Vec3<f32>, Quat),
+        ray: (Vec3<f32>, Vec3<f32>),
+        max_distance: f32,
+        transformation_system: &TransformationSystem,
+        user: Entity,
+        mask: u32,
+    ) -> Option<RayHit> {
+        self.shape_cast(
+            (ColliderShape::OrientedBox(shape.0), shape.1),
+            ray,
+            max_distance,
+            transformation_system,
+            (user, mask),
+        )
+    }
+
+    //shape(shape, rotation), filter(user, mask), see `sphere_cast`
+    pub fn shape_cast(
+        &self,
+        shape: (ColliderShape, Quat),
+        ray: (Vec3<f32>, Vec3<f32>),
+        max_distance: f32,
+        transformation_system: &TransformationSystem,
+        filter: (Entity, u32),
+    ) -> Option<RayHit> {
+        let direction = RigidBodySystem::normalized_direction(ray.1)?;
+
+        self.first_shape_hit(
+            shape,
+            (ray.0, direction),
+            max_distance,
+            |collider| self.cast_target_pose(collider, transformation_system, filter),
+            false,
+        )
+    }
+
+    // Bodies overlapping a shape centered at `position`, the normals point from the shape towards
+    // the bodies. Filtered like casts.
+    pub fn find_shape_contacts(
+        &self,
+        shape: (ColliderShape, Quat),
+        position: Vec3<f32>,
+        transformation_system: &TransformationSystem,
+        filter: (Entity, u32),
+    ) -> Vec<(Entity, CollisionManifold)> {
+        let mut contacts = Vec::new();
+
+        for collider in self.rigid_bodies.as_slice().iter() {
+            let pose = match self.cast_target_pose(collider, transformation_system, filter) {
+                Some(pose) => pose,
+                None => continue,
+            };
+
+            if let Some(manifold) = shape
+                .0
+                .colliding((position, shape.1), &collider.shape, pose)
+            {
+                contacts.push((collider.owner, manifold));
+            }
+        }
+
+        contacts
+    }
+
+    //aabb: (position, extents), ray: (origin, direction, direction_inverse)
+    pub fn ray_vs_aabb_intersecting(
+        aabb: &(Vec3<f32>, Vec3<f32>),
+        ray: (&Vec3<f32>, &Vec3<f32>, &Vec3<f32>),
+    ) -> bool {
+        RigidBodySystem::ray_vs_aabb(aabb, ray).is_some()
+    }
+
+    // Entry and exit distance of the ray, measured in lengths of its direction.
+    fn ray_vs_aabb(
+        aabb: &(Vec3<f32>, Vec3<f32>),
+        ray: (&Vec3<f32>, &Vec3<f32>, &Vec3<f32>),
+    ) -> Option<(f32, f32)> {
+        let mut tmin = f32::MIN;
+        let mut tmax = f32::MAX;
+
+        for i in 0..3 {
+            if ray.1[i] == 0.0 {
+                // Parallel to the slab, the inverse would make a NaN out of an origin on a face.
+                if (ray.0[i] - aabb.0[i]).abs() > aabb.1[i] {
+                    return None;
+                }
+
+                continue;
+            }
+
+            let t1 = ((aabb.0[i] - aabb.1[i]) - ray.0[i]) * ray.2[i];
+            let t2 = ((aabb.0[i] + aabb.1[i]) - ray.0[i]) * ray.2[i];
+            tmin = tmin.max(t1.min(t2));
+            tmax = tmax.min(t1.max(t2));
+        }
+
+        if tmax >= tmin.max(0.0) {
+            Some((tmin, tmax))
+        } else {
+            None
+        }
+    }
+
+    fn cast_ray<F: FnMut(RayHit)>(
+        &self,
+        ray: (Vec3<f32>, Vec3<f32>),
+        max_distance: f32,
+        transformation_system: &TransformationSystem,
+        filter: (Entity, u32),
+        mut report: F,
+    ) {
+        let direction = match RigidBodySystem::normalized_direction(ray.1) {
+            Some(d) => d,
+            None => return,
+        };
+
+        for collider in self.rigid_bodies.as_slice().iter() {
+            let pose = match self.cast_target_pose(collider, transformation_system, filter) {
+                Some(pose) => pose,
+                None => continue,
+            };
+
+            if let Some((distance, normal)) = collider.shape.ray_cast(pose, (ray.0, direction)) {
+                if distance <= max_distance {
+                    report(RayHit {
+                        distance,
+                        point: ray.0 + direction * distance,
+                        normal,
+                        entity: collider.owner,
+                    });
+                }
+            }
+        }
+    }
+
+    // The direction of the ray has to be normalized. Bodies `target_pose` returns no pose for are
+    // skipped, as are the ones already touching the shape at the origin if `ignore_touching` is set.
+    fn first_shape_hit<F: Fn(&RigidBody) -> Option<(Vec3<f32>, Quat)>>(
+        &self,
+        shape: (ColliderShape, Quat),
+        ray: (Vec3<f32>, Vec3<f32>),
+        max_distance: f32,
+        target_pose: F,
+        ignore_touching: bool,
+    ) -> Option<RayHit> {
+        let inv = Vec3::new(1.0 / ray.1.x, 1.0 / ray.1.y, 1.0 / ray.1.z);
+        let bounds = shape.0.get_bounding_extents(&shape.1);
+        let step = shape.0.get_cast_step().max(1.0e-3);
+        let mut result: Option<RayHit> = None;
+
+        for collider in self.rigid_bodies.as_slice().iter() {
+            let pose = match target_pose(collider) {
+                Some(pose) => pose,
+                None => continue,
+            };
+            let limit = result.map_or(max_distance, |r| r.distance);
+            let aabb = (
+                pose.0,
+                collider.shape.get_bounding_extents(&pose.1) + bounds,
+            );
+            let range = match RigidBodySystem::ray_vs_aabb(&aabb, (&ray.0, &ray.1, &inv)) {
+                Some((enter, exit)) if enter <= limit => (enter.max(0.0), exit.min(limit)),
+                _ => continue,
+            };
+
+            let touching = |t: f32| {
+                shape
+                    .0
+                    .colliding((ray.0 + ray.1 * t, shape.1), &collider.shape, pose)
+            };
+            let mut previous = None;
+            let mut t = range.0;
+
+            // Marches in steps small enough not to skip anything, then narrows down the first
+            // touching distance by bisection.
+            let hit = loop {
+                if let Some(manifold) = touching(t) {
+                    break match previous {
+                        None if ignore_touching && t == 0.0 => None,
+                        None => Some((t, manifold)),
+                        Some(mut low) => {
+                            let mut high = (t, manifold);
+
+                            for _ in 0..CAST_ITERATIONS {
+                                let middle = (low + high.0) * 0.5;
+
+                                match touching(middle) {
+                                    Some(manifold) => high = (middle, manifold),
+                                    None => low = middle,
+                                }
+                            }
+
+                            Some(high)
+                        }
+                    };
+                }
+
+                if t >= range.1 {
+                    break None;
+                }
+
+                previous = Some(t);
+                t = (t + step).min(range.1);
+            };
+
+            if let Some((distance, manifold)) = hit {
+                result = Some(RayHit {
+                    distance,
+                    point: manifold.point,
+                    normal: -manifold.normal,
+                    entity: collider.owner,
+                });
+            }
+        }
+
+        result
+    }
+
+    // Triggers are never hit by casts, neither are bodies without a transformation.
+    fn cast_target_pose(
+        &self,
+        collider: &RigidBody,
+        transformation_system: &TransformationSystem,
+        filter: (Entity, u32),
+    ) -> Option<(Vec3<f32>, Quat)> {
+        if collider.owner == filter.0
+            || collider.trigger
+            || collider.collision_layer & filter.1 == 0
+        {
+            return None;
+        }
+
+        transformation_system
+            .get_world_pose(&collider.owner)
+            .map(|(position, rotation)| collider.get_shape_pose(position, rotation))
+    }
+
+    fn normalized_direction(direction: Vec3<f32>) -> Option<Vec3<f32>> {
+        let length = direction.length();
+
+        if length > 0.0 {
+            Some(direction * (1.0 / length))
+        } else {
+            None
+        }
+    }
+
+    // How far the accumulator is into the next fixed step, used to interpolate rendered poses.
+    pub fn get_interpolation_factor(&self) -> f32 {
+        (self.timer / self.config.fixed_step).clamp(0.0, 1.0)
+    }
+
+    // Moves the bodies at rows `first..last` by their velocities. The rows are spread over the
+    // worker threads, every body sees the others as they were before the step.
+    pub fn update_colliders(
+        &mut self,
+        first: usize,
+        last: usize,
+        transformation_system: &mut TransformationSystem,
+    ) {
+        let integrations = {
+            let ts = &*transformation_system;
+
+            self.workers.map_ranges(first..last, |rows| {
+                rows.map(|row| self.integrate(row, ts)).collect::<Vec<_>>()
+            })
+        };
+
+        for (row, integration) in (first..last).zip(integrations.into_iter().flatten()) {
+            let integration = match integration {
+                Some(i) => i,
+                None => continue,
+            };
+
+            let collider = &mut self.rigid_bodies.as_mut_slice()[row];
+            collider.velocity = integration.velocity;
+            collider.locomotion = Vec3::new(0.0, 0.0, 0.0);
+
+            if let Some((position, rotation)) = integration.pose {
+                transformation_system.set_world_position(&collider.owner, position);
+
+                if let Some(rotation) = rotation {
+                    transformation_system.set_world_rotation(&collider.owner, rotation);
+                }
+            }
+        }
+    }
+
+    // Where the body at `row` ends up after a step, without changing anything yet. `None` for
+    // bodies without a transformation.
+    fn integrate(
+        &self,
+        row: usize,
+        transformation_system: &TransformationSystem,
+    ) -> Option<Integration> {
+        let collider = &self.rigid_bodies.as_slice()[row];
+        let (position, rotation) = transformation_system.get_world_pose(&collider.owner)?;
+
+        if collider.sleeping {
+            return Some(Integration {
+                velocity: collider.velocity,
+                pose: None,
+            });
+        }
+
+        let mut velocity = collider.velocity;
+        let mut motion = (collider.velocity + collider.locomotion) * self.config.fixed_step;
+        let hit = if collider.continuous
+            && !collider.trigger
+            && motion.length() > collider.shape.get_cast_step()
+        {
+            self.sweep(row, motion, transformation_system)
+        } else {
+            None
+        };
+
+        // Stops at the earliest impact, what is left of the motion is dropped.
+        if let Some((hit, elasticity)) = hit {
+            let distance = motion.length();
+            motion *= (hit.distance + CONTINUOUS_COLLISION_SKIN).min(distance) / distance;
+
+            let normal_vel = velocity.dot(hit.normal);
+
+            if let Some(elasticity) = elasticity.filter(|_| normal_vel < 0.0) {
+                velocity -= hit.normal * ((1.0 + elasticity) * normal_vel);
+            }
+        }
+
+        let mut position = position + motion;
+
+        // Bodies at rest, e.g. all of the level geometry, leave their transformations untouched.
+        if collider.angular_velocity == Vec3::new(0.0, 0.0, 0.0) {
+            return Some(Integration {
+                velocity,
+                pose: (motion != Vec3::new(0.0, 0.0, 0.0)).then_some((position, None)),
+            });
+        }
+
+        let turned = if self.config.deterministic {
+            quat_integrate_portable(
+                &rotation.normalized(),
+                collider.angular_velocity,
+                self.config.fixed_step,
+            )
+        } else {
+            quat_integrate(
+                &rotation.normalized(),
+                collider.angular_velocity,
+                self.config.fixed_step,
+            )
+        };
+
+        // Turns around the center of the shape rather than the origin of the owner.
+        if collider.shape.uses_rotation() {
+            let center = collider.get_shape_pose(position, rotation).0;
+            position = center - quat_rotate_vector(&turned, collider.offset);
+        }
+
+        Some(Integration {
+            velocity,
+            pose: Some((position, Some(turned))),
+        })
+    }
+
+    fn get_thread_count(&self) -> usize {
+        match self.config.threads {
+            0 => self.available_threads,
+            n => n,
+        }
+    }
+
+    fn apply_forces(&mut self, transformation_system: &TransformationSystem) {
+        let step = self.config.fixed_step;
+        let gravity = self.config.gravity * step;
+        let linear_damping = self.config.get_damping_factor(self.config.linear_damping);
+        let angular_damping = self.config.get_damping_factor(self.config.angular_damping);
+
+        for collider in self.rigid_bodies.as_mut_slice().iter_mut() {
+            let (position, rotation) = match transformation_system.get_world_pose(&collider.owner) {
+                Some(pose) => pose,
+                None => continue,
+            };
+
+            collider.in_contact = false;
+            collider.foothold = false;
+            collider.center = collider.get_shape_pose(position, rotation).0;
+            collider.world_inv_inertia = collider.get_world_inv_inertia(&rotation);
+
+            if collider.inv_mass == 0.0 || collider.sleeping {
+                continue;
+            }
+
+            if !collider.gravity_immune {
+                collider.velocity += gravity * collider.gravity_scale;
+            }
+
+            collider.velocity += collider.force * (collider.inv_mass * step);
+            collider.angular_velocity +=
+                mul_inertia(&collider.world_inv_inertia, collider.torque) * step;
+
+            collider.velocity *= linear_damping;
+            collider.angular_velocity *= angular_damping;
+        }
+    }
+
+    // Earliest impact of the body at `index` along `motion`, together with the elasticity of the
+    // impact if the body hit is static. Dynamic bodies are swept at their pose at the start of the
+    // step, bouncing off them is left to the contact solver once they touch.
+    fn sweep(
+        &self,
+        index: usize,
+        motion: Vec3<f32>,
+        transformation_system: &TransformationSystem,
+    ) -> Option<(RayHit, Option<f32>)> {
+        let body = &self.rigid_bodies.as_slice()[index];
+        let (position, rotation) = transformation_system.get_world_pose(&body.owner)?;
+        let pose = body.get_shape_pose(position, rotation);
+        let direction = RigidBodySystem::normalized_direction(motion)?;
+
+        let hit = self.first_shape_hit(
+            (body.shape, pose.1),
+            (pose.0, direction),
+            motion.length(),
+            |other| {
+                if other.trigger || other.owner == body.owner || !body.collides_with(other) {
+                    return None;
+                }
+
+                transformation_system
+                    .get_world_pose(&other.owner)
+                    .map(|(position, rotation)| other.get_shape_pose(position, rotation))
+            },
+            true,
+        )?;
+
+        let elasticity = match self.rigid_bodies.get(&hit.entity) {
+            Some(other) if other.inv_mass != 0.0 => None,
+            Some(other) => Some(CombineMode::combine(
+                (body.elasticity, body.restitution_combine),
+                (other.elasticity, other.restitution_combine),
+            )),
+            None => Some(body.elasticity),
+        };
+
+        Some((hit, elasticity))
+    }
+
+    pub fn update(&mut self, dt: f32, transformation_system: &mut TransformationSystem) {
+        self.timer += dt;
+        self.events.clear();
+        self.joint_events.clear();
+        self.step_hashes.clear();
+
+        // Workers are only started or stopped here, when the thread count has changed.
+        self.workers.resize(self.get_thread_count());
+
+        let mut substeps = 0;
+
+        while self.config.fixed_step > 0.0 && self.timer >= self.config.fixed_step {
+            if substeps == self.config.max_substeps {
+                self.timer %= self.config.fixed_step;
+                break;
+            }
+
+            substeps += 1;
+            self.timer -= self.config.fixed_step;
+            transformation_system.save_previous_poses();
+
+            self.apply_forces(transformation_system);
+
+            let mut pairs = std::mem::take(&mut self.pairs);
+            let mut touching = Vec::new();
+            let mut resolved = Vec::new();
+            self.find_pairs(transformation_system, &mut pairs);
+
+            let rigid_bodies = self.rigid_bodies.as_mut_slice();
+
+            // Contacts are found before the bodies move, so their velocities can be solved first.
+            for &(i, j) in pairs.iter() {
+                // Bodies whose transformation has been removed take no part in collisions.
+                let pose_1 = match transformation_system.get_world_pose(&rigid_bodies[i].owner) {
+                    Some(pose) => pose,
+                    None => continue,
+                };
+                let pose_2 = match transformation_system.get_world_pose(&rigid_bodies[j].owner) {
+                    Some(pose) => pose,
+                    None => continue,
+                };
+
+                if let Some(manifold) =
+                    rigid_bodies[i].colliding(&rigid_bodies[j], (pose_1, pose_2))
+                {
+                    let trigger = rigid_bodies[i].trigger || rigid_bodies[j].trigger;
+
+                    touching.push(Contact {
+                        entities: (rigid_bodies[i].owner, rigid_bodies[j].owner),
+                        manifold,
+                        trigger,
+                        normal_impulse: 0.0,
+                        friction_impulse: Vec3::new(0.0, 0.0, 0.0),
+                    });
+
+                    if trigger {
+                        continue;
+                    }
+
+                    if rigid_bodies[i].inv_mass == 0.0 && manifold.normal.y >= FOOTHOLD_MIN_NORMAL_Y
+                    {
+                        rigid_bodies[j].foothold = true;
+                    } else if rigid_bodies[j].inv_mass == 0.0
+                        && manifold.normal.y <= -FOOTHOLD_MIN_NORMAL_Y
+                    {
+                        rigid_bodies[i].foothold = true;
+                    }
+
+                    rigid_bodies[i].in_contact = true;
+                    rigid_bodies[j].in_contact = true;
+
+                    // A body moving into a sleeping one wakes it up.
+                    for (a, b) in [(i, j), (j, i)] {
+                        if rigid_bodies[a].sleeping
+                            && rigid_bodies[b].inv_mass > 0.0
+                            && !rigid_bodies[b].sleeping
+                            && rigid_bodies[b].velocity.length_squared() > SLEEP_SPEED * SLEEP_SPEED
+                        {
+                            rigid_bodies[a].wake_up();
+                        }
+                    }
+
+                    resolved.push(((i, j), manifold, touching.len() - 1));
+                }
+            }
+
+            let mut constraints = Vec::with_capacity(resolved.len());
+
+            for (rows, manifold, contact) in resolved.iter() {
+                let bodies = self.rigid_bodies.as_slice();
+                let inv_masses = (
+                    bodies[rows.0].get_solver_inv_mass(),
+                    bodies[rows.1].get_solver_inv_mass(),
+                );
+                let impulses = self.get_cached_impulses(&touching[*contact].entities);
+
+                constraints.push(ContactConstraint::new(
+                    *rows, *manifold, bodies, inv_masses, impulses,
+                ));
+            }
+
+            let mut joint_rows = Vec::new();
+            self.add_joint_rows(transformation_system, &mut joint_rows);
+
+            let rigid_bodies = self.rigid_bodies.as_mut_slice();
+
+            for row in joint_rows.iter() {
+                // Jointed bodies are held, they don't wake up for a lack of contacts.
+                for body in [row.get_rows().0, row.get_rows().1].iter().flatten() {
+                    rigid_bodies[*body].in_contact = true;
+                }
+
+                row.warm_start(rigid_bodies);
+            }
+
+            for constraint in constraints.iter() {
+                constraint.warm_start(rigid_bodies);
+            }
+
+            for _ in 0..self.config.solver_iterations {
+                for row in joint_rows.iter_mut() {
+                    row.solve(rigid_bodies);
+                }
+
+                for constraint in constraints.iter_mut() {
+                    constraint.solve(rigid_bodies);
+                }
+            }
+
+            self.store_joint_impulses(&joint_rows);
+
+            let rigid_bodies = self.rigid_bodies.as_mut_slice();
+
+            for (constraint, (_, _, contact)) in constraints.iter().zip(resolved.iter()) {
+                let corrections = constraint.get_corrections();
+
+                for (row, correction) in [
+                    (constraint.rows.0, -corrections.0),
+                    (constraint.rows.1, corrections.1),
+                ] {
+                    let owner = &rigid_bodies[row].owner;
+
+                    if let Some(position) = transformation_system.get_world_position(owner) {
+                        transformation_system.set_world_position(owner, position + correction);
+                    }
+                }
+
+                touching[*contact].normal_impulse = constraint.get_normal_impulse();
+                touching[*contact].friction_impulse = constraint.get_friction_impulse();
+            }
+
+            self.update_colliders(0, self.rigid_bodies.len(), transformation_system);
+
+            self.pairs = pairs;
+            self.report_contacts(touching);
+            self.update_sleeping(&resolved, &joint_rows);
+
+            if self.config.deterministic {
+                let hash = self.get_state_hash(transformation_system);
+                self.step_hashes.push(hash);
+            }
+        }
+
+        // Forces last for the whole update they were applied before.
+        if substeps > 0 {
+            for body in self.rigid_bodies.as_mut_slice().iter_mut() {
+                body.force = Vec3::new(0.0, 0.0, 0.0);
+                body.torque = Vec3::new(0.0, 0.0, 0.0);
+            }
+        }
+    }
+
+    // Center of mass and rotated unit axes of the body of `entity`, together with its row. The
+    // world sits at the origin without turning.
+    fn get_joint_frame(
+        &self,
+        entity: Option<&Entity>,
+        transformation_system: &TransformationSystem,
+    ) -> Option<(Option<usize>, Frame)> {
+        let entity = match entity {
+            Some(e) => e,
+            None => {
+                return Some((
+                    None,
+                    (
+                        Vec3::new(0.0, 0.0, 0.0),
+                        [
+                            Vec3::new(1.0, 0.0, 0.0),
+                            Vec3::new(0.0, 1.0, 0.0),
+                            Vec3::new(0.0, 0.0, 1.0),
+                        ],
+                    ),
+                ))
+            }
+        };
+
+        let row = self.rigid_bodies.index_of(entity)?;
+        let (position, rotation) = transformation_system.get_world_pose(entity)?;
+        let body = &self.rigid_bodies.as_slice()[row];
+        let rotation = rotation.normalized();
+
+        Some((
+            Some(row),
+            (
+                body.get_shape_pose(position, rotation).0,
+                [
+                    quat_rotate_vector(&rotation, Vec3::new(1.0, 0.0, 0.0)),
+                    quat_rotate_vector(&rotation, Vec3::new(0.0, 1.0, 0.0)),
+                    quat_rotate_vector(&rotation, Vec3::new(0.0, 0.0, 1.0)),
+                ],
+            ),
+        ))
+    }
+
+    fn add_joint_rows(
+        &self,
+        transformation_system: &TransformationSystem,
+        rows: &mut Vec<JointRow>,
+    ) {
+        for (index, joint) in self.joints.iter().enumerate() {
+            let entities = joint.get_entities();
+            let frames = (
+                self.get_joint_frame(Some(&entities.0), transformation_system),
+                self.get_joint_frame(entities.1.as_ref(), transformation_system),
+            );
+
+            if let (Some((a, frame_a)), Some((b, frame_b))) = frames {
+                joint.add_rows(
+                    index,
+                    self.rigid_bodies.as_slice(),
+                    (a, b),
+                    (frame_a, frame_b),
+                    &self.config,
+                    rows,
+                );
+            }
+        }
+    }
+
+    // Keeps the impulses of the rows for the next step and breaks the joints that held on too hard.
+    fn store_joint_impulses(&mut self, rows: &[JointRow]) {
+        let step = self.config.fixed_step;
+        let mut start = 0;
+
+        for (index, joint) in self.joints.iter_mut().enumerate() {
+            let count = rows[start..]
+                .iter()
+                .take_while(|row| row.joint == index)
+                .count();
+            joint.store_impulses(&rows[start..start + count]);
+            start += count;
+        }
+
+        let joint_events = &mut self.joint_events;
+
+        self.joints.retain(|joint| match joint.get_break_force() {
+            Some(limit) if joint.get_force(step) > limit => {
+                joint_events.push(JointBreakEvent {
+                    joint: joint.get_handle(),
+                    entities: joint.get_entities(),
+                    force: joint.get_force(step),
+                });
+
+                false
+            }
+            _ => true,
+        });
+    }
+
+    // Joints of bodies that no longer exist go with them.
+    fn remove_dangling_joints(&mut self) {
+        let rigid_bodies = &self.rigid_bodies;
+
+        self.joints.retain(|joint| {
+            let (a, b) = joint.get_entities();
+
+            rigid_bodies.contains(&a) && b.map_or(true, |b| rigid_bodies.contains(&b))
+        });
+    }
+
+    // Impulses the pair ended the last step with, relative to the order of `entities`.
+    fn get_cached_impulses(&self, entities: &(Entity, Entity)) -> (f32, Vec3<f32>) {
+        let swapped = entities.0.to_bits() > entities.1.to_bits();
+        let key = if swapped {
+            (entities.1.to_bits(), entities.0.to_bits())
+        } else {
+            (entities.0.to_bits(), entities.1.to_bits())
+        };
+
+        match self
+            .contacts
+            .binary_search_by_key(&key, |c| (c.entities.0.to_bits(), c.entities.1.to_bits()))
+        {
+            Ok(index) if !swapped => (
+                self.contacts[index].normal_impulse,
+                self.contacts[index].friction_impulse,
+            ),
+            Ok(index) => (
+                self.contacts[index].normal_impulse,
+                -self.contacts[index].friction_impulse,
+            ),
+            Err(_) => (0.0, Vec3::new(0.0, 0.0, 0.0)),
+        }
+    }
+
+    // Bodies touching each other sleep and wake up together, otherwise a body falling asleep
+    // under a stack would suddenly carry all of it alone. Sleeping bodies that lost all their
+    // contacts wake up, so they don't hang in the air.
+    fn update_sleeping(
+        &mut self,
+        resolved: &[((usize, usize), CollisionManifold, usize)],
+        joint_rows: &[JointRow],
+    ) {
+        let step = self.config.fixed_step;
+        let rigid_bodies = self.rigid_bodies.as_mut_slice();
+        let mut islands: Vec<usize> = (0..rigid_bodies.len()).collect();
+
+        fn find(islands: &mut [usize], mut row: usize) -> usize {
+            while islands[row] != row {
+                islands[row] = islands[islands[row]];
+                row = islands[row];
+            }
+
+            row
+        }
+
+        let pairs = resolved
+            .iter()
+            .map(|(rows, _, _)| *rows)
+            .chain(joint_rows.iter().filter_map(|row| match row.get_rows() {
+                (Some(i), Some(j)) => Some((i, j)),
+                _ => None,
+            }));
+
+        for (i, j) in pairs {
+            if rigid_bodies[i].inv_mass > 0.0 && rigid_bodies[j].inv_mass > 0.0 {
+                let (a, b) = (find(&mut islands, i), find(&mut islands, j));
+                islands[a.max(b)] = a.min(b);
+            }
+        }
+
+        // Whether any body of the island is awake, and whether all of them are ready to sleep.
+        let mut states = vec![(false, true); rigid_bodies.len()];
+
+        for (row, body) in rigid_bodies.iter_mut().enumerate() {
+            if body.inv_mass == 0.0 {
+                continue;
+            }
+
+            if body.sleeping && !body.in_contact {
+                body.wake_up();
+            }
+
+            if !body.sleeping {
+                if !body.never_sleeping
+                    && body.velocity.length_squared() < SLEEP_SPEED * SLEEP_SPEED
+                    && body.angular_velocity.length_squared() < SLEEP_SPEED * SLEEP_SPEED
+                {
+                    body.sleep_timer += step;
+                } else {
+                    body.sleep_timer = 0.0;
+                }
+            }
+
+            let island = find(&mut islands, row);
+            states[island].0 |= !body.sleeping;
+            states[island].1 &= body.sleeping || body.sleep_timer >= SLEEP_DELAY;
+        }
+
+        for (row, body) in rigid_bodies.iter_mut().enumerate() {
+            if body.inv_mass == 0.0 {
+                continue;
+            }
+
+            match states[find(&mut islands, row)] {
+                (true, true) => {
+                    body.sleeping = true;
+                    body.velocity = Vec3::new(0.0, 0.0, 0.0);
+                    body.angular_velocity = Vec3::new(0.0, 0.0, 0.0);
+                }
+                (true, false) if body.sleeping => body.wake_up(),
+                _ => (),
+            }
+        }
+    }
+
+    // Compares the contacts of a step with the ones of the previous step and records the events.
+    fn report_contacts(&mut self, mut touching: Vec<Contact>) {
+        for contact in touching.iter_mut() {
+            if contact.entities.0.to_bits() > contact.entities.1.to_bits() {
+                contact.entities = (contact.entities.1, contact.entities.0);
+                contact.manifold = contact.manifold.flipped();
+                contact.friction_impulse = -contact.friction_impulse;
+            }
+        }
+
+        touching.sort_unstable_by_key(|c| (c.entities.0.to_bits(), c.entities.1.to_bits()));
+
+        let previous = std::mem::replace(&mut self.contacts, touching);
+        let key = |c: &Contact| (c.entities.0.to_bits(), c.entities.1.to_bits());
+
+        for contact in self.contacts.iter() {
+            let kind = match previous.binary_search_by_key(&key(contact), key) {
+                Ok(_) => CollisionEventKind::Stay,
+                Err(_) => CollisionEventKind::Begin,
+            };
+
+            self.events.push(CollisionEvent {
+                kind,
+                entities: contact.entities,
+                manifold: contact.manifold,
+                trigger: contact.trigger,
+            });
+        }
+
+        for contact in previous.iter() {
+            if self
+                .contacts
+                .binary_search_by_key(&key(contact), key)
+                .is_err()
+            {
+                self.events.push(CollisionEvent {
+                    kind: CollisionEventKind::End,
+                    entities: contact.entities,
+                    manifold: contact.manifold,
+                    trigger: contact.trigger,
+                });
+            }
+        }
+    }
+}
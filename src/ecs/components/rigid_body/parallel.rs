@@ -0,0 +1,161 @@
+use std::iter;
+use std::mem;
+use std::ops::Range;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::mpsc::{self, Sender};
+use std::thread::{self, JoinHandle};
+
+// Fewer rows than this per thread aren't worth handing to a worker.
+const MIN_ROWS_PER_THREAD: usize = 256;
+
+type Job = Box<dyn FnOnce() + Send + 'static>;
+
+struct Worker {
+    jobs: Sender<Job>,
+    thread: JoinHandle<()>,
+}
+
+impl Worker {
+    fn start() -> Option<Worker> {
+        let (jobs, queue) = mpsc::channel::<Job>();
+        let thread = thread::Builder::new()
+            .name(String::from("physics worker"))
+            .spawn(move || {
+                for job in queue {
+                    job();
+                }
+            })
+            .ok()?;
+
+        Some(Worker { jobs, thread })
+    }
+
+    // Lets the worker finish what it has been given and waits for it to quit.
+    fn stop(self) {
+        drop(self.jobs);
+        let _ = self.thread.join();
+    }
+}
+
+// Threads that are started once and kept around for every step, rather than started for every
+// call. The calling thread always takes part in the work, so a pool for `threads` threads runs
+// `threads - 1` workers.
+pub(super) struct WorkerPool {
+    workers: Vec<Worker>,
+}
+
+impl WorkerPool {
+    pub(super) fn new() -> WorkerPool {
+        WorkerPool {
+            workers: Vec::new(),
+        }
+    }
+
+    pub(super) fn get_thread_count(&self) -> usize {
+        self.workers.len() + 1
+    }
+
+    // Starts or stops workers until the pool runs `threads` threads. Fewer are run if the system
+    // refuses to start more.
+    pub(super) fn resize(&mut self, threads: usize) {
+        let workers = threads.max(1) - 1;
+
+        while self.workers.len() > workers {
+            if let Some(worker) = self.workers.pop() {
+                worker.stop();
+            }
+        }
+
+        while self.workers.len() < workers {
+            match Worker::start() {
+                Some(worker) => self.workers.push(worker),
+                None => break,
+            }
+        }
+    }
+
+    // Splits `rows` into one contiguous range per thread, the calling thread taking the first
+    // one, and runs `work` on each of them. The results come back in the order of the ranges, so
+    // concatenating them gives the same result no matter how many threads did the work.
+    pub(super) fn map_ranges<T, F>(&self, rows: Range<usize>, work: F) -> Vec<T>
+    where
+        T: Send,
+        F: Fn(Range<usize>) -> T + Sync,
+    {
+        let threads = self
+            .get_thread_count()
+            .min(rows.len() / MIN_ROWS_PER_THREAD)
+            .max(1);
+
+        if threads == 1 {
+            return vec![work(rows)];
+        }
+
+        let size = rows.len().div_ceil(threads);
+        let ranges: Vec<Range<usize>> = (0..threads)
+            .map(|i| (rows.start + i * size)..(rows.start + (i + 1) * size).min(rows.end))
+            .collect();
+        let work = &work;
+
+        let pending: Vec<_> = ranges[1..]
+            .iter()
+            .zip(self.workers.iter())
+            .map(|(range, worker)| {
+                let range = range.clone();
+                let (result, receiver) = mpsc::channel();
+                let job: Box<dyn FnOnce() + Send + '_> = Box::new(move || {
+                    let _ = result.send(panic::catch_unwind(AssertUnwindSafe(|| work(range))));
+                });
+
+                // SAFETY: The job borrows `work`, which outlives this call. Every job sent is
+                // waited for below before returning, even if the work panics, so no job can
+                // still be running once the borrow ends.
+                let job: Job = unsafe { mem::transmute(job) };
+
+                // A worker that is gone hands the job back, it is run right away instead.
+                if let Err(mpsc::SendError(job)) = worker.jobs.send(job) {
+                    job();
+                }
+
+                receiver
+            })
+            .collect();
+
+        let first = panic::catch_unwind(AssertUnwindSafe(|| work(ranges[0].clone())));
+
+        let rest: Vec<_> = pending
+            .into_iter()
+            .map(|receiver| receiver.recv())
+            .collect();
+        let mut results = Vec::with_capacity(threads);
+
+        for result in iter::once(Ok(first)).chain(rest) {
+            match result {
+                Ok(Ok(result)) => results.push(result),
+                Ok(Err(payload)) => panic::resume_unwind(payload),
+                Err(_) => panic!("A physics worker quit without finishing its job!"),
+            }
+        }
+
+        results
+    }
+}
+
+impl Default for WorkerPool {
+    fn default() -> WorkerPool {
+        WorkerPool::new()
+    }
+}
+
+impl Drop for WorkerPool {
+    fn drop(&mut self) {
+        for worker in self.workers.drain(..) {
+            worker.stop();
+        }
+    }
+}
+
+// The number of threads the machine runs at once, 1 if it can't be told.
+pub(super) fn available_threads() -> usize {
+    thread::available_parallelism().map_or(1, |n| n.get())
+}
@@ -0,0 +1,820 @@
+use super::super::super::super::utilities::quat_rotate_vector;
+use gamemath::{Quat, Vec3};
+
+const EPSILON: f32 = 1.0e-6;
+const CONTACT_TOLERANCE: f32 = 1.0e-3;
+pub(super) const MAX_CONTACT_POINTS: usize = 4;
+const SEARCH_ITERATIONS: usize = 24;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ColliderShape {
+    // Half extents of a box that stays axis aligned no matter how the entity is rotated.
+    Box(Vec3<f32>),
+    // Half extents of a box that follows the rotation of the entity.
+    OrientedBox(Vec3<f32>),
+    Sphere(f32),
+    // A cylinder capped by half spheres, standing along the local y axis. `half_height` is half
+    // the length of the cylinder part.
+    Capsule { radius: f32, half_height: f32 },
+}
+
+// `normal` points from the first body towards the second one, `point` lies in the middle of the
+// overlapping region.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CollisionManifold {
+    pub(super) penetration: f32,
+    pub(super) normal: Vec3<f32>,
+    pub(super) point: Vec3<f32>,
+    // Boxes lying flat on each other touch in several points, which keeps them from rocking.
+    pub(super) points: [Vec3<f32>; MAX_CONTACT_POINTS],
+    pub(super) point_count: usize,
+}
+
+impl CollisionManifold {
+    fn new(penetration: f32, normal: Vec3<f32>, point: Vec3<f32>) -> CollisionManifold {
+        CollisionManifold {
+            penetration,
+            normal,
+            point,
+            points: [point; MAX_CONTACT_POINTS],
+            point_count: 1,
+        }
+    }
+
+    // `point` becomes the average of `points`, which must not be empty.
+    fn with_points(penetration: f32, normal: Vec3<f32>, points: &[Vec3<f32>]) -> CollisionManifold {
+        let mut manifold = CollisionManifold::new(penetration, normal, points[0]);
+        let mut sum = Vec3::new(0.0, 0.0, 0.0);
+
+        for (i, p) in points.iter().take(MAX_CONTACT_POINTS).enumerate() {
+            manifold.points[i] = *p;
+            sum += *p;
+        }
+
+        manifold.point_count = points.len().min(MAX_CONTACT_POINTS);
+        manifold.point = sum * (1.0 / manifold.point_count as f32);
+        manifold
+    }
+
+    pub fn get_points(&self) -> &[Vec3<f32>] {
+        &self.points[..self.point_count]
+    }
+
+    pub fn get_penetration(&self) -> f32 {
+        self.penetration
+    }
+
+    pub fn get_normal(&self) -> Vec3<f32> {
+        self.normal
+    }
+
+    pub fn get_point(&self) -> Vec3<f32> {
+        self.point
+    }
+
+    pub(super) fn flipped(mut self) -> CollisionManifold {
+        self.normal = -self.normal;
+        self
+    }
+}
+
+struct OrientedBox {
+    center: Vec3<f32>,
+    axes: [Vec3<f32>; 3],
+    extents: [f32; 3],
+}
+
+// A sphere swept along a segment, which covers both spheres and capsules.
+struct SweptSphere {
+    start: Vec3<f32>,
+    end: Vec3<f32>,
+    radius: f32,
+}
+
+enum Volume {
+    Box(OrientedBox),
+    Swept(SweptSphere),
+}
+
+impl ColliderShape {
+    pub fn uses_rotation(&self) -> bool {
+        !matches!(self, ColliderShape::Box(_) | ColliderShape::Sphere(_))
+    }
+
+    // Half extents in the local space of the shape.
+    pub fn get_extents(&self) -> Vec3<f32> {
+        match *self {
+            ColliderShape::Box(extents) | ColliderShape::OrientedBox(extents) => extents,
+            ColliderShape::Sphere(radius) => Vec3::new(radius, radius, radius),
+            ColliderShape::Capsule {
+                radius,
+                half_height,
+            } => Vec3::new(radius, radius + half_height, radius),
+        }
+    }
+
+    // Half extents of the world space bounding box of the shape under `rotation`.
+    pub fn get_bounding_extents(&self, rotation: &Quat) -> Vec3<f32> {
+        match *self {
+            ColliderShape::Box(extents) => extents,
+            ColliderShape::Sphere(radius) => Vec3::new(radius, radius, radius),
+            ColliderShape::OrientedBox(extents) => {
+                let axes = rotated_axes(rotation);
+                let mut result = Vec3::new(0.0, 0.0, 0.0);
+
+                for i in 0..3 {
+                    result.x += (axes[i].x * extents[i]).abs();
+                    result.y += (axes[i].y * extents[i]).abs();
+                    result.z += (axes[i].z * extents[i]).abs();
+                }
+
+                result
+            }
+            ColliderShape::Capsule {
+                radius,
+                half_height,
+            } => {
+                let up = quat_rotate_vector(rotation, Vec3::new(0.0, half_height, 0.0));
+
+                Vec3::new(
+                    up.x.abs() + radius,
+                    up.y.abs() + radius,
+                    up.z.abs() + radius,
+                )
+            }
+        }
+    }
+
+    // Poses are (center, rotation) of each shape.
+    pub fn colliding(
+        &self,
+        pose: (Vec3<f32>, Quat),
+        other: &ColliderShape,
+        other_pose: (Vec3<f32>, Quat),
+    ) -> Option<CollisionManifold> {
+        if let (ColliderShape::Box(extents), ColliderShape::Box(other_extents)) = (self, other) {
+            return aabb_vs_aabb((pose.0, *extents), (other_pose.0, *other_extents));
+        }
+
+        match (self.volume(pose), other.volume(other_pose)) {
+            (Volume::Swept(a), Volume::Swept(b)) => swept_vs_swept(&a, &b),
+            (Volume::Box(a), Volume::Box(b)) => obb_vs_obb(&a, &b),
+            (Volume::Box(a), Volume::Swept(b)) => obb_vs_swept(&a, &b),
+            (Volume::Swept(a), Volume::Box(b)) => obb_vs_swept(&b, &a).map(|m| m.flipped()),
+        }
+    }
+
+    // Principal moments of inertia around the local axes through the center, for a solid shape.
+    pub fn get_inertia(&self, mass: f32) -> Vec3<f32> {
+        match *self {
+            ColliderShape::Box(e) | ColliderShape::OrientedBox(e) => Vec3::new(
+                mass / 3.0 * (e.y * e.y + e.z * e.z),
+                mass / 3.0 * (e.x * e.x + e.z * e.z),
+                mass / 3.0 * (e.x * e.x + e.y * e.y),
+            ),
+            ColliderShape::Sphere(radius) => {
+                let inertia = 0.4 * mass * radius * radius;
+
+                Vec3::new(inertia, inertia, inertia)
+            }
+            ColliderShape::Capsule {
+                radius,
+                half_height,
+            } => {
+                // The cylinder and the two half spheres share the mass by volume.
+                let height = half_height * 2.0;
+                let cylinder = height;
+                let spheres = 4.0 / 3.0 * radius;
+                let cylinder_mass = mass * cylinder / (cylinder + spheres);
+                let spheres_mass = mass - cylinder_mass;
+                let r2 = radius * radius;
+
+                let axial = cylinder_mass * r2 * 0.5 + spheres_mass * 0.4 * r2;
+                let lateral = cylinder_mass * (height * height / 12.0 + r2 * 0.25)
+                    + spheres_mass * (0.4 * r2 + height * height * 0.25 + 0.375 * height * radius);
+
+                Vec3::new(lateral, axial, lateral)
+            }
+        }
+    }
+
+    // Distance along the ray to where it enters the shape, together with the surface normal there.
+    // The direction has to be normalized, rays starting inside the shape hit at distance zero.
+    pub fn ray_cast(
+        &self,
+        pose: (Vec3<f32>, Quat),
+        ray: (Vec3<f32>, Vec3<f32>),
+    ) -> Option<(f32, Vec3<f32>)> {
+        match self.volume(pose) {
+            Volume::Box(b) => ray_vs_obb(&b, ray),
+            Volume::Swept(s) => ray_vs_swept(&s, ray),
+        }
+    }
+
+    // The largest distance the shape can move without skipping over anything it would touch.
+    pub fn get_cast_step(&self) -> f32 {
+        match *self {
+            ColliderShape::Box(extents) | ColliderShape::OrientedBox(extents) => {
+                extents.x.min(extents.y).min(extents.z)
+            }
+            ColliderShape::Sphere(radius) | ColliderShape::Capsule { radius, .. } => radius,
+        }
+    }
+
+    fn volume(&self, pose: (Vec3<f32>, Quat)) -> Volume {
+        match *self {
+            ColliderShape::Box(extents) => Volume::Box(OrientedBox {
+                center: pose.0,
+                axes: rotated_axes(&Quat::identity()),
+                extents: [extents.x, extents.y, extents.z],
+            }),
+            ColliderShape::OrientedBox(extents) => Volume::Box(OrientedBox {
+                center: pose.0,
+                axes: rotated_axes(&pose.1),
+                extents: [extents.x, extents.y, extents.z],
+            }),
+            ColliderShape::Sphere(radius) => Volume::Swept(SweptSphere {
+                start: pose.0,
+                end: pose.0,
+                radius,
+            }),
+            ColliderShape::Capsule {
+                radius,
+                half_height,
+            } => {
+                let up = quat_rotate_vector(&pose.1, Vec3::new(0.0, half_height, 0.0));
+
+                Volume::Swept(SweptSphere {
+                    start: pose.0 - up,
+                    end: pose.0 + up,
+                    radius,
+                })
+            }
+        }
+    }
+}
+
+impl OrientedBox {
+    fn projected_radius(&self, axis: Vec3<f32>) -> f32 {
+        (0..3)
+            .map(|i| self.extents[i] * self.axes[i].dot(axis).abs())
+            .sum()
+    }
+
+    fn closest_point(&self, point: Vec3<f32>) -> Vec3<f32> {
+        let d = point - self.center;
+        let mut result = self.center;
+
+        for i in 0..3 {
+            let distance = d.dot(self.axes[i]).clamp(-self.extents[i], self.extents[i]);
+            result += self.axes[i] * distance;
+        }
+
+        result
+    }
+
+    fn vertices(&self) -> [Vec3<f32>; 8] {
+        let x = self.axes[0] * self.extents[0];
+        let y = self.axes[1] * self.extents[1];
+        let z = self.axes[2] * self.extents[2];
+        let c = self.center;
+
+        [
+            c - x - y - z,
+            c + x - y - z,
+            c - x + y - z,
+            c + x + y - z,
+            c - x - y + z,
+            c + x - y + z,
+            c - x + y + z,
+            c + x + y + z,
+        ]
+    }
+
+    // The average of the vertices reaching furthest along `direction`, so a face touching flat on
+    // something yields its center rather than an arbitrary corner.
+    fn support_point(&self, direction: Vec3<f32>) -> Vec3<f32> {
+        let vertices = self.vertices();
+        let max = vertices
+            .iter()
+            .map(|v| v.dot(direction))
+            .fold(f32::MIN, f32::max);
+        let mut sum = Vec3::new(0.0, 0.0, 0.0);
+        let mut count = 0.0;
+
+        for v in vertices.iter() {
+            if v.dot(direction) >= max - CONTACT_TOLERANCE {
+                sum += *v;
+                count += 1.0;
+            }
+        }
+
+        sum * (1.0 / count)
+    }
+
+    // The face whose normal points furthest along `direction`, as its four corners in order.
+    fn face(&self, direction: Vec3<f32>) -> [Vec3<f32>; 4] {
+        let mut axis = 0;
+
+        for i in 1..3 {
+            if self.axes[i].dot(direction).abs() > self.axes[axis].dot(direction).abs() {
+                axis = i;
+            }
+        }
+
+        let sign = if self.axes[axis].dot(direction) < 0.0 {
+            -1.0
+        } else {
+            1.0
+        };
+        let (u, v) = ((axis + 1) % 3, (axis + 2) % 3);
+        let c = self.center + self.axes[axis] * (self.extents[axis] * sign);
+        let u = self.axes[u] * self.extents[u];
+        let v = self.axes[v] * self.extents[v];
+
+        [c - u - v, c + u - v, c + u + v, c - u + v]
+    }
+
+    // Where the face of `incident` facing against `normal` reaches below the face of this box
+    // facing along `normal`, moved by `offset`. The sides of the face are clipped off one by one,
+    // and at most four of the remaining corners are kept, the ones furthest out.
+    fn clipped_face_points(
+        &self,
+        incident: &OrientedBox,
+        normal: Vec3<f32>,
+        offset: Vec3<f32>,
+    ) -> Vec<Vec3<f32>> {
+        let mut polygon: Vec<Vec3<f32>> = incident.face(-normal).to_vec();
+        let reference = self.face(normal);
+        let mut sides = Vec::with_capacity(4);
+
+        for i in 0..3 {
+            if self.axes[i].dot(normal).abs() < 0.5 {
+                sides.push((self.axes[i], self.extents[i]));
+                sides.push((-self.axes[i], self.extents[i]));
+            }
+        }
+
+        for (axis, extent) in sides.iter() {
+            let distance = |p: &Vec3<f32>| (*p - self.center).dot(*axis) - extent;
+            let mut clipped = Vec::with_capacity(polygon.len() + 1);
+
+            for (i, p) in polygon.iter().enumerate() {
+                let q = &polygon[(i + 1) % polygon.len()];
+                let (dp, dq) = (distance(p), distance(q));
+
+                if dp <= 0.0 {
+                    clipped.push(*p);
+                }
+
+                if (dp < 0.0 && dq > 0.0) || (dp > 0.0 && dq < 0.0) {
+                    clipped.push(*p + (*q - *p) * (dp / (dp - dq)));
+                }
+            }
+
+            polygon = clipped;
+
+            if polygon.is_empty() {
+                return polygon;
+            }
+        }
+
+        polygon.retain(|p| (*p - reference[0]).dot(normal) <= CONTACT_TOLERANCE);
+
+        if polygon.len() > MAX_CONTACT_POINTS {
+            let mut kept = Vec::with_capacity(MAX_CONTACT_POINTS);
+
+            for (axis, _) in sides.iter() {
+                let furthest = polygon
+                    .iter()
+                    .copied()
+                    .max_by(|p, q| p.dot(*axis).total_cmp(&q.dot(*axis)));
+
+                if let Some(p) = furthest {
+                    if !kept.contains(&p) {
+                        kept.push(p);
+                    }
+                }
+            }
+
+            polygon = kept;
+        }
+
+        for p in polygon.iter_mut() {
+            *p += offset;
+        }
+
+        polygon
+    }
+}
+
+fn rotated_axes(rotation: &Quat) -> [Vec3<f32>; 3] {
+    [
+        quat_rotate_vector(rotation, Vec3::new(1.0, 0.0, 0.0)),
+        quat_rotate_vector(rotation, Vec3::new(0.0, 1.0, 0.0)),
+        quat_rotate_vector(rotation, Vec3::new(0.0, 0.0, 1.0)),
+    ]
+}
+
+//aabb: (position, extents)
+fn aabb_vs_aabb(a: (Vec3<f32>, Vec3<f32>), b: (Vec3<f32>, Vec3<f32>)) -> Option<CollisionManifold> {
+    let direction = b.0 - a.0;
+    let overlap = Vec3::new(
+        a.1.x + b.1.x - direction.x.abs(),
+        a.1.y + b.1.y - direction.y.abs(),
+        a.1.z + b.1.z - direction.z.abs(),
+    );
+
+    if overlap.x > 0.0 && overlap.y > 0.0 && overlap.z > 0.0 {
+        let min = Vec3::new(
+            (a.0.x - a.1.x).max(b.0.x - b.1.x),
+            (a.0.y - a.1.y).max(b.0.y - b.1.y),
+            (a.0.z - a.1.z).max(b.0.z - b.1.z),
+        );
+        let max = Vec3::new(
+            (a.0.x + a.1.x).min(b.0.x + b.1.x),
+            (a.0.y + a.1.y).min(b.0.y + b.1.y),
+            (a.0.z + a.1.z).min(b.0.z + b.1.z),
+        );
+        let mut manifold = CollisionManifold::new(
+            overlap.x.min(overlap.y.min(overlap.z)),
+            Vec3::new(0.0, 0.0, 0.0),
+            (min + max) * 0.5,
+        );
+
+        if manifold.penetration == overlap.x {
+            if direction.x < 0.0 {
+                manifold.normal = Vec3::new(-1.0, 0.0, 0.0);
+            } else {
+                manifold.normal = Vec3::new(1.0, 0.0, 0.0);
+            }
+        } else if manifold.penetration == overlap.y {
+            if direction.y < 0.0 {
+                manifold.normal = Vec3::new(0.0, -1.0, 0.0);
+            } else {
+                manifold.normal = Vec3::new(0.0, 1.0, 0.0);
+            }
+        } else if direction.z < 0.0 {
+            manifold.normal = Vec3::new(0.0, 0.0, -1.0);
+        } else {
+            manifold.normal = Vec3::new(0.0, 0.0, 1.0);
+        }
+
+        Some(manifold)
+    } else {
+        None
+    }
+}
+
+fn sphere_vs_sphere(a: (Vec3<f32>, f32), b: (Vec3<f32>, f32)) -> Option<CollisionManifold> {
+    let d = b.0 - a.0;
+    let radii = a.1 + b.1;
+    let distance_squared = d.length_squared();
+
+    if distance_squared >= radii * radii {
+        return None;
+    }
+
+    let distance = distance_squared.sqrt();
+    let normal = if distance > EPSILON {
+        d * (1.0 / distance)
+    } else {
+        Vec3::new(0.0, 1.0, 0.0)
+    };
+    let penetration = radii - distance;
+
+    Some(CollisionManifold::new(
+        penetration,
+        normal,
+        a.0 + normal * (a.1 - penetration * 0.5),
+    ))
+}
+
+fn swept_vs_swept(a: &SweptSphere, b: &SweptSphere) -> Option<CollisionManifold> {
+    let (p, q) = closest_points_on_segments((a.start, a.end), (b.start, b.end));
+
+    sphere_vs_sphere((p, a.radius), (q, b.radius))
+}
+
+fn obb_vs_swept(b: &OrientedBox, s: &SweptSphere) -> Option<CollisionManifold> {
+    let distance_at = |t: f32| {
+        let p = s.start + (s.end - s.start) * t;
+        let c = b.closest_point(p);
+
+        ((p - c).length_squared(), p, c)
+    };
+
+    // The distance from the box is convex along the segment, so a ternary search finds the
+    // closest point of the segment.
+    let mut range = (0.0, 1.0);
+
+    for _ in 0..SEARCH_ITERATIONS {
+        let third = (range.1 - range.0) / 3.0;
+
+        if distance_at(range.0 + third).0 < distance_at(range.1 - third).0 {
+            range.1 -= third;
+        } else {
+            range.0 += third;
+        }
+    }
+
+    let (distance_squared, p, c) = distance_at((range.0 + range.1) * 0.5);
+
+    if distance_squared >= s.radius * s.radius {
+        return None;
+    }
+
+    if distance_squared > EPSILON * EPSILON {
+        let distance = distance_squared.sqrt();
+        let normal = (p - c) * (1.0 / distance);
+        let penetration = s.radius - distance;
+        let mut points = Vec::with_capacity(2);
+
+        // A capsule lying along a face touches it at both ends, a single point would make it
+        // rock from one end onto the other.
+        if s.start != s.end {
+            for end in [s.start, s.end] {
+                let c = b.closest_point(end);
+                let offset = end - c;
+                let distance = offset.length();
+
+                if distance < s.radius && offset.dot(normal) >= distance * (1.0 - CONTACT_TOLERANCE)
+                {
+                    points.push(c - normal * ((s.radius - distance) * 0.5));
+                }
+            }
+        }
+
+        if points.len() == 2 {
+            return Some(CollisionManifold::with_points(penetration, normal, &points));
+        }
+
+        return Some(CollisionManifold::new(
+            penetration,
+            normal,
+            c - normal * (penetration * 0.5),
+        ));
+    }
+
+    // The segment passes through the box, find the cheapest way out along the box faces and the
+    // axes perpendicular to both the segment and a face.
+    let direction = s.end - s.start;
+    let mut axes = b.axes.to_vec();
+
+    if direction.length_squared() > EPSILON {
+        for axis in b.axes.iter() {
+            let cross = direction.cross(*axis);
+
+            if cross.length_squared() > EPSILON {
+                axes.push(cross.normalized());
+            }
+        }
+    }
+
+    let mut result: Option<CollisionManifold> = None;
+
+    for axis in axes.iter() {
+        let radius = b.projected_radius(*axis);
+        let start = (s.start - b.center).dot(*axis);
+        let end = (s.end - b.center).dot(*axis);
+        let forward = radius - start.min(end) + s.radius;
+        let backward = start.max(end) + radius + s.radius;
+        let (penetration, normal) = if forward < backward {
+            (forward, *axis)
+        } else {
+            (backward, -*axis)
+        };
+
+        if result
+            .as_ref()
+            .map_or(true, |m| penetration < m.penetration)
+        {
+            result = Some(CollisionManifold::new(penetration, normal, p));
+        }
+    }
+
+    result
+}
+
+// Separating axis test over the face normals of both boxes and the cross products of their edges.
+fn obb_vs_obb(a: &OrientedBox, b: &OrientedBox) -> Option<CollisionManifold> {
+    let d = b.center - a.center;
+    let mut axes = Vec::with_capacity(15);
+    let mut best: Option<(f32, Vec3<f32>, usize)> = None;
+
+    for axis in a.axes.iter() {
+        axes.push((*axis, 0));
+    }
+
+    for axis in b.axes.iter() {
+        axes.push((*axis, 1));
+    }
+
+    for axis_a in a.axes.iter() {
+        for axis_b in b.axes.iter() {
+            let cross = axis_a.cross(*axis_b);
+
+            if cross.length_squared() > EPSILON {
+                axes.push((cross.normalized(), 2));
+            }
+        }
+    }
+
+    for (axis, kind) in axes {
+        let overlap = a.projected_radius(axis) + b.projected_radius(axis) - d.dot(axis).abs();
+
+        if overlap <= 0.0 {
+            return None;
+        }
+
+        // Face contacts are preferred over nearly equal edge contacts, they give far more stable
+        // contact points.
+        let bias = if kind == 2 { CONTACT_TOLERANCE } else { 0.0 };
+
+        if best.map_or(true, |b| overlap < b.0 - bias) {
+            let normal = if d.dot(axis) < 0.0 { -axis } else { axis };
+            best = Some((overlap, normal, kind));
+        }
+    }
+
+    let (penetration, normal, kind) = best?;
+    let half = normal * (penetration * 0.5);
+    let points = match kind {
+        0 => a.clipped_face_points(b, normal, half),
+        1 => b.clipped_face_points(a, -normal, -half),
+        _ => Vec::new(),
+    };
+
+    if points.is_empty() {
+        let point = match kind {
+            0 => b.support_point(-normal) + half,
+            1 => a.support_point(normal) - half,
+            _ => (b.support_point(-normal) + a.support_point(normal)) * 0.5,
+        };
+
+        return Some(CollisionManifold::new(penetration, normal, point));
+    }
+
+    Some(CollisionManifold::with_points(penetration, normal, &points))
+}
+
+// Slab test in the space of the box.
+fn ray_vs_obb(b: &OrientedBox, ray: (Vec3<f32>, Vec3<f32>)) -> Option<(f32, Vec3<f32>)> {
+    let d = ray.0 - b.center;
+    let mut entry = (f32::MIN, Vec3::new(0.0, 0.0, 0.0));
+    let mut exit = f32::MAX;
+
+    for i in 0..3 {
+        let origin = d.dot(b.axes[i]);
+        let direction = ray.1.dot(b.axes[i]);
+
+        if direction.abs() <= EPSILON {
+            if origin.abs() > b.extents[i] {
+                return None;
+            }
+
+            continue;
+        }
+
+        let t1 = (-b.extents[i] - origin) / direction;
+        let t2 = (b.extents[i] - origin) / direction;
+        let (near, far, normal) = if t1 < t2 {
+            (t1, t2, -b.axes[i])
+        } else {
+            (t2, t1, b.axes[i])
+        };
+
+        if near > entry.0 {
+            entry = (near, normal);
+        }
+
+        exit = exit.min(far);
+
+        if entry.0 > exit || exit < 0.0 {
+            return None;
+        }
+    }
+
+    if entry.0 < 0.0 {
+        Some((0.0, -ray.1))
+    } else {
+        Some(entry)
+    }
+}
+
+// The entry of a ray into the union of the spheres at both ends and the cylinder between them is
+// the earliest entry into any of them.
+fn ray_vs_swept(s: &SweptSphere, ray: (Vec3<f32>, Vec3<f32>)) -> Option<(f32, Vec3<f32>)> {
+    let (closest, _) = closest_points_on_segments((s.start, s.end), (ray.0, ray.0));
+
+    if (ray.0 - closest).length_squared() <= s.radius * s.radius {
+        return Some((0.0, -ray.1));
+    }
+
+    let mut result: Option<(f32, Vec3<f32>)> = None;
+    let mut consider = |hit: Option<(f32, Vec3<f32>)>| {
+        if let Some(hit) = hit {
+            if result.map_or(true, |r| hit.0 < r.0) {
+                result = Some(hit);
+            }
+        }
+    };
+
+    consider(ray_vs_sphere((s.start, s.radius), ray));
+    consider(ray_vs_sphere((s.end, s.radius), ray));
+
+    let axis = s.end - s.start;
+    let length_squared = axis.dot(axis);
+
+    if length_squared > EPSILON {
+        let offset = ray.0 - s.start;
+        let axis_direction = axis.dot(ray.1);
+        let axis_offset = axis.dot(offset);
+        let a = length_squared - axis_direction * axis_direction;
+        let b = length_squared * ray.1.dot(offset) - axis_offset * axis_direction;
+        let c = length_squared * offset.dot(offset)
+            - axis_offset * axis_offset
+            - s.radius * s.radius * length_squared;
+        let h = b * b - a * c;
+
+        if a > EPSILON && h >= 0.0 {
+            let t = (-b - h.sqrt()) / a;
+            let y = axis_offset + t * axis_direction;
+
+            if t >= 0.0 && y > 0.0 && y < length_squared {
+                let normal = (offset + ray.1 * t - axis * (y / length_squared)) * (1.0 / s.radius);
+                consider(Some((t, normal)));
+            }
+        }
+    }
+
+    result
+}
+
+fn ray_vs_sphere(
+    sphere: (Vec3<f32>, f32),
+    ray: (Vec3<f32>, Vec3<f32>),
+) -> Option<(f32, Vec3<f32>)> {
+    let offset = ray.0 - sphere.0;
+    let b = offset.dot(ray.1);
+    let c = offset.dot(offset) - sphere.1 * sphere.1;
+    let h = b * b - c;
+
+    if h < 0.0 {
+        return None;
+    }
+
+    let t = -b - h.sqrt();
+
+    if t < 0.0 {
+        return None;
+    }
+
+    Some((t, (offset + ray.1 * t) * (1.0 / sphere.1)))
+}
+
+// Closest points between the segments `a` and `b`, as in Ericson's Real-Time Collision Detection.
+fn closest_points_on_segments(
+    a: (Vec3<f32>, Vec3<f32>),
+    b: (Vec3<f32>, Vec3<f32>),
+) -> (Vec3<f32>, Vec3<f32>) {
+    let d1 = a.1 - a.0;
+    let d2 = b.1 - b.0;
+    let r = a.0 - b.0;
+    let length_1 = d1.dot(d1);
+    let length_2 = d2.dot(d2);
+    let f = d2.dot(r);
+
+    if length_1 <= EPSILON && length_2 <= EPSILON {
+        return (a.0, b.0);
+    }
+
+    let (s, t) = if length_1 <= EPSILON {
+        (0.0, (f / length_2).clamp(0.0, 1.0))
+    } else {
+        let c = d1.dot(r);
+
+        if length_2 <= EPSILON {
+            ((-c / length_1).clamp(0.0, 1.0), 0.0)
+        } else {
+            let e = d1.dot(d2);
+            let denominator = length_1 * length_2 - e * e;
+            let s = if denominator > EPSILON {
+                ((e * f - c * length_2) / denominator).clamp(0.0, 1.0)
+            } else {
+                0.0
+            };
+            let t = (e * s + f) / length_2;
+
+            if t < 0.0 {
+                ((-c / length_1).clamp(0.0, 1.0), 0.0)
+            } else if t > 1.0 {
+                (((e - c) / length_1).clamp(0.0, 1.0), 1.0)
+            } else {
+                (s, t)
+            }
+        }
+    };
+
+    (a.0 + d1 * s, b.0 + d2 * t)
+}
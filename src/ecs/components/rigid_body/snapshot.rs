@@ -0,0 +1,163 @@
+use super::super::super::Entity;
+use super::super::character_controller::{CharacterControllerSystem, CharacterState};
+use super::super::transformation::TransformationSystem;
+use super::joint::{JointHandle, MAX_ROWS};
+use super::{Contact, RigidBodySystem};
+use gamemath::{Quat, Vec3};
+
+// Everything about a body that changes while simulating, together with the pose of its owner.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(super) struct BodyState {
+    pub(super) entity: Entity,
+    pub(super) position: Vec3<f32>,
+    pub(super) rotation: Quat,
+    pub(super) velocity: Vec3<f32>,
+    pub(super) angular_velocity: Vec3<f32>,
+    pub(super) locomotion: Vec3<f32>,
+    pub(super) force: Vec3<f32>,
+    pub(super) torque: Vec3<f32>,
+    pub(super) sleep_timer: f32,
+    pub(super) sleeping: bool,
+    pub(super) in_contact: bool,
+    pub(super) foothold: bool,
+}
+
+// The state of a `RigidBodySystem` and a `CharacterControllerSystem` and the poses of their
+// owners at one point in time, see `RigidBodySystem::save_snapshot` and
+// `CharacterControllerSystem::save_snapshot`. Settings, shapes and the bodies and controllers
+// themselves aren't part of it, restoring only brings back the state of those that still exist.
+pub struct PhysicsSnapshot {
+    pub(super) timer: f32,
+    pub(super) bodies: Vec<BodyState>,
+    // The contacts and joint impulses of the last step, needed to warm start the next one the
+    // same way.
+    pub(super) contacts: Vec<Contact>,
+    pub(super) joints: Vec<(JointHandle, [f32; MAX_ROWS])>,
+    pub(crate) characters: Vec<CharacterState>,
+}
+
+impl PhysicsSnapshot {
+    pub fn new() -> PhysicsSnapshot {
+        PhysicsSnapshot {
+            timer: 0.0,
+            bodies: Vec::new(),
+            contacts: Vec::new(),
+            joints: Vec::new(),
+            characters: Vec::new(),
+        }
+    }
+
+    pub fn get_body_count(&self) -> usize {
+        self.bodies.len()
+    }
+
+    pub fn get_character_count(&self) -> usize {
+        self.characters.len()
+    }
+
+    pub fn contains(&self, entity: &Entity) -> bool {
+        self.bodies.iter().any(|body| body.entity == *entity)
+    }
+
+    pub(super) fn clear(&mut self) {
+        self.bodies.clear();
+        self.contacts.clear();
+        self.joints.clear();
+    }
+}
+
+impl Default for PhysicsSnapshot {
+    fn default() -> PhysicsSnapshot {
+        PhysicsSnapshot::new()
+    }
+}
+
+// A ring buffer of the most recent snapshots, for rollback and rewinding time. The snapshots are
+// reused once the buffer is full, so recording every step doesn't allocate.
+pub struct SnapshotHistory {
+    snapshots: Vec<PhysicsSnapshot>,
+    // Index of the latest snapshot and the number of snapshots held.
+    latest: usize,
+    len: usize,
+}
+
+impl SnapshotHistory {
+    pub fn new(capacity: usize) -> SnapshotHistory {
+        SnapshotHistory {
+            snapshots: (0..capacity.max(1))
+                .map(|_| PhysicsSnapshot::new())
+                .collect(),
+            latest: 0,
+            len: 0,
+        }
+    }
+
+    pub fn get_capacity(&self) -> usize {
+        self.snapshots.len()
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    // Records the current state, dropping the oldest snapshot when the buffer is full.
+    pub fn record(
+        &mut self,
+        rigid_body_system: &RigidBodySystem,
+        character_controller_system: &CharacterControllerSystem,
+        transformation_system: &TransformationSystem,
+    ) {
+        let capacity = self.snapshots.len();
+
+        if self.len > 0 {
+            self.latest = (self.latest + 1) % capacity;
+        }
+
+        self.len = (self.len + 1).min(capacity);
+        let snapshot = &mut self.snapshots[self.latest];
+        rigid_body_system.save_snapshot(transformation_system, snapshot);
+        character_controller_system.save_snapshot(transformation_system, snapshot);
+    }
+
+    // The snapshot recorded `steps_back` records ago, 0 being the latest one.
+    pub fn get(&self, steps_back: usize) -> Option<&PhysicsSnapshot> {
+        if steps_back >= self.len {
+            return None;
+        }
+
+        let capacity = self.snapshots.len();
+        Some(&self.snapshots[(self.latest + capacity - steps_back) % capacity])
+    }
+
+    // Restores the snapshot recorded `steps_back` records ago and forgets the ones after it, so
+    // recording continues from there. Returns false if the history doesn't reach back that far.
+    pub fn rewind(
+        &mut self,
+        steps_back: usize,
+        rigid_body_system: &mut RigidBodySystem,
+        character_controller_system: &mut CharacterControllerSystem,
+        transformation_system: &mut TransformationSystem,
+    ) -> bool {
+        let snapshot = match self.get(steps_back) {
+            Some(s) => s,
+            None => return false,
+        };
+
+        rigid_body_system.restore_snapshot(snapshot, transformation_system);
+        character_controller_system.restore_snapshot(snapshot, transformation_system);
+
+        let capacity = self.snapshots.len();
+        self.latest = (self.latest + capacity - steps_back) % capacity;
+        self.len -= steps_back;
+
+        true
+    }
+}
@@ -0,0 +1,290 @@
+use super::shape::{CollisionManifold, MAX_CONTACT_POINTS};
+use super::RigidBody;
+use gamemath::Vec3;
+
+// Contacts closing slower than this don't bounce, which keeps resting bodies from jittering.
+const RESTITUTION_MIN_SPEED: f32 = 1.0;
+// Bodies sliding slower than this along a contact are held by static friction.
+const STATIC_FRICTION_MAX_SPEED: f32 = 0.1;
+// Penetration left alone by the position correction, so resting contacts stay in contact.
+const PENETRATION_SLOP: f32 = 0.005;
+const POSITION_CORRECTION: f32 = 0.8;
+
+// How the friction or restitution coefficients of two bodies are combined. When the bodies use
+// different modes, the one further down the list wins.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum CombineMode {
+    Average,
+    Minimum,
+    Multiply,
+    Maximum,
+}
+
+impl CombineMode {
+    // Coefficients are (value, mode) of each body.
+    pub fn combine(a: (f32, CombineMode), b: (f32, CombineMode)) -> f32 {
+        match a.1.max(b.1) {
+            CombineMode::Average => (a.0 + b.0) * 0.5,
+            CombineMode::Minimum => a.0.min(b.0),
+            CombineMode::Multiply => a.0 * b.0,
+            CombineMode::Maximum => a.0.max(b.0),
+        }
+    }
+}
+
+// A contact between the bodies at two rows, solved with sequential impulses. The impulses are
+// accumulated over the iterations and kept between steps to warm start the next solve.
+pub(super) struct ContactConstraint {
+    pub(super) rows: (usize, usize),
+    pub(super) manifold: CollisionManifold,
+    inv_masses: (f32, f32),
+    inv_inertias: ([Vec3<f32>; 3], [Vec3<f32>; 3]),
+    points: [ContactPoint; MAX_CONTACT_POINTS],
+    point_count: usize,
+    tangents: [Vec3<f32>; 2],
+    friction: f32,
+}
+
+#[derive(Clone, Copy)]
+struct ContactPoint {
+    // From the center of mass of each body to the point.
+    arms: (Vec3<f32>, Vec3<f32>),
+    normal_mass: f32,
+    tangent_masses: [f32; 2],
+    restitution_bias: f32,
+    normal_impulse: f32,
+    // Applied to the second body, the first one gets the opposite.
+    friction_impulse: Vec3<f32>,
+}
+
+impl ContactConstraint {
+    // `inv_masses` may differ from the ones of the bodies, sleeping bodies are treated as static.
+    // Bodies with no inverse mass don't turn either. The cached impulses are shared out evenly
+    // between the points of the manifold.
+    pub(super) fn new(
+        rows: (usize, usize),
+        manifold: CollisionManifold,
+        bodies: &[RigidBody],
+        inv_masses: (f32, f32),
+        impulses: (f32, Vec3<f32>),
+    ) -> ContactConstraint {
+        let (a, b) = (&bodies[rows.0], &bodies[rows.1]);
+        let normal = manifold.get_normal();
+        let static_inertia = [Vec3::new(0.0, 0.0, 0.0); 3];
+        let inv_inertias = (
+            if inv_masses.0 == 0.0 {
+                static_inertia
+            } else {
+                a.world_inv_inertia
+            },
+            if inv_masses.1 == 0.0 {
+                static_inertia
+            } else {
+                b.world_inv_inertia
+            },
+        );
+
+        let rv = point_velocity(b, manifold.point - b.center)
+            - point_velocity(a, manifold.point - a.center);
+        let tangent_vel = rv - normal * rv.dot(normal);
+
+        let restitution = CombineMode::combine(
+            (a.elasticity, a.restitution_combine),
+            (b.elasticity, b.restitution_combine),
+        );
+        let friction = if tangent_vel.length() < STATIC_FRICTION_MAX_SPEED {
+            CombineMode::combine(
+                (a.static_friction, a.friction_combine),
+                (b.static_friction, b.friction_combine),
+            )
+        } else {
+            CombineMode::combine(
+                (a.dynamic_friction, a.friction_combine),
+                (b.dynamic_friction, b.friction_combine),
+            )
+        };
+
+        let tangents = tangents(normal);
+        let share = 1.0 / manifold.point_count as f32;
+        // The normal of the contact may have turned since the impulses were cached.
+        let friction_impulse = (impulses.1 - normal * impulses.1.dot(normal)) * share;
+        let mut points = [ContactPoint {
+            arms: (Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 0.0)),
+            normal_mass: 0.0,
+            tangent_masses: [0.0, 0.0],
+            restitution_bias: 0.0,
+            normal_impulse: impulses.0 * share,
+            friction_impulse,
+        }; MAX_CONTACT_POINTS];
+
+        for (point, position) in points.iter_mut().zip(manifold.get_points().iter()) {
+            let arms = (*position - a.center, *position - b.center);
+            let mass =
+                |direction: Vec3<f32>| effective_mass(direction, inv_masses, inv_inertias, arms);
+            let normal_vel = (point_velocity(b, arms.1) - point_velocity(a, arms.0)).dot(normal);
+
+            point.arms = arms;
+            point.normal_mass = mass(normal);
+            point.tangent_masses = [mass(tangents[0]), mass(tangents[1])];
+
+            if normal_vel < -RESTITUTION_MIN_SPEED {
+                point.restitution_bias = -restitution * normal_vel;
+            }
+        }
+
+        ContactConstraint {
+            rows,
+            manifold,
+            inv_masses,
+            inv_inertias,
+            points,
+            point_count: manifold.point_count,
+            tangents,
+            friction,
+        }
+    }
+
+    // Summed over the points, to be cached for the next step.
+    pub(super) fn get_normal_impulse(&self) -> f32 {
+        self.points[..self.point_count]
+            .iter()
+            .map(|p| p.normal_impulse)
+            .sum()
+    }
+
+    pub(super) fn get_friction_impulse(&self) -> Vec3<f32> {
+        let mut sum = Vec3::new(0.0, 0.0, 0.0);
+
+        for point in self.points[..self.point_count].iter() {
+            sum += point.friction_impulse;
+        }
+
+        sum
+    }
+
+    pub(super) fn warm_start(&self, bodies: &mut [RigidBody]) {
+        let normal = self.manifold.get_normal();
+
+        for point in self.points[..self.point_count].iter() {
+            self.apply(
+                bodies,
+                point,
+                normal * point.normal_impulse + point.friction_impulse,
+            );
+        }
+    }
+
+    pub(super) fn solve(&mut self, bodies: &mut [RigidBody]) {
+        let normal = self.manifold.get_normal();
+
+        for i in 0..self.point_count {
+            let mut point = self.points[i];
+
+            if point.normal_mass == 0.0 {
+                continue;
+            }
+
+            let rv = point.get_relative_velocity(bodies, self.rows);
+            let lambda = (point.restitution_bias - rv.dot(normal)) / point.normal_mass;
+            let previous = point.normal_impulse;
+            point.normal_impulse = (previous + lambda).max(0.0);
+            self.apply(bodies, &point, normal * (point.normal_impulse - previous));
+
+            // Friction works in the tangent plane and is limited to a circle around the contact.
+            let rv = point.get_relative_velocity(bodies, self.rows);
+            let previous = point.friction_impulse;
+            let mut impulse = previous;
+
+            for (tangent, mass) in self.tangents.iter().zip(point.tangent_masses.iter()) {
+                if *mass > 0.0 {
+                    impulse -= *tangent * (rv.dot(*tangent) / mass);
+                }
+            }
+
+            let limit = self.friction * point.normal_impulse;
+
+            if impulse.length_squared() > limit * limit {
+                impulse = impulse.normalized() * limit;
+            }
+
+            point.friction_impulse = impulse;
+            self.apply(bodies, &point, impulse - previous);
+            self.points[i] = point;
+        }
+    }
+
+    // How far to move each body, the first one against and the second one along the normal.
+    pub(super) fn get_corrections(&self) -> (Vec3<f32>, Vec3<f32>) {
+        let mass = self.inv_masses.0 + self.inv_masses.1;
+
+        if mass == 0.0 {
+            return (Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 0.0));
+        }
+
+        let depth = (self.manifold.get_penetration() - PENETRATION_SLOP).max(0.0);
+        let correction = self.manifold.get_normal() * (depth * POSITION_CORRECTION / mass);
+
+        (
+            correction * self.inv_masses.0,
+            correction * self.inv_masses.1,
+        )
+    }
+
+    fn apply(&self, bodies: &mut [RigidBody], point: &ContactPoint, impulse: Vec3<f32>) {
+        let (a, b) = self.rows;
+
+        bodies[a].velocity -= impulse * self.inv_masses.0;
+        bodies[a].angular_velocity -=
+            mul_inertia(&self.inv_inertias.0, point.arms.0.cross(impulse));
+        bodies[b].velocity += impulse * self.inv_masses.1;
+        bodies[b].angular_velocity +=
+            mul_inertia(&self.inv_inertias.1, point.arms.1.cross(impulse));
+    }
+}
+
+impl ContactPoint {
+    fn get_relative_velocity(&self, bodies: &[RigidBody], rows: (usize, usize)) -> Vec3<f32> {
+        point_velocity(&bodies[rows.1], self.arms.1) - point_velocity(&bodies[rows.0], self.arms.0)
+    }
+}
+
+// Inverse inertia tensors are kept as their rows.
+pub(super) fn mul_inertia(inv_inertia: &[Vec3<f32>; 3], v: Vec3<f32>) -> Vec3<f32> {
+    Vec3::new(
+        inv_inertia[0].dot(v),
+        inv_inertia[1].dot(v),
+        inv_inertia[2].dot(v),
+    )
+}
+
+// Velocity of the point at `arm` from the center of mass of the body.
+fn point_velocity(body: &RigidBody, arm: Vec3<f32>) -> Vec3<f32> {
+    body.velocity + body.angular_velocity.cross(arm)
+}
+
+// Inverse of the mass the pair resists an impulse along `direction` at the contact with.
+fn effective_mass(
+    direction: Vec3<f32>,
+    inv_masses: (f32, f32),
+    inv_inertias: ([Vec3<f32>; 3], [Vec3<f32>; 3]),
+    arms: (Vec3<f32>, Vec3<f32>),
+) -> f32 {
+    let turn_a = arms.0.cross(direction);
+    let turn_b = arms.1.cross(direction);
+
+    inv_masses.0
+        + inv_masses.1
+        + turn_a.dot(mul_inertia(&inv_inertias.0, turn_a))
+        + turn_b.dot(mul_inertia(&inv_inertias.1, turn_b))
+}
+
+pub(super) fn tangents(normal: Vec3<f32>) -> [Vec3<f32>; 2] {
+    let tangent = if normal.x.abs() >= 0.57735 {
+        Vec3::new(normal.y, -normal.x, 0.0)
+    } else {
+        Vec3::new(0.0, normal.z, -normal.y)
+    }
+    .normalized();
+
+    [tangent, normal.cross(tangent)]
+}
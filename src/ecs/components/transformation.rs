@@ -1,19 +1,204 @@
-use super::super::Entity;
-use fnv::FnvHashMap;
+use super::super::super::renderer::build_model_matrix;
+use super::super::super::utilities::{quat_conjugate, quat_nlerp, quat_rotate_vector};
+use super::super::storage::{ComponentStorage, ComponentsMut};
+use super::super::{Entity, EntityManager};
+use gamemath::Mat4;
 use gamemath::Quat;
 use gamemath::Vec3;
 
+// Position, scale and rotation.
+type Pose = (Vec3<f32>, Vec3<f32>, Quat);
+
+// `position`, `scale` and `rotation` are relative to the parent, or to the world for entities
+// without one. `pivot` only offsets the rendered model and is not inherited by children. Every
+// change marks the data dirty so `TransformationSystem::update` only rebuilds the model matrices
+// of entities that actually moved.
 pub struct TransformationData {
-    owner: Entity,
-    pub position: Vec3<f32>,
-    pub scale: Vec3<f32>,
-    pub pivot: Vec3<f32>,
-    pub rotation: Quat,
+    position: Vec3<f32>,
+    scale: Vec3<f32>,
+    pivot: Vec3<f32>,
+    rotation: Quat,
+    dirty: bool,
+    moved: bool,
+    model_matrix: Mat4,
+    parent: Entity,
+    children: Vec<Entity>,
+    destroyed_with_parent: bool,
+    world_position: Vec3<f32>,
+    world_scale: Vec3<f32>,
+    world_rotation: Quat,
+    previous_position: Vec3<f32>,
+    previous_scale: Vec3<f32>,
+    previous_rotation: Quat,
 }
 
+impl TransformationData {
+    pub fn get_position(&self) -> Vec3<f32> {
+        self.position
+    }
+
+    pub fn get_scale(&self) -> Vec3<f32> {
+        self.scale
+    }
+
+    pub fn get_pivot(&self) -> Vec3<f32> {
+        self.pivot
+    }
+
+    pub fn get_rotation(&self) -> Quat {
+        self.rotation
+    }
+
+    // Replace writes to the formerly public fields, which would bypass the dirty flag, e.g. through
+    // `TransformationSystem::components_mut`. Setting the current value leaves the data clean.
+    pub fn set_position(&mut self, position: Vec3<f32>) {
+        if self.position != position {
+            self.position = position;
+            self.dirty = true;
+        }
+    }
+
+    pub fn set_scale(&mut self, scale: Vec3<f32>) {
+        if self.scale != scale {
+            self.scale = scale;
+            self.dirty = true;
+        }
+    }
+
+    pub fn set_pivot(&mut self, pivot: Vec3<f32>) {
+        if self.pivot != pivot {
+            self.pivot = pivot;
+            self.dirty = true;
+        }
+    }
+
+    pub fn set_rotation(&mut self, rotation: Quat) {
+        if self.rotation != rotation {
+            self.rotation = rotation;
+            self.dirty = true;
+        }
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    // The cached matrix is rebuilt by `TransformationSystem::update`, until then it is computed on
+    // the fly for entities that have changed. Children only see the poses their ancestors had at
+    // the last update, `TransformationSystem::get_model_matrix` also follows ancestors that moved
+    // since.
+    pub fn get_model_matrix(&self) -> Mat4 {
+        if self.dirty {
+            self.compute_model_matrix()
+        } else {
+            self.model_matrix
+        }
+    }
+
+    pub fn get_parent(&self) -> Option<Entity> {
+        if self.parent == Entity::null() {
+            None
+        } else {
+            Some(self.parent)
+        }
+    }
+
+    pub fn get_children(&self) -> &[Entity] {
+        &self.children
+    }
+
+    // World poses of children are refreshed by `TransformationSystem::update`, entities without a
+    // parent always report their current pose.
+    pub fn get_world_position(&self) -> Vec3<f32> {
+        if self.parent == Entity::null() {
+            self.position
+        } else {
+            self.world_position
+        }
+    }
+
+    pub fn get_world_scale(&self) -> Vec3<f32> {
+        if self.parent == Entity::null() {
+            self.scale
+        } else {
+            self.world_scale
+        }
+    }
+
+    pub fn get_world_rotation(&self) -> Quat {
+        if self.parent == Entity::null() {
+            self.rotation
+        } else {
+            self.world_rotation
+        }
+    }
+
+    // The world pose blended from the one saved at the start of the last physics step towards the
+    // current one, `factor` being the leftover fraction of the physics accumulator.
+    pub fn get_interpolated_position(&self, factor: f32) -> Vec3<f32> {
+        self.previous_position + (self.get_world_position() - self.previous_position) * factor
+    }
+
+    pub fn get_interpolated_scale(&self, factor: f32) -> Vec3<f32> {
+        self.previous_scale + (self.get_world_scale() - self.previous_scale) * factor
+    }
+
+    pub fn get_interpolated_rotation(&self, factor: f32) -> Quat {
+        quat_nlerp(&self.previous_rotation, &self.get_world_rotation(), factor)
+    }
+
+    // Falls back to the cached model matrix for everything that did not move since the last
+    // physics step.
+    pub fn get_interpolated_model_matrix(&self, factor: f32) -> Mat4 {
+        if self.previous_position == self.get_world_position()
+            && self.previous_scale == self.get_world_scale()
+            && self.previous_rotation == self.get_world_rotation()
+        {
+            self.get_model_matrix()
+        } else {
+            build_model_matrix(
+                self.get_interpolated_scale(factor),
+                self.pivot,
+                self.get_interpolated_rotation(factor),
+                self.get_interpolated_position(factor),
+            )
+        }
+    }
+
+    fn save_previous_pose(&mut self) {
+        self.previous_position = self.get_world_position();
+        self.previous_scale = self.get_world_scale();
+        self.previous_rotation = self.get_world_rotation();
+    }
+
+    fn set_world_pose(&mut self, pose: Pose) {
+        self.world_position = pose.0;
+        self.world_scale = pose.1;
+        self.world_rotation = pose.2;
+        self.dirty = true;
+    }
+
+    fn compute_model_matrix(&self) -> Mat4 {
+        build_model_matrix(
+            self.get_world_scale(),
+            self.pivot,
+            self.get_world_rotation(),
+            self.get_world_position(),
+        )
+    }
+
+    fn refresh_model_matrix(&mut self) {
+        self.model_matrix = self.compute_model_matrix();
+        self.dirty = false;
+    }
+}
+
+// Besides the model matrices, the system records which entities were moved so that systems caching
+// poses, e.g. the broadphase for static bodies, don't have to compare them every step.
 pub struct TransformationSystem {
-    map: FnvHashMap<Entity, usize>,
-    data: Vec<TransformationData>,
+    data: ComponentStorage<TransformationData>,
+    moved: Vec<Entity>,
+    untracked_writes: bool,
 }
 
 pub struct TransformationBuilder {
@@ -21,6 +206,8 @@ pub struct TransformationBuilder {
     scale: Option<Vec3<f32>>,
     pivot: Option<Vec3<f32>>,
     rotation: Option<Quat>,
+    parent: Option<Entity>,
+    destroyed_with_parent: bool,
 }
 
 impl TransformationBuilder {
@@ -30,6 +217,8 @@ impl TransformationBuilder {
             scale: None,
             pivot: None,
             rotation: None,
+            parent: None,
+            destroyed_with_parent: false,
         }
     }
 
@@ -53,25 +242,51 @@ impl TransformationBuilder {
         self
     }
 
-    fn build(self, owner: Entity) -> TransformationData {
+    // The position, scale and rotation given to the builder are then relative to the parent.
+    pub fn with_parent(mut self, parent: Entity) -> TransformationBuilder {
+        self.parent = Some(parent);
+        self
+    }
+
+    pub fn destroyed_with_parent(mut self) -> TransformationBuilder {
+        self.destroyed_with_parent = true;
+        self
+    }
+
+    fn build(self) -> TransformationData {
+        let position = match self.position {
+            Some(p) => p,
+            None => Vec3::new(0.0, 0.0, 0.0),
+        };
+        let scale = match self.scale {
+            Some(s) => s,
+            None => Vec3::new(1.0, 1.0, 1.0),
+        };
+        let rotation = match self.rotation {
+            Some(r) => r,
+            None => Quat::identity(),
+        };
+
         TransformationData {
-            owner,
-            position: match self.position {
-                Some(p) => p,
-                None => Vec3::new(0.0, 0.0, 0.0),
-            },
-            scale: match self.scale {
-                Some(s) => s,
-                None => Vec3::new(1.0, 1.0, 1.0),
-            },
+            position,
+            scale,
             pivot: match self.pivot {
                 Some(p) => p,
                 None => Vec3::new(0.0, 0.0, 0.0),
             },
-            rotation: match self.rotation {
-                Some(r) => r,
-                None => Quat::identity(),
-            },
+            rotation,
+            dirty: true,
+            moved: false,
+            model_matrix: Mat4::identity(),
+            parent: Entity::null(),
+            children: Vec::new(),
+            destroyed_with_parent: self.destroyed_with_parent,
+            world_position: position,
+            world_scale: scale,
+            world_rotation: rotation,
+            previous_position: position,
+            previous_scale: scale,
+            previous_rotation: rotation,
         }
     }
 }
@@ -79,8 +294,9 @@ impl TransformationBuilder {
 impl TransformationSystem {
     pub fn new() -> TransformationSystem {
         TransformationSystem {
-            map: FnvHashMap::with_capacity_and_hasher(1, Default::default()),
-            data: Vec::new(),
+            data: ComponentStorage::new(),
+            moved: Vec::new(),
+            untracked_writes: false,
         }
     }
 
@@ -89,137 +305,496 @@ impl TransformationSystem {
         entity: &Entity,
         initial_transformation: TransformationBuilder,
     ) {
-        match self.entity_has_transformation(entity) {
-            true => (), //TODO: Add error logging/printing here!
-            false => {
-                self.data.push(initial_transformation.build(*entity));
-                self.map.insert(entity.clone(), self.data.len() - 1);
+        let parent = initial_transformation.parent;
+
+        //TODO: Add error logging/printing here if the entity already has a transformation!
+        if self.data.insert(entity, initial_transformation.build()) {
+            if let Some(parent) = parent {
+                self.link(entity, &parent);
             }
+
+            self.mark_moved(entity);
         }
     }
 
+    // Children are detached and keep their current world pose.
     pub fn remove_transformation_from_entity(&mut self, entity: &Entity) {
-        let mut swapped = (false, 0);
-        let mut removed = false;
+        self.unlink_children(entity);
+        self.set_parent(entity, None);
+        self.mark_moved(entity);
+        self.data.remove(entity);
+    }
+
+    // Children marked `destroyed_with_parent` are destroyed along with their parent, all others
+    // are detached. Has to run before the other systems drain the destroyed list for the same
+    // reason as `ParticleEmitterSystem::remove_destroyed_entities`.
+    pub fn remove_destroyed_entities(&mut self, entity_manager: &mut EntityManager) {
+        let mut i = 0;
+
+        while i < entity_manager.get_destroyed_entities().len() {
+            let entity = entity_manager.get_destroyed_entities()[i];
 
-        if *entity != Entity::null() {
-            match self.map.get(entity) {
-                Some(index) => {
-                    self.data.swap_remove(*index);
-                    removed = true;
+            if let Some(t) = self.data.get(&entity) {
+                for child in t.children.clone().iter() {
+                    let cascade = match self.data.get(child) {
+                        Some(c) => c.destroyed_with_parent,
+                        None => false,
+                    };
 
-                    if self.data.is_empty() == false {
-                        swapped = (true, *index);
+                    if cascade {
+                        entity_manager.destroy_entity(child).ok();
                     }
                 }
-                None => (),
-            }
-        }
 
-        if removed == true {
-            self.map.remove(entity);
-        }
+                self.remove_transformation_from_entity(&entity);
+            }
 
-        if swapped.0 == true && swapped.1 != self.data.len() {
-            *self.map.get_mut(&self.data[swapped.1].owner).unwrap() = swapped.1;
+            i += 1;
         }
     }
 
     pub fn entity_has_transformation(&self, entity: &Entity) -> bool {
-        self.map.contains_key(entity)
+        self.data.contains(entity)
     }
 
-    pub fn get_forward_vector(&self, entity: &Entity) -> Option<Vec3<f32>> {
-        match self.map.get(entity) {
-            Some(index) => {
-                let r = self.data[*index].rotation.extract_matrix();
-                Some(r.get_forward_vector())
+    pub fn components(&self) -> &ComponentStorage<TransformationData> {
+        &self.data
+    }
+
+    // Writes through the returned components are not recorded as moves, so the next
+    // `drain_moved_entities` reports that any entity may have moved.
+    pub fn components_mut(&mut self) -> ComponentsMut<'_, TransformationData> {
+        self.untracked_writes = true;
+        ComponentsMut::new(&mut self.data)
+    }
+
+    // Hands out the entities moved since the last call, without their children that moved along.
+    // Returns false if poses may also have been written without being recorded.
+    pub fn drain_moved_entities(&mut self, moved: &mut Vec<Entity>) -> bool {
+        moved.clear();
+        std::mem::swap(moved, &mut self.moved);
+
+        for entity in moved.iter() {
+            if let Some(t) = self.data.get_mut(entity) {
+                t.moved = false;
             }
-            None => None,
         }
+
+        !std::mem::replace(&mut self.untracked_writes, false)
+    }
+
+    pub fn get_forward_vector(&self, entity: &Entity) -> Option<Vec3<f32>> {
+        self.data
+            .get(entity)
+            .map(|t| t.get_world_rotation().extract_matrix().get_forward_vector())
     }
 
     pub fn get_right_vector(&self, entity: &Entity) -> Option<Vec3<f32>> {
-        match self.map.get(entity) {
-            Some(index) => {
-                let r = self.data[*index].rotation.extract_matrix();
-                Some(r.get_right_vector())
-            }
-            None => None,
-        }
+        self.data
+            .get(entity)
+            .map(|t| t.get_world_rotation().extract_matrix().get_right_vector())
     }
 
     pub fn get_position(&self, entity: &Entity) -> Option<Vec3<f32>> {
-        match self.map.get(entity) {
-            Some(index) => Some(self.data[*index].position),
-            None => None,
-        }
+        self.data.get(entity).map(|t| t.position)
     }
 
+    // Marks the transformation dirty whether or not the position is actually changed.
     pub fn get_position_mut(&mut self, entity: &Entity) -> Option<&mut Vec3<f32>> {
-        match self.map.get(entity) {
-            Some(index) => Some(&mut self.data[*index].position),
-            None => None,
+        self.mark_moved(entity);
+        self.data.get_mut(entity).map(|t| {
+            t.dirty = true;
+            &mut t.position
+        })
+    }
+
+    // World poses computed from the current poses of all ancestors, unlike the ones cached by
+    // `update` that `TransformationData` reports.
+    pub fn get_world_position(&self, entity: &Entity) -> Option<Vec3<f32>> {
+        self.get_world_pose(entity).map(|pose| pose.0)
+    }
+
+    pub fn get_world_rotation(&self, entity: &Entity) -> Option<Quat> {
+        self.get_world_pose(entity).map(|pose| pose.1)
+    }
+
+    // The position and rotation in world space, which is what physics works with.
+    pub fn get_world_pose(&self, entity: &Entity) -> Option<(Vec3<f32>, Quat)> {
+        let t = self.data.get(entity)?;
+
+        if t.parent == Entity::null() {
+            Some((t.position, t.rotation))
+        } else {
+            let world = self.compute_world_pose(entity);
+            Some((world.0, world.2))
+        }
+    }
+
+    // Moves the entity to `position` in world space, converting it into the space of its parent.
+    pub fn set_world_position(&mut self, entity: &Entity, position: Vec3<f32>) {
+        let parent = match self.data.get(entity) {
+            Some(t) => t.parent,
+            None => return,
+        };
+
+        if parent == Entity::null() {
+            self.set_position(entity, position);
+            return;
+        }
+
+        let local =
+            TransformationSystem::relative_position(&self.compute_world_pose(&parent), position);
+
+        if let Some(t) = self.data.get_mut(entity) {
+            t.set_position(local);
+            t.world_position = position;
+        }
+
+        self.mark_moved(entity);
+    }
+
+    pub fn set_world_rotation(&mut self, entity: &Entity, rotation: Quat) {
+        let parent = match self.data.get(entity) {
+            Some(t) => t.parent,
+            None => return,
+        };
+
+        if parent == Entity::null() {
+            self.set_rotation(entity, rotation);
+            return;
+        }
+
+        let local = quat_conjugate(&self.compute_world_pose(&parent).2.normalized()) * rotation;
+
+        if let Some(t) = self.data.get_mut(entity) {
+            t.set_rotation(local);
+            t.world_rotation = rotation;
+        }
+
+        self.mark_moved(entity);
+    }
+
+    // The cached model matrix, recomputed from the current poses when the entity or any of its
+    // ancestors changed since the last `update`.
+    pub fn get_model_matrix(&self, entity: &Entity) -> Option<Mat4> {
+        let t = self.data.get(entity)?;
+
+        if self.is_dirty_along_parent_chain(entity) {
+            let world = self.compute_world_pose(entity);
+            Some(build_model_matrix(world.1, t.pivot, world.2, world.0))
+        } else {
+            Some(t.model_matrix)
         }
     }
 
     pub fn get_transformation_data(&self, entity: &Entity) -> Option<&TransformationData> {
-        match self.map.get(entity) {
-            Some(index) => Some(&self.data[*index]),
-            None => None,
+        self.data.get(entity)
+    }
+
+    pub fn get_parent(&self, entity: &Entity) -> Option<Entity> {
+        self.data.get(entity).and_then(|t| t.get_parent())
+    }
+
+    pub fn get_children(&self, entity: &Entity) -> &[Entity] {
+        match self.data.get(entity) {
+            Some(t) => &t.children,
+            None => &[],
         }
     }
 
-    pub fn rotate(&mut self, entity: &Entity, axis: Vec3<f32>, angle: f32) {
-        match self.map.get(entity) {
-            Some(index) => {
-                self.data[*index].rotation.rotate(angle, axis);
+    // Attaches `entity` to `parent`, or detaches it for `None`, without moving it in the world.
+    // Returns false if either has no transformation or the parent is a descendant of `entity`.
+    pub fn set_parent(&mut self, entity: &Entity, parent: Option<&Entity>) -> bool {
+        if !self.data.contains(entity) {
+            return false;
+        }
+
+        if let Some(parent) = parent {
+            if !self.data.contains(parent) || self.is_ancestor_or_self(entity, parent) {
+                return false;
+            }
+        }
+
+        let world = self.compute_world_pose(entity);
+        self.unlink_from_parent(entity);
+
+        let local = match parent {
+            Some(parent) => {
+                let parent_world = self.compute_world_pose(parent);
+                self.link(entity, parent);
+                TransformationSystem::relative_pose(&parent_world, &world)
             }
-            None => (),
+            None => world,
+        };
+
+        if let Some(t) = self.data.get_mut(entity) {
+            t.position = local.0;
+            t.scale = local.1;
+            t.rotation = local.2;
+            t.set_world_pose(world);
+        }
+
+        true
+    }
+
+    pub fn set_destroyed_with_parent(&mut self, entity: &Entity, destroyed_with_parent: bool) {
+        if let Some(t) = self.data.get_mut(entity) {
+            t.destroyed_with_parent = destroyed_with_parent;
         }
     }
 
+    pub fn rotate(&mut self, entity: &Entity, axis: Vec3<f32>, angle: f32) {
+        if let Some(t) = self.data.get_mut(entity) {
+            t.rotation.rotate(angle, axis);
+            t.dirty = true;
+        }
+
+        self.mark_moved(entity);
+    }
+
     pub fn set_rotation(&mut self, entity: &Entity, rotation: Quat) {
-        if *entity != Entity::null() {
-            match self.map.get(entity) {
-                Some(index) => {
-                    self.data[*index].rotation = rotation;
-                }
-                None => (),
-            }
+        if let Some(t) = self.data.get_mut(entity) {
+            t.set_rotation(rotation);
         }
+
+        self.mark_moved(entity);
     }
 
     pub fn set_position(&mut self, entity: &Entity, position: Vec3<f32>) {
-        if *entity != Entity::null() {
-            match self.map.get(entity) {
-                Some(index) => {
-                    self.data[*index].position = position;
-                }
-                None => (),
-            }
+        if let Some(t) = self.data.get_mut(entity) {
+            t.set_position(position);
+        }
+
+        self.mark_moved(entity);
+    }
+
+    pub fn set_pivot(&mut self, entity: &Entity, pivot: Vec3<f32>) {
+        if let Some(t) = self.data.get_mut(entity) {
+            t.set_pivot(pivot);
         }
     }
 
     pub fn set_scale(&mut self, entity: &Entity, scale: Vec3<f32>) {
-        if *entity != Entity::null() {
-            match self.map.get(entity) {
-                Some(index) => {
-                    self.data[*index].scale = scale;
+        if let Some(t) = self.data.get_mut(entity) {
+            t.set_scale(scale);
+        }
+
+        self.mark_moved(entity);
+    }
+
+    pub fn apply_movement(&mut self, entity: &Entity, movement: Vec3<f32>) {
+        if let Some(t) = self.data.get_mut(entity) {
+            t.set_position(t.position + movement);
+        }
+
+        self.mark_moved(entity);
+    }
+
+    // Called by the physics system at the start of every fixed step.
+    pub fn save_previous_poses(&mut self) {
+        for (_, t) in self.data.iter_mut() {
+            t.save_previous_pose();
+        }
+    }
+
+    // Makes the entity snap to its current pose instead of sliding there, e.g. after a teleport.
+    pub fn reset_interpolation(&mut self, entity: &Entity) {
+        if let Some(t) = self.data.get_mut(entity) {
+            t.save_previous_pose();
+        }
+    }
+
+    pub fn get_interpolated_position(&self, entity: &Entity, factor: f32) -> Option<Vec3<f32>> {
+        self.data
+            .get(entity)
+            .map(|t| t.get_interpolated_position(factor))
+    }
+
+    // Propagates world poses from every root down its hierarchy, parents before children, and
+    // rebuilds the model matrices of everything that moved. Call once per frame after everything
+    // has moved and before drawing.
+    pub fn update(&mut self) {
+        let mut stack = Vec::new();
+
+        for (_, t) in self.data.iter_mut() {
+            if t.parent == Entity::null() {
+                let changed = t.dirty;
+
+                if changed {
+                    t.refresh_model_matrix();
+                }
+
+                for child in t.children.iter() {
+                    stack.push((*child, (t.position, t.scale, t.rotation), changed));
+                }
+            }
+        }
+
+        while let Some((entity, parent_world, parent_changed)) = stack.pop() {
+            if let Some(t) = self.data.get_mut(&entity) {
+                let changed = parent_changed || t.dirty;
+
+                if changed {
+                    let world = TransformationSystem::combine_poses(
+                        &parent_world,
+                        &(t.position, t.scale, t.rotation),
+                    );
+                    t.set_world_pose(world);
+                    t.refresh_model_matrix();
+                }
+
+                let world = (t.world_position, t.world_scale, t.world_rotation);
+
+                for child in t.children.iter() {
+                    stack.push((*child, world, changed));
                 }
-                None => (),
             }
         }
     }
 
-    pub fn apply_movement(&mut self, entity: &Entity, movement: Vec3<f32>) {
-        if *entity != Entity::null() {
-            match self.map.get(entity) {
-                Some(index) => {
-                    self.data[*index].position += movement;
+    fn link(&mut self, entity: &Entity, parent: &Entity) {
+        if !self.data.contains(parent) || self.is_ancestor_or_self(entity, parent) {
+            return;
+        }
+
+        if let Some(t) = self.data.get_mut(entity) {
+            t.parent = *parent;
+        }
+
+        if let Some(p) = self.data.get_mut(parent) {
+            p.children.push(*entity);
+        }
+
+        let world = self.compute_world_pose(entity);
+
+        if let Some(t) = self.data.get_mut(entity) {
+            t.set_world_pose(world);
+        }
+    }
+
+    fn unlink_from_parent(&mut self, entity: &Entity) {
+        let parent = match self.data.get_mut(entity) {
+            Some(t) => std::mem::replace(&mut t.parent, Entity::null()),
+            None => return,
+        };
+
+        if let Some(p) = self.data.get_mut(&parent) {
+            p.children.retain(|c| c != entity);
+        }
+    }
+
+    fn unlink_children(&mut self, entity: &Entity) {
+        let children = match self.data.get(entity) {
+            Some(t) => t.children.clone(),
+            None => return,
+        };
+
+        for child in children.iter() {
+            self.set_parent(child, None);
+        }
+    }
+
+    fn mark_moved(&mut self, entity: &Entity) {
+        if let Some(t) = self.data.get_mut(entity) {
+            if !t.moved {
+                t.moved = true;
+                self.moved.push(*entity);
+            }
+        }
+    }
+
+    fn is_ancestor_or_self(&self, ancestor: &Entity, entity: &Entity) -> bool {
+        let mut current = *entity;
+
+        while current != Entity::null() {
+            if current == *ancestor {
+                return true;
+            }
+
+            current = match self.data.get(&current) {
+                Some(t) => t.parent,
+                None => Entity::null(),
+            };
+        }
+
+        false
+    }
+
+    fn is_dirty_along_parent_chain(&self, entity: &Entity) -> bool {
+        let mut current = *entity;
+
+        while let Some(t) = self.data.get(&current) {
+            if t.dirty {
+                return true;
+            }
+
+            current = t.parent;
+        }
+
+        false
+    }
+
+    // Walks up the parent chain instead of relying on the poses cached by `update`.
+    fn compute_world_pose(&self, entity: &Entity) -> Pose {
+        match self.data.get(entity) {
+            Some(t) => {
+                let local = (t.position, t.scale, t.rotation);
+
+                if t.parent == Entity::null() {
+                    local
+                } else {
+                    TransformationSystem::combine_poses(&self.compute_world_pose(&t.parent), &local)
                 }
-                None => (),
             }
+            None => (
+                Vec3::new(0.0, 0.0, 0.0),
+                Vec3::new(1.0, 1.0, 1.0),
+                Quat::identity(),
+            ),
         }
     }
+
+    fn combine_poses(parent: &Pose, local: &Pose) -> Pose {
+        let scaled = Vec3::new(
+            local.0.x * parent.1.x,
+            local.0.y * parent.1.y,
+            local.0.z * parent.1.z,
+        );
+
+        (
+            parent.0 + quat_rotate_vector(&parent.2, scaled),
+            Vec3::new(
+                local.1.x * parent.1.x,
+                local.1.y * parent.1.y,
+                local.1.z * parent.1.z,
+            ),
+            parent.2 * local.2,
+        )
+    }
+
+    // The inverse of `combine_poses`: the local pose that yields `world` under `parent`.
+    fn relative_pose(parent: &Pose, world: &Pose) -> Pose {
+        let inverse_rotation = quat_conjugate(&parent.2.normalized());
+
+        (
+            TransformationSystem::relative_position(parent, world.0),
+            Vec3::new(
+                world.1.x / parent.1.x,
+                world.1.y / parent.1.y,
+                world.1.z / parent.1.z,
+            ),
+            inverse_rotation * world.2,
+        )
+    }
+
+    fn relative_position(parent: &Pose, position: Vec3<f32>) -> Vec3<f32> {
+        let unrotated =
+            quat_rotate_vector(&quat_conjugate(&parent.2.normalized()), position - parent.0);
+
+        Vec3::new(
+            unrotated.x / parent.1.x,
+            unrotated.y / parent.1.y,
+            unrotated.z / parent.1.z,
+        )
+    }
 }
@@ -1,17 +1,25 @@
+use std::cell::Cell;
 use std::collections::VecDeque;
+use std::error::Error;
+use std::fmt;
 
+pub mod command_buffer;
 pub mod components;
+pub mod query;
+pub mod storage;
+pub mod world;
 
 // DO NOT CHANGE BELOW!
-const ENTITY_INDEX_BITS: u32 = 24;
-const ENTITY_GENERATION_BITS: u32 = 8;
-const ENTITY_INDEX_MASK: u32 = (1 << ENTITY_INDEX_BITS) - 1;
-const ENTITY_GENERATION_MASK: u32 = (1 << ENTITY_GENERATION_BITS) - 1;
+const ENTITY_INDEX_BITS: u32 = 32;
+const ENTITY_GENERATION_BITS: u32 = 32;
+const ENTITY_INDEX_MASK: u64 = (1 << ENTITY_INDEX_BITS) - 1;
+const ENTITY_GENERATION_MASK: u64 = (1 << ENTITY_GENERATION_BITS) - 1;
+const RETIRED_GENERATION: u32 = ENTITY_GENERATION_MASK as u32;
 const MINIMUM_FREE_INDICES: usize = 1024;
 // DO NOT CHANGE ABOVE!
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-pub struct Entity(u32);
+pub struct Entity(u64);
 
 impl Entity {
     #[inline]
@@ -19,26 +27,59 @@ impl Entity {
         Entity(0)
     }
 
-    fn new(index: u32, generation: u8) -> Entity {
+    // Raw bits for save files and network messages, see `from_bits`.
+    pub fn to_bits(&self) -> u64 {
+        self.0
+    }
+
+    pub fn from_bits(bits: u64) -> Entity {
+        Entity(bits)
+    }
+
+    fn new(index: u32, generation: u32) -> Entity {
         Entity(
-            (index & ENTITY_INDEX_MASK)
-                | ((generation as u32 & ENTITY_GENERATION_MASK) << ENTITY_INDEX_BITS),
+            (index as u64 & ENTITY_INDEX_MASK)
+                | ((generation as u64 & ENTITY_GENERATION_MASK) << ENTITY_INDEX_BITS),
         )
     }
 
     fn index(&self) -> u32 {
-        self.0 & ENTITY_INDEX_MASK
+        (self.0 & ENTITY_INDEX_MASK) as u32
     }
 
-    fn generation(&self) -> u8 {
-        ((self.0 >> ENTITY_INDEX_BITS) & ENTITY_GENERATION_MASK) as u8
+    fn generation(&self) -> u32 {
+        ((self.0 >> ENTITY_INDEX_BITS) & ENTITY_GENERATION_MASK) as u32
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntityError {
+    // The null entity never refers to anything.
+    Null,
+    // The index was never handed out by this entity manager, e.g. a handle from another world.
+    Unknown,
+    // The slot exists but has been destroyed since the handle was created.
+    Dead,
+}
+
+impl fmt::Display for EntityError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EntityError::Null => write!(f, "null entity"),
+            EntityError::Unknown => write!(f, "entity is unknown to this entity manager"),
+            EntityError::Dead => write!(f, "entity has been destroyed"),
+        }
+    }
+}
+
+impl Error for EntityError {}
+
 pub struct EntityManager {
-    generation: Vec<u8>,
+    generation: Vec<u32>,
     active: Vec<bool>,
     free_indices: VecDeque<u32>,
+    destroyed: Vec<Entity>,
+    reserved: Cell<u32>,
 }
 
 impl EntityManager {
@@ -47,6 +88,8 @@ impl EntityManager {
             generation: Vec::new(),
             active: Vec::new(),
             free_indices: VecDeque::new(),
+            destroyed: Vec::new(),
+            reserved: Cell::new(0),
         };
 
         em.create_new_entity();
@@ -54,20 +97,27 @@ impl EntityManager {
         em
     }
 
-    pub fn set_entity_is_active(&mut self, entity: &Entity, active: bool) {
-        if self.entity_is_alive(entity) == true {
-            self.active[entity.index() as usize] = active;
-        }
+    pub fn set_entity_is_active(
+        &mut self,
+        entity: &Entity,
+        active: bool,
+    ) -> Result<(), EntityError> {
+        let idx = self.validate_entity(entity)?;
+        self.active[idx] = active;
+
+        Ok(())
     }
 
-    pub fn toggle_entity_is_active(&mut self, entity: &Entity) {
-        if self.entity_is_alive(entity) == true {
-            let idx = entity.index();
-            self.active[idx as usize] = !self.active[idx as usize];
-        }
+    pub fn toggle_entity_is_active(&mut self, entity: &Entity) -> Result<(), EntityError> {
+        let idx = self.validate_entity(entity)?;
+        self.active[idx] = !self.active[idx];
+
+        Ok(())
     }
 
     pub fn create_new_entity(&mut self) -> Entity {
+        self.flush_reserved_entities();
+
         let mut idx = 0;
 
         if self.free_indices.len() > MINIMUM_FREE_INDICES {
@@ -82,17 +132,94 @@ impl EntityManager {
         Entity::new(idx, self.generation[idx as usize])
     }
 
+    // Hands out a handle for a fresh slot without needing mutable access. The entity stays
+    // inactive until `create_reserved_entity` is called for it.
+    pub fn reserve_entity(&self) -> Entity {
+        let idx = self.generation.len() as u32 + self.reserved.get();
+        self.reserved.set(self.reserved.get() + 1);
+
+        Entity::new(idx, 0)
+    }
+
+    pub fn create_reserved_entity(&mut self, entity: &Entity) -> Result<(), EntityError> {
+        self.flush_reserved_entities();
+        self.set_entity_is_active(entity, true)
+    }
+
+    // Gives back a reserved handle that is never going to be created, so its slot can be reused.
+    pub fn release_reserved_entity(&mut self, entity: &Entity) -> Result<(), EntityError> {
+        self.flush_reserved_entities();
+        self.destroy_entity(entity)
+    }
+
+    fn flush_reserved_entities(&mut self) {
+        for _ in 0..self.reserved.replace(0) {
+            self.generation.push(0);
+            self.active.push(false);
+        }
+    }
+
+    // Checks a handle from an untrusted source (another world, a save file, the network) and
+    // returns its slot. Reserved entities are unknown until the reservation has been flushed.
+    pub fn validate_entity(&self, entity: &Entity) -> Result<usize, EntityError> {
+        if *entity == Entity::null() {
+            return Err(EntityError::Null);
+        }
+
+        let idx = entity.index() as usize;
+
+        // Forged handles can carry the retired generation, which no live entity ever has.
+        match self.generation.get(idx) {
+            Some(generation)
+                if *generation == entity.generation() && *generation != RETIRED_GENERATION =>
+            {
+                Ok(idx)
+            }
+            Some(_) => Err(EntityError::Dead),
+            None => Err(EntityError::Unknown),
+        }
+    }
+
+    pub fn get_entity_is_active(&self, entity: &Entity) -> Result<bool, EntityError> {
+        let idx = self.validate_entity(entity)?;
+
+        Ok(self.active[idx])
+    }
+
+    // False for dead or unknown entities.
     pub fn entity_is_active(&self, entity: &Entity) -> bool {
-        self.active[entity.index() as usize]
+        self.get_entity_is_active(entity).unwrap_or(false)
     }
 
     pub fn entity_is_alive(&self, entity: &Entity) -> bool {
-        self.generation[entity.index() as usize] == entity.generation()
+        self.validate_entity(entity).is_ok()
+    }
+
+    // Fails without side effects for handles that are null, unknown or already destroyed, so an
+    // index can never end up in the free list twice.
+    pub fn destroy_entity(&mut self, entity: &Entity) -> Result<(), EntityError> {
+        let idx = self.validate_entity(entity)? as u32;
+        self.generation[idx as usize] = self.generation[idx as usize].saturating_add(1);
+        self.active[idx as usize] = false;
+
+        // A slot whose generation reaches the retired marker is never handed out again, so no
+        // live handle can ever carry that generation and stale handles stay dead for good.
+        if self.generation[idx as usize] != RETIRED_GENERATION {
+            self.free_indices.push_back(idx);
+        }
+
+        self.destroyed.push(*entity);
+
+        Ok(())
+    }
+
+    // Entities destroyed since the last call to `clear_destroyed_entities`, in destruction order.
+    // Every system holding components drains its rows for these before the list is cleared.
+    pub fn get_destroyed_entities(&self) -> &[Entity] {
+        &self.destroyed
     }
 
-    pub fn destroy_entity(&mut self, entity: &Entity) {
-        let idx = entity.index();
-        self.generation[idx as usize] += 1;
-        self.free_indices.push_back(idx);
+    pub fn clear_destroyed_entities(&mut self) {
+        self.destroyed.clear();
     }
 }
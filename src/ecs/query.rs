@@ -0,0 +1,196 @@
+use super::storage::{ComponentStorage, ComponentsMut, RowsMut};
+use super::{Entity, EntityManager};
+
+// Keeps `Fetch` and `Rows` from being implemented outside of this module. `Query` hands out
+// mutable components relying on every entity having at most one row, which only the storages
+// guarantee.
+mod sealed {
+    pub trait Sealed {}
+}
+
+// Something that can take part in a join: a shared component storage, a mutable component view
+// or a tuple of those. The member with the fewest rows drives the iteration.
+pub trait Fetch: sealed::Sealed {
+    type Item;
+    type Rows: Rows<Item = Self::Item>;
+
+    // Called once when the query is built.
+    fn into_rows(self) -> Self::Rows;
+}
+
+// The rows of a `Fetch` while a query walks them.
+pub trait Rows: sealed::Sealed {
+    type Item;
+
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn entity_at(&self, row: usize) -> Entity;
+
+    /// # Safety
+    /// The returned item may borrow mutably from the fetched storages for as long as they are
+    /// borrowed by `self`, so the caller must never fetch the same entity twice.
+    unsafe fn fetch(&mut self, entity: &Entity) -> Option<Self::Item>;
+}
+
+impl<T> sealed::Sealed for &ComponentStorage<T> {}
+impl<T> sealed::Sealed for &mut ComponentStorage<T> {}
+impl<T> sealed::Sealed for ComponentsMut<'_, T> {}
+impl<T> sealed::Sealed for RowsMut<'_, T> {}
+
+impl<'a, T> Fetch for &'a ComponentStorage<T> {
+    type Item = &'a T;
+    type Rows = &'a ComponentStorage<T>;
+
+    fn into_rows(self) -> &'a ComponentStorage<T> {
+        self
+    }
+}
+
+impl<'a, T> Rows for &'a ComponentStorage<T> {
+    type Item = &'a T;
+
+    fn len(&self) -> usize {
+        ComponentStorage::len(self)
+    }
+
+    fn entity_at(&self, row: usize) -> Entity {
+        self.entities()[row]
+    }
+
+    unsafe fn fetch(&mut self, entity: &Entity) -> Option<&'a T> {
+        let storage: &'a ComponentStorage<T> = self;
+        storage.get(entity)
+    }
+}
+
+impl<'a, T> Fetch for &'a mut ComponentStorage<T> {
+    type Item = &'a mut T;
+    type Rows = RowsMut<'a, T>;
+
+    fn into_rows(self) -> RowsMut<'a, T> {
+        self.rows_mut()
+    }
+}
+
+impl<'a, T> Fetch for ComponentsMut<'a, T> {
+    type Item = &'a mut T;
+    type Rows = RowsMut<'a, T>;
+
+    fn into_rows(self) -> RowsMut<'a, T> {
+        ComponentsMut::into_rows(self)
+    }
+}
+
+impl<'a, T> Rows for RowsMut<'a, T> {
+    type Item = &'a mut T;
+
+    fn len(&self) -> usize {
+        RowsMut::len(self)
+    }
+
+    fn entity_at(&self, row: usize) -> Entity {
+        self.entities()[row]
+    }
+
+    unsafe fn fetch(&mut self, entity: &Entity) -> Option<&'a mut T> {
+        self.get_mut(entity)
+    }
+}
+
+macro_rules! impl_fetch_for_tuple {
+    ($(($name:ident, $index:tt)),+) => {
+        impl<$($name: sealed::Sealed),+> sealed::Sealed for ($($name,)+) {}
+
+        impl<$($name: Fetch),+> Fetch for ($($name,)+) {
+            type Item = ($($name::Item,)+);
+            type Rows = ($($name::Rows,)+);
+
+            fn into_rows(self) -> Self::Rows {
+                ($(self.$index.into_rows(),)+)
+            }
+        }
+
+        impl<$($name: Rows),+> Rows for ($($name,)+) {
+            type Item = ($($name::Item,)+);
+
+            fn len(&self) -> usize {
+                let mut len = usize::MAX;
+                $(len = len.min(self.$index.len());)+
+                len
+            }
+
+            fn entity_at(&self, row: usize) -> Entity {
+                let len = self.len();
+                $(
+                    if self.$index.len() == len {
+                        return self.$index.entity_at(row);
+                    }
+                )+
+                unreachable!()
+            }
+
+            unsafe fn fetch(&mut self, entity: &Entity) -> Option<Self::Item> {
+                Some(($(self.$index.fetch(entity)?,)+))
+            }
+        }
+    };
+}
+
+impl_fetch_for_tuple!((A, 0));
+impl_fetch_for_tuple!((A, 0), (B, 1));
+impl_fetch_for_tuple!((A, 0), (B, 1), (C, 2));
+impl_fetch_for_tuple!((A, 0), (B, 1), (C, 2), (D, 3));
+impl_fetch_for_tuple!((A, 0), (B, 1), (C, 2), (D, 3), (E, 4));
+impl_fetch_for_tuple!((A, 0), (B, 1), (C, 2), (D, 3), (E, 4), (F, 5));
+
+pub struct Query<'e, F: Fetch> {
+    entity_manager: &'e EntityManager,
+    rows: F::Rows,
+    row: usize,
+    include_inactive: bool,
+}
+
+impl<'e, F: Fetch> Query<'e, F> {
+    pub fn including_inactive(mut self) -> Query<'e, F> {
+        self.include_inactive = true;
+        self
+    }
+}
+
+impl<'e, F: Fetch> Iterator for Query<'e, F> {
+    type Item = (Entity, F::Item);
+
+    fn next(&mut self) -> Option<(Entity, F::Item)> {
+        while self.row < self.rows.len() {
+            let entity = self.rows.entity_at(self.row);
+            self.row += 1;
+
+            if !self.include_inactive && !self.entity_manager.entity_is_active(&entity) {
+                continue;
+            }
+
+            // Every entity has at most one row in the driving storage, so no entity is fetched
+            // twice during the lifetime of the query.
+            if let Some(item) = unsafe { self.rows.fetch(&entity) } {
+                return Some((entity, item));
+            }
+        }
+
+        None
+    }
+}
+
+// Iterates every active entity that has a row in all members of `fetch`, e.g.
+// `query(&entity_manager, (transformations.components_mut(), rigid_bodies.components()))`.
+pub fn query<F: Fetch>(entity_manager: &EntityManager, fetch: F) -> Query<'_, F> {
+    Query {
+        entity_manager,
+        rows: fetch.into_rows(),
+        row: 0,
+        include_inactive: false,
+    }
+}
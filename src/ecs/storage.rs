@@ -0,0 +1,200 @@
+use super::{Entity, EntityManager};
+use fnv::FnvHashMap;
+use std::iter::Zip;
+use std::marker::PhantomData;
+use std::slice::{Iter, IterMut};
+
+// Densely packed component rows keyed by entity. Removal swaps the last row into the freed slot,
+// so row order is not stable across removals.
+pub struct ComponentStorage<T> {
+    map: FnvHashMap<Entity, usize>,
+    owners: Vec<Entity>,
+    data: Vec<T>,
+}
+
+impl<T> ComponentStorage<T> {
+    pub fn new() -> ComponentStorage<T> {
+        ComponentStorage {
+            map: FnvHashMap::with_capacity_and_hasher(1, Default::default()),
+            owners: Vec::new(),
+            data: Vec::new(),
+        }
+    }
+
+    // Returns false without touching the storage if the entity is null or already has a row.
+    pub fn insert(&mut self, entity: &Entity, component: T) -> bool {
+        if *entity == Entity::null() || self.map.contains_key(entity) {
+            return false;
+        }
+
+        self.owners.push(*entity);
+        self.data.push(component);
+        self.map.insert(*entity, self.data.len() - 1);
+
+        true
+    }
+
+    pub fn remove(&mut self, entity: &Entity) -> Option<T> {
+        let index = self.map.remove(entity)?;
+        let component = self.data.swap_remove(index);
+        self.owners.swap_remove(index);
+
+        if index < self.owners.len() {
+            self.map.insert(self.owners[index], index);
+        }
+
+        Some(component)
+    }
+
+    pub fn remove_destroyed(&mut self, entity_manager: &EntityManager) {
+        for entity in entity_manager.get_destroyed_entities() {
+            self.remove(entity);
+        }
+    }
+
+    pub fn contains(&self, entity: &Entity) -> bool {
+        self.map.contains_key(entity)
+    }
+
+    pub fn get(&self, entity: &Entity) -> Option<&T> {
+        self.map.get(entity).map(|index| &self.data[*index])
+    }
+
+    pub fn get_mut(&mut self, entity: &Entity) -> Option<&mut T> {
+        match self.map.get(entity) {
+            Some(index) => Some(&mut self.data[*index]),
+            None => None,
+        }
+    }
+
+    pub fn index_of(&self, entity: &Entity) -> Option<usize> {
+        self.map.get(entity).copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.map.clear();
+        self.owners.clear();
+        self.data.clear();
+    }
+
+    pub fn entities(&self) -> &[Entity] {
+        &self.owners
+    }
+
+    pub fn as_slice(&self) -> &[T] {
+        &self.data
+    }
+
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        &mut self.data
+    }
+
+    pub fn iter(&self) -> Zip<Iter<'_, Entity>, Iter<'_, T>> {
+        self.owners.iter().zip(self.data.iter())
+    }
+
+    pub fn iter_mut(&mut self) -> Zip<Iter<'_, Entity>, IterMut<'_, T>> {
+        self.owners.iter().zip(self.data.iter_mut())
+    }
+
+    pub(super) fn rows_mut(&mut self) -> RowsMut<'_, T> {
+        RowsMut {
+            map: &self.map,
+            owners: &self.owners,
+            data: self.data.as_mut_ptr(),
+            rows: PhantomData,
+        }
+    }
+}
+
+impl<T> Default for ComponentStorage<T> {
+    fn default() -> ComponentStorage<T> {
+        ComponentStorage::new()
+    }
+}
+
+// The rows of a storage borrowed mutably as a whole, but handed out one at a time by a query.
+// Every row is reached through a pointer to the first one taken up front, going through the
+// storage again would invalidate the rows handed out before.
+pub struct RowsMut<'a, T> {
+    map: &'a FnvHashMap<Entity, usize>,
+    owners: &'a [Entity],
+    data: *mut T,
+    rows: PhantomData<&'a mut [T]>,
+}
+
+impl<'a, T> RowsMut<'a, T> {
+    pub(super) fn len(&self) -> usize {
+        self.owners.len()
+    }
+
+    pub(super) fn entities(&self) -> &[Entity] {
+        self.owners
+    }
+
+    /// # Safety
+    /// The same entity must not be fetched twice while the row handed out for it is in use.
+    pub(super) unsafe fn get_mut(&mut self, entity: &Entity) -> Option<&'a mut T> {
+        let index = *self.map.get(entity)?;
+
+        // SAFETY: The index comes from the map of the storage, so it is in bounds, and the caller
+        // guarantees that no other reference to the row exists.
+        Some(unsafe { &mut *self.data.add(index) })
+    }
+}
+
+// Mutable access to the rows of a storage without the ability to add or remove them, used by
+// systems that need to keep other bookkeeping in sync with their component rows.
+pub struct ComponentsMut<'a, T> {
+    storage: &'a mut ComponentStorage<T>,
+}
+
+impl<'a, T> ComponentsMut<'a, T> {
+    pub fn new(storage: &'a mut ComponentStorage<T>) -> ComponentsMut<'a, T> {
+        ComponentsMut { storage }
+    }
+
+    pub fn contains(&self, entity: &Entity) -> bool {
+        self.storage.contains(entity)
+    }
+
+    pub fn get(&self, entity: &Entity) -> Option<&T> {
+        self.storage.get(entity)
+    }
+
+    pub fn get_mut(&mut self, entity: &Entity) -> Option<&mut T> {
+        self.storage.get_mut(entity)
+    }
+
+    pub fn len(&self) -> usize {
+        self.storage.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.storage.is_empty()
+    }
+
+    pub fn entities(&self) -> &[Entity] {
+        self.storage.entities()
+    }
+
+    pub fn iter(&self) -> Zip<Iter<'_, Entity>, Iter<'_, T>> {
+        self.storage.iter()
+    }
+
+    pub fn iter_mut(&mut self) -> Zip<Iter<'_, Entity>, IterMut<'_, T>> {
+        self.storage.iter_mut()
+    }
+
+    pub(super) fn into_rows(self) -> RowsMut<'a, T> {
+        self.storage.rows_mut()
+    }
+}
@@ -0,0 +1,271 @@
+use super::super::renderer::Renderer;
+use super::components::character_controller::{
+    CharacterControllerBuilder, CharacterControllerSystem,
+};
+use super::components::drawable::{DrawableBuilder, DrawableSystem};
+use super::components::health::{HealthBuilder, HealthSystem};
+use super::components::name::{NameBuilder, NameSystem};
+use super::components::particle_emitter::{ParticleEmitterBuilder, ParticleEmitterSystem};
+use super::components::rigid_body::{RigidBodyBuilder, RigidBodySystem};
+use super::components::text::{TextBuilder, TextSystem};
+use super::components::transformation::{TransformationBuilder, TransformationSystem};
+use super::{Entity, EntityError, EntityManager};
+use gameprng::xorshift128plus::XorShift128Plus;
+
+// Owns an entity manager together with all built-in systems. Worlds share no state, so a menu
+// scene and a gameplay scene can be kept loaded side by side.
+pub struct World {
+    pub entity_manager: EntityManager,
+    pub transformation_system: TransformationSystem,
+    pub rigid_body_system: RigidBodySystem,
+    pub character_controller_system: CharacterControllerSystem,
+    pub health_system: HealthSystem,
+    pub drawable_system: DrawableSystem,
+    pub particle_emitter_system: ParticleEmitterSystem,
+    pub text_system: TextSystem,
+    pub name_system: NameSystem,
+}
+
+pub struct EntityBuilder<'w> {
+    world: &'w mut World,
+    entity: Entity,
+}
+
+impl<'w> EntityBuilder<'w> {
+    pub fn with_transformation(self, transformation: TransformationBuilder) -> EntityBuilder<'w> {
+        self.world
+            .transformation_system
+            .add_transformation_to_entity(&self.entity, transformation);
+        self
+    }
+
+    pub fn with_rigid_body(self, rigid_body: RigidBodyBuilder) -> EntityBuilder<'w> {
+        self.world.rigid_body_system.add_rigid_body_to_entity(
+            &self.entity,
+            rigid_body,
+            &self.world.transformation_system,
+        );
+        self
+    }
+
+    pub fn with_character_controller(
+        self,
+        controller: CharacterControllerBuilder,
+    ) -> EntityBuilder<'w> {
+        self.world
+            .character_controller_system
+            .add_character_controller_to_entity(
+                &self.entity,
+                controller,
+                &self.world.transformation_system,
+            );
+        self
+    }
+
+    pub fn with_health(self, health: HealthBuilder) -> EntityBuilder<'w> {
+        self.world
+            .health_system
+            .add_health_to_entity(&self.entity, health);
+        self
+    }
+
+    // A name already taken by another entity is ignored, tags are always added.
+    pub fn with_name(self, name: NameBuilder) -> EntityBuilder<'w> {
+        self.world
+            .name_system
+            .add_name_to_entity(&self.entity, name);
+        self
+    }
+
+    pub fn with_drawable<'a>(
+        self,
+        renderer: &mut Renderer<'a>,
+        drawable: DrawableBuilder<'a>,
+    ) -> EntityBuilder<'w> {
+        self.world.drawable_system.add_drawable_to_entity(
+            &self.entity,
+            &self.world.transformation_system,
+            renderer,
+            drawable,
+        );
+        self
+    }
+
+    pub fn with_text<'a>(
+        self,
+        renderer: &mut Renderer<'a>,
+        text: TextBuilder<'a>,
+    ) -> EntityBuilder<'w> {
+        self.world.text_system.add_text_to_entity(
+            &self.entity,
+            &self.world.transformation_system,
+            renderer,
+            text,
+        );
+        self
+    }
+
+    pub fn with_particle_emitter(
+        self,
+        renderer: &mut Renderer,
+        particle_emitter: ParticleEmitterBuilder,
+    ) -> EntityBuilder<'w> {
+        let world = &mut *self.world;
+
+        world
+            .particle_emitter_system
+            .add_particle_emitter_to_entity(
+                &self.entity,
+                particle_emitter,
+                renderer,
+                &mut world.entity_manager,
+                &mut world.transformation_system,
+                &mut world.rigid_body_system,
+                &mut world.drawable_system,
+            );
+        self
+    }
+
+    pub fn inactive(self) -> EntityBuilder<'w> {
+        self.world
+            .entity_manager
+            .set_entity_is_active(&self.entity, false)
+            .ok();
+        self
+    }
+
+    pub fn build(self) -> Entity {
+        self.entity
+    }
+}
+
+impl World {
+    pub fn new() -> World {
+        World {
+            entity_manager: EntityManager::new(),
+            transformation_system: TransformationSystem::new(),
+            rigid_body_system: RigidBodySystem::new(),
+            character_controller_system: CharacterControllerSystem::new(),
+            health_system: HealthSystem::new(),
+            drawable_system: DrawableSystem::new(),
+            particle_emitter_system: ParticleEmitterSystem::new(),
+            text_system: TextSystem::new(),
+            name_system: NameSystem::new(),
+        }
+    }
+
+    // Components are added in call order, so the transformation has to come before anything that
+    // depends on it (rigid bodies, drawables, texts).
+    pub fn spawn(&mut self) -> EntityBuilder<'_> {
+        let entity = self.entity_manager.create_new_entity();
+
+        EntityBuilder {
+            world: self,
+            entity,
+        }
+    }
+
+    pub fn despawn(&mut self, entity: &Entity) -> Result<(), EntityError> {
+        self.entity_manager.destroy_entity(entity)?;
+        self.remove_destroyed_entities();
+
+        Ok(())
+    }
+
+    pub fn find_entity_by_name(&self, name: &str) -> Option<Entity> {
+        self.name_system
+            .find_entity_by_name(name, &self.entity_manager)
+    }
+
+    pub fn get_entities_with_tag<'a>(&'a self, tag: &str) -> impl Iterator<Item = Entity> + 'a {
+        self.name_system
+            .get_entities_with_tag(tag, &self.entity_manager)
+    }
+
+    pub fn remove_destroyed_entities(&mut self) {
+        // Removing emitters and cascading through hierarchies can both destroy more entities, so
+        // both run until the destroyed list stops growing.
+        let mut count = 0;
+
+        while count != self.entity_manager.get_destroyed_entities().len() {
+            count = self.entity_manager.get_destroyed_entities().len();
+
+            self.particle_emitter_system
+                .remove_destroyed_entities(&mut self.entity_manager);
+            self.transformation_system
+                .remove_destroyed_entities(&mut self.entity_manager);
+        }
+
+        self.rigid_body_system
+            .remove_destroyed_entities(&self.entity_manager);
+        self.character_controller_system
+            .remove_destroyed_entities(&self.entity_manager);
+        self.health_system
+            .remove_destroyed_entities(&self.entity_manager);
+        self.drawable_system
+            .remove_destroyed_entities(&self.entity_manager);
+        self.text_system
+            .remove_destroyed_entities(&self.entity_manager);
+        self.name_system
+            .remove_destroyed_entities(&self.entity_manager);
+        self.entity_manager.clear_destroyed_entities();
+    }
+
+    // The part of `update` that runs without a renderer. Characters move against the bodies
+    // where this update left them, and health takes the damage of this update's collisions.
+    pub fn simulate(&mut self, dt: f32) {
+        self.rigid_body_system
+            .update(dt, &mut self.transformation_system);
+        self.character_controller_system.update(
+            dt,
+            &mut self.transformation_system,
+            &self.rigid_body_system,
+        );
+        self.health_system
+            .apply_collision_damage(&self.rigid_body_system);
+
+        self.health_system.update(&mut self.entity_manager);
+    }
+
+    pub fn update(&mut self, dt: f32, prng: &mut XorShift128Plus, renderer: &mut Renderer) {
+        self.simulate(dt);
+
+        self.particle_emitter_system.update(
+            dt,
+            prng,
+            renderer,
+            &mut self.entity_manager,
+            &mut self.transformation_system,
+            &mut self.rigid_body_system,
+            &mut self.drawable_system,
+        );
+
+        self.remove_destroyed_entities();
+        self.transformation_system.update();
+        self.draw(renderer);
+    }
+
+    pub fn draw(&self, renderer: &mut Renderer) {
+        let interpolation_factor = self.rigid_body_system.get_interpolation_factor();
+
+        self.drawable_system.draw_all(
+            &self.entity_manager,
+            &self.transformation_system,
+            interpolation_factor,
+            renderer,
+        );
+
+        self.text_system.draw_all(
+            &self.entity_manager,
+            &self.transformation_system,
+            interpolation_factor,
+            renderer,
+        );
+    }
+}
+
+impl Default for World {
+    fn default() -> World {
+        World::new()
+    }
+}
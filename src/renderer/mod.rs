@@ -41,6 +41,42 @@ pub struct RenderJob {
     pub emissive_tint: Vec4<f32>,
 }
 
+// A render job with a precomputed model matrix, e.g. one cached by the transformation system.
+#[derive(Clone, Copy)]
+pub struct ModelRenderJob {
+    pub model: ModelInfo,
+    pub shader: GLuint,
+    pub textures: usize,
+    pub model_matrix: Mat4,
+    pub uv_size: Vec2<f32>,
+    pub uv_offset: Vec2<f32>,
+    pub tint: Vec4<f32>,
+    pub emissive_tint: Vec4<f32>,
+}
+
+pub fn build_model_matrix(
+    scale: Vec3<f32>,
+    pivot: Vec3<f32>,
+    rotation: Quat,
+    position: Vec3<f32>,
+) -> Mat4 {
+    let r = rotation.normalized().extract_matrix().transposed();
+    let mut p = Mat4::identity();
+    let mut s = Mat4::identity();
+    let mut t = Mat4::identity();
+
+    s.scale(scale);
+    p.translate(pivot);
+    t.translate(position);
+
+    let mut m = s;
+    m *= p;
+    m *= r;
+    m *= t;
+
+    m
+}
+
 pub struct InstanceBuffer {
     model_matrix: Mat4,
     tint: Vec4<f32>,
@@ -604,26 +640,25 @@ impl<'a> Renderer<'a> {
     }
 
     pub fn add_render_job(&mut self, job: RenderJob) {
+        self.add_model_render_job(ModelRenderJob {
+            model: job.model,
+            shader: job.shader,
+            textures: job.textures,
+            model_matrix: build_model_matrix(job.scale, job.pivot, job.rotation, job.position),
+            uv_size: job.uv_size,
+            uv_offset: job.uv_offset,
+            tint: job.tint,
+            emissive_tint: job.emissive_tint,
+        });
+    }
+
+    pub fn add_model_render_job(&mut self, job: ModelRenderJob) {
         match self.render_jobs.get_mut(&job.shader) {
             Some(shader_jobs) => match shader_jobs.get_mut(&job.model.vao) {
                 Some(model_jobs) => match model_jobs.1.get_mut(&job.textures) {
                     Some(texture_jobs) => {
-                        let r = job.rotation.normalized().extract_matrix().transposed();
-                        let mut p = Mat4::identity();
-                        let mut s = Mat4::identity();
-                        let mut t = Mat4::identity();
-
-                        s.scale(job.scale);
-                        p.translate(job.pivot);
-                        t.translate(job.position);
-
-                        let mut m = s;
-                        m *= p;
-                        m *= r;
-                        m *= t;
-
                         texture_jobs.push(InstanceBuffer {
-                            model_matrix: m,
+                            model_matrix: job.model_matrix,
                             tint: job.tint,
                             emissive_tint: job.emissive_tint,
                             uv_size: job.uv_size,
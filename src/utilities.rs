@@ -1,3 +1,5 @@
+use gamemath::{Quat, Vec3, Vec4};
+use std::f64::consts::{FRAC_2_PI, FRAC_PI_2, FRAC_PI_6, PI};
 use std::io::{Error, Read, Write};
 use std::mem;
 use std::slice;
@@ -56,6 +58,133 @@ pub enum SearchDirection2D {
     Right,
 }
 
+// The inverse rotation for unit quaternions.
+pub fn quat_conjugate(rotation: &Quat) -> Quat {
+    Quat {
+        x: -rotation.x,
+        y: -rotation.y,
+        z: -rotation.z,
+        w: rotation.w,
+    }
+}
+
+// Rotates `vector` the same way the renderer rotates vertices, so `a * b` applies `b` first.
+pub fn quat_rotate_vector(rotation: &Quat, vector: Vec3<f32>) -> Vec3<f32> {
+    (rotation.extract_matrix() * Vec4::new(vector.x, vector.y, vector.z, 0.0)).into()
+}
+
+// Turns `rotation` by `angular_velocity` around the world axes for `dt` seconds. The angular
+// velocity follows the right hand rule, as cross products do.
+pub fn quat_integrate(rotation: &Quat, angular_velocity: Vec3<f32>, dt: f32) -> Quat {
+    let speed = angular_velocity.length();
+
+    if speed <= 0.0 {
+        return *rotation;
+    }
+
+    (Quat::rotation(-speed * dt, angular_velocity * (1.0 / speed)) * *rotation).normalized()
+}
+
+// Same as `quat_integrate`, but with `portable_sin_cos` so the result doesn't depend on the math
+// library of the platform.
+pub fn quat_integrate_portable(rotation: &Quat, angular_velocity: Vec3<f32>, dt: f32) -> Quat {
+    let speed = angular_velocity.length();
+
+    if speed <= 0.0 {
+        return *rotation;
+    }
+
+    let (sin, cos) = portable_sin_cos(-speed * dt * 0.5);
+    let axis = angular_velocity * (sin / speed);
+
+    (Quat {
+        x: axis.x,
+        y: axis.y,
+        z: axis.z,
+        w: cos,
+    } * *rotation)
+        .normalized()
+}
+
+// Sine and cosine built from basic arithmetic only, which gives the same bits on every platform
+// unlike the system math library. Accurate to the last bit of an f32 in almost all cases.
+pub fn portable_sin_cos(radians: f32) -> (f32, f32) {
+    let quarters = (radians as f64 * FRAC_2_PI).round();
+    let x = radians as f64 - quarters * FRAC_PI_2;
+    let x2 = x * x;
+    let sin = x
+        * (1.0
+            - x2 / 6.0
+                * (1.0 - x2 / 20.0 * (1.0 - x2 / 42.0 * (1.0 - x2 / 72.0 * (1.0 - x2 / 110.0)))));
+    let cos = 1.0
+        - x2 / 2.0 * (1.0 - x2 / 12.0 * (1.0 - x2 / 30.0 * (1.0 - x2 / 56.0 * (1.0 - x2 / 90.0))));
+
+    let (sin, cos) = match (quarters as i64).rem_euclid(4) {
+        0 => (sin, cos),
+        1 => (cos, -sin),
+        2 => (-sin, -cos),
+        _ => (-cos, sin),
+    };
+
+    (sin as f32, cos as f32)
+}
+
+// The portable counterpart of `f32::atan2`, see `portable_sin_cos`.
+pub fn portable_atan2(y: f32, x: f32) -> f32 {
+    const SQRT_3: f64 = 1.732_050_807_568_877_2;
+
+    if x == 0.0 && y == 0.0 {
+        return 0.0;
+    }
+
+    let (ax, ay) = (x.abs() as f64, y.abs() as f64);
+    let steep = ay > ax;
+    let t = if steep { ax / ay } else { ay / ax };
+
+    // Moves the argument close to zero, where the series converges quickly.
+    let (offset, u) = if t > 2.0 - SQRT_3 {
+        (FRAC_PI_6, (t * SQRT_3 - 1.0) / (t + SQRT_3))
+    } else {
+        (0.0, t)
+    };
+
+    let u2 = u * u;
+    let mut series = 0.0;
+
+    for k in (0..9).rev() {
+        series = 1.0 / (2 * k + 1) as f64 - u2 * series;
+    }
+
+    let mut angle = offset + u * series;
+
+    if steep {
+        angle = FRAC_PI_2 - angle;
+    }
+    if x < 0.0 {
+        angle = PI - angle;
+    }
+    if y < 0.0 {
+        angle = -angle;
+    }
+
+    angle as f32
+}
+
+// Normalized linear interpolation along the shorter arc, good enough for the small steps between
+// two physics updates.
+pub fn quat_nlerp(from: &Quat, to: &Quat, t: f32) -> Quat {
+    let dot = from.x * to.x + from.y * to.y + from.z * to.z + from.w * to.w;
+    let sign = if dot < 0.0 { -1.0 } else { 1.0 };
+
+    Quat {
+        x: from.x + (to.x * sign - from.x) * t,
+        y: from.y + (to.y * sign - from.y) * t,
+        z: from.z + (to.z * sign - from.z) * t,
+        w: from.w + (to.w * sign - from.w) * t,
+    }
+    .normalized()
+}
+
 pub fn read_struct<T, R: Read>(reader: &mut R) -> Result<T, Error> {
     let num_bytes = mem::size_of::<T>();
 
@@ -0,0 +1,138 @@
+use black_grimoire::ecs::components::rigid_body::broadphase::BroadphaseMode;
+use black_grimoire::ecs::components::rigid_body::{RigidBodyBuilder, RigidBodySystem};
+use black_grimoire::ecs::components::transformation::{
+    TransformationBuilder, TransformationSystem,
+};
+use black_grimoire::ecs::{Entity, EntityManager};
+use black_grimoire::gamemath::{Quat, Vec3};
+use black_grimoire::gameprng::prng_traits::{PrngGeneration, PrngSeeding};
+use black_grimoire::gameprng::xorshift128plus::XorShift128Plus;
+use std::f32::consts::TAU;
+
+const STEP: f32 = 1.0 / 60.0;
+
+fn random_vector(prng: &mut XorShift128Plus, min: f32, max: f32) -> Vec3<f32> {
+    Vec3::new(
+        prng.range(min, max),
+        prng.range(min, max),
+        prng.range(min, max),
+    )
+}
+
+fn random_body(prng: &mut XorShift128Plus) -> RigidBodyBuilder {
+    let builder = RigidBodyBuilder::new()
+        .with_offset(random_vector(prng, -0.2, 0.2))
+        .with_collision_layer(1 << prng.range(0u32, 3u32))
+        .with_collision_mask(prng.range(1u32, 15u32));
+
+    match prng.range(0u32, 3u32) {
+        0 => builder.with_extents(random_vector(prng, 0.2, 1.0)),
+        1 => builder.with_oriented_extents(random_vector(prng, 0.2, 1.0)),
+        2 => builder.with_sphere(prng.range(0.2, 1.0)),
+        _ => builder.with_capsule(prng.range(0.2, 0.6), prng.range(0.1, 0.8)),
+    }
+}
+
+#[test]
+fn sweep_and_prune_finds_the_same_contacts_as_brute_force() {
+    for seed in 1..=5 {
+        let mut prng = XorShift128Plus::new(seed);
+        let mut entity_manager = EntityManager::new();
+        let mut transformation_system = TransformationSystem::new();
+        let mut rigid_body_system = RigidBodySystem::new();
+        let mut statics: Vec<Entity> = Vec::new();
+
+        for i in 0..80 {
+            let entity = entity_manager.create_new_entity();
+            let mut body = random_body(&mut prng);
+
+            if i < 60 {
+                body = body
+                    .with_mass(prng.range(0.5, 5.0))
+                    .with_velocity(random_vector(&mut prng, -2.0, 2.0));
+            } else {
+                statics.push(entity);
+            }
+
+            let axis = random_vector(&mut prng, -1.0, 1.0) + Vec3::new(0.0, 0.01, 0.0);
+            transformation_system.add_transformation_to_entity(
+                &entity,
+                TransformationBuilder::new()
+                    .at_position(random_vector(&mut prng, -6.0, 6.0))
+                    .with_rotation(Quat::rotation(prng.range(0.0, TAU), axis.normalized())),
+            );
+            rigid_body_system.add_rigid_body_to_entity(&entity, body, &transformation_system);
+        }
+
+        for step in 0..60 {
+            // The cached bounds of static bodies have to notice when one is moved.
+            if step % 10 == 5 {
+                let entity = statics[prng.range(0, statics.len() as u32 - 1) as usize];
+                let position = transformation_system.get_position(&entity).unwrap();
+                transformation_system
+                    .set_position(&entity, position + random_vector(&mut prng, -1.0, 1.0));
+            }
+
+            let swept = rigid_body_system.find_contacts(&mut transformation_system);
+            rigid_body_system.set_broadphase_mode(BroadphaseMode::BruteForce);
+            let brute = rigid_body_system.find_contacts(&mut transformation_system);
+            rigid_body_system.set_broadphase_mode(BroadphaseMode::SweepAndPrune);
+
+            assert_eq!(swept, brute, "scene {} step {}", seed, step);
+
+            rigid_body_system.update(STEP, &mut transformation_system);
+        }
+    }
+}
+
+#[test]
+fn moving_a_static_body_updates_its_pairs() {
+    let mut entity_manager = EntityManager::new();
+    let mut transformation_system = TransformationSystem::new();
+    let mut rigid_body_system = RigidBodySystem::new();
+    let parent = entity_manager.create_new_entity();
+    let platform = entity_manager.create_new_entity();
+    let ball = entity_manager.create_new_entity();
+
+    transformation_system.add_transformation_to_entity(&parent, TransformationBuilder::new());
+    transformation_system
+        .add_transformation_to_entity(&platform, TransformationBuilder::new().with_parent(parent));
+    transformation_system.add_transformation_to_entity(
+        &ball,
+        TransformationBuilder::new().at_position(Vec3::new(5.0, 0.0, 0.0)),
+    );
+    rigid_body_system.add_rigid_body_to_entity(
+        &platform,
+        RigidBodyBuilder::new(),
+        &transformation_system,
+    );
+    rigid_body_system.add_rigid_body_to_entity(
+        &ball,
+        RigidBodyBuilder::new().with_sphere(0.5).with_mass(1.0),
+        &transformation_system,
+    );
+
+    assert!(rigid_body_system
+        .find_contacts(&mut transformation_system)
+        .is_empty());
+
+    transformation_system.set_position(&platform, Vec3::new(5.0, 0.0, 0.0));
+    assert!(!rigid_body_system
+        .find_contacts(&mut transformation_system)
+        .is_empty());
+
+    // Moving the parent moves the platform along.
+    transformation_system.set_position(&parent, Vec3::new(-5.0, 0.0, 0.0));
+    assert!(rigid_body_system
+        .find_contacts(&mut transformation_system)
+        .is_empty());
+
+    transformation_system
+        .components_mut()
+        .get_mut(&parent)
+        .unwrap()
+        .set_position(Vec3::new(0.0, 0.0, 0.0));
+    assert!(!rigid_body_system
+        .find_contacts(&mut transformation_system)
+        .is_empty());
+}
@@ -0,0 +1,109 @@
+use black_grimoire::ecs::components::rigid_body::event::CollisionEventKind;
+use black_grimoire::ecs::components::rigid_body::RigidBodyBuilder;
+use black_grimoire::ecs::components::transformation::TransformationBuilder;
+use black_grimoire::ecs::world::World;
+use black_grimoire::ecs::Entity;
+use black_grimoire::gamemath::Vec3;
+
+const STEP: f32 = 1.0 / 60.0;
+
+fn bullet(shape: u32, continuous: bool) -> RigidBodyBuilder {
+    let builder = RigidBodyBuilder::new()
+        .with_mass(0.01)
+        .with_velocity(Vec3::new(900.0, 0.0, 0.0))
+        .is_gravity_immune();
+    let builder = match shape {
+        0 => builder.with_sphere(0.02),
+        1 => builder.with_extents(Vec3::new(0.02, 0.02, 0.02)),
+        _ => builder.with_capsule(0.02, 0.05),
+    };
+
+    match continuous {
+        true => builder.with_continuous_collision(),
+        false => builder,
+    }
+}
+
+// Fires a bullet at `target` for half a second, returns where the bullet ended up and whether
+// it hit the target.
+fn fire(world: &mut World, target: Entity, bullet: RigidBodyBuilder) -> (f32, bool) {
+    let bullet = world
+        .spawn()
+        .with_transformation(TransformationBuilder::new())
+        .with_rigid_body(bullet)
+        .build();
+    let mut hit = false;
+
+    for _ in 0..30 {
+        world
+            .rigid_body_system
+            .update(STEP, &mut world.transformation_system);
+
+        hit |= world
+            .rigid_body_system
+            .get_collision_events()
+            .iter()
+            .any(|e| {
+                e.get_kind() == CollisionEventKind::Begin && e.get_other(&bullet) == Some(target)
+            });
+    }
+
+    (
+        world.transformation_system.get_position(&bullet).unwrap().x,
+        hit,
+    )
+}
+
+fn wall(world: &mut World) -> Entity {
+    world
+        .spawn()
+        .with_transformation(TransformationBuilder::new().at_position(Vec3::new(5.0, 0.0, 0.0)))
+        .with_rigid_body(RigidBodyBuilder::new().with_extents(Vec3::new(0.005, 5.0, 5.0)))
+        .build()
+}
+
+#[test]
+fn fast_bullets_tunnel_through_thin_walls() {
+    for shape in 0..3 {
+        let mut world = World::new();
+        let wall = wall(&mut world);
+        let (x, _) = fire(&mut world, wall, bullet(shape, false));
+
+        assert!(x > 100.0, "shape {} at {}", shape, x);
+    }
+}
+
+#[test]
+fn continuous_bullets_stop_at_thin_walls() {
+    for shape in 0..3 {
+        let mut world = World::new();
+        let wall = wall(&mut world);
+        let (x, hit) = fire(&mut world, wall, bullet(shape, true));
+
+        assert!(x > 4.9 && x < 5.0, "shape {} at {}", shape, x);
+        assert!(hit);
+    }
+}
+
+#[test]
+fn continuous_bullets_hit_dynamic_bodies() {
+    let mut world = World::new();
+    let target = world
+        .spawn()
+        .with_transformation(TransformationBuilder::new().at_position(Vec3::new(5.0, 0.0, 0.0)))
+        .with_rigid_body(
+            RigidBodyBuilder::new()
+                .with_mass(1.0)
+                .with_extents(Vec3::new(0.005, 0.5, 0.5))
+                .is_gravity_immune(),
+        )
+        .build();
+    let (x, hit) = fire(&mut world, target, bullet(0, true));
+
+    assert!(hit);
+
+    // The bullet pushes the target along instead of passing through it.
+    let target_x = world.transformation_system.get_position(&target).unwrap().x;
+    assert!(target_x > 5.0, "target at {}", target_x);
+    assert!(x < target_x, "bullet at {}, target at {}", x, target_x);
+}
@@ -0,0 +1,338 @@
+use black_grimoire::ecs::components::character_controller::CharacterControllerBuilder;
+use black_grimoire::ecs::components::rigid_body::RigidBodyBuilder;
+use black_grimoire::ecs::components::transformation::TransformationBuilder;
+use black_grimoire::ecs::world::World;
+use black_grimoire::ecs::Entity;
+use black_grimoire::gamemath::{Quat, Vec3};
+
+const STEP: f32 = 1.0 / 60.0;
+
+fn block(world: &mut World, position: Vec3<f32>, extents: Vec3<f32>) -> Entity {
+    world
+        .spawn()
+        .with_transformation(TransformationBuilder::new().at_position(position))
+        .with_rigid_body(RigidBodyBuilder::new().with_extents(extents))
+        .build()
+}
+
+// A floor with its top at zero.
+fn floor(world: &mut World) -> Entity {
+    block(world, Vec3::new(0.0, -0.5, 0.0), Vec3::new(50.0, 0.5, 50.0))
+}
+
+fn ramp(world: &mut World, x: f32, angle: f32) -> Entity {
+    world
+        .spawn()
+        .with_transformation(
+            TransformationBuilder::new()
+                .at_position(Vec3::new(x, 0.0, 0.0))
+                .with_rotation(Quat::rotation(angle, Vec3::new(0.0, 0.0, 1.0))),
+        )
+        .with_rigid_body(RigidBodyBuilder::new().with_oriented_extents(Vec3::new(4.0, 0.1, 2.0)))
+        .build()
+}
+
+// A character with its feet at `position`.
+fn character(world: &mut World, position: Vec3<f32>) -> Entity {
+    world
+        .spawn()
+        .with_transformation(TransformationBuilder::new().at_position(position))
+        .with_character_controller(
+            CharacterControllerBuilder::new().with_offset(Vec3::new(0.0, 0.9, 0.0)),
+        )
+        .build()
+}
+
+fn step(world: &mut World, steps: usize) {
+    for _ in 0..steps {
+        world
+            .rigid_body_system
+            .update(STEP, &mut world.transformation_system);
+        world.character_controller_system.update(
+            STEP,
+            &mut world.transformation_system,
+            &world.rigid_body_system,
+        );
+    }
+}
+
+fn position(world: &World, entity: &Entity) -> Vec3<f32> {
+    world.transformation_system.get_position(entity).unwrap()
+}
+
+fn velocity(world: &World, entity: &Entity) -> Vec3<f32> {
+    world
+        .character_controller_system
+        .components()
+        .get(entity)
+        .unwrap()
+        .get_velocity()
+}
+
+#[test]
+fn lands_and_walks_along_the_ground() {
+    let mut world = World::new();
+    let floor = floor(&mut world);
+    let character = character(&mut world, Vec3::new(0.0, 2.0, 0.0));
+
+    step(&mut world, 60);
+
+    let controllers = &world.character_controller_system;
+    assert!(controllers.is_grounded(&character));
+    assert_eq!(controllers.get_ground_entity(&character), Some(floor));
+    assert!(controllers.get_ground_normal(&character).unwrap().y > 0.99);
+    assert!(position(&world, &character).y.abs() < 0.03);
+
+    // Upwards movement is ignored, walking keeps the character on the ground.
+    world
+        .character_controller_system
+        .set_movement(&character, Vec3::new(3.0, 5.0, 0.0));
+    step(&mut world, 60);
+
+    let walked = position(&world, &character);
+    assert!(
+        (walked.x - 3.0).abs() < 0.05 && walked.y.abs() < 0.03,
+        "{:?}",
+        walked
+    );
+    assert!(world.character_controller_system.is_grounded(&character));
+}
+
+#[test]
+fn steps_up_low_ledges_but_not_walls() {
+    let mut world = World::new();
+    floor(&mut world);
+    block(
+        &mut world,
+        Vec3::new(3.0, 0.125, 0.0),
+        Vec3::new(1.0, 0.125, 1.0),
+    );
+    block(
+        &mut world,
+        Vec3::new(3.0, 0.3, 5.0),
+        Vec3::new(1.0, 0.3, 1.0),
+    );
+    let climber = character(&mut world, Vec3::new(0.0, 0.0, 0.0));
+    let blocked = character(&mut world, Vec3::new(3.0, 0.0, 2.0));
+
+    step(&mut world, 5);
+    world
+        .character_controller_system
+        .set_movement(&climber, Vec3::new(3.0, 0.0, 0.0));
+    world
+        .character_controller_system
+        .set_movement(&blocked, Vec3::new(0.0, 0.0, 3.0));
+    step(&mut world, 60);
+
+    let climbed = position(&world, &climber);
+    assert!(
+        (climbed.y - 0.25).abs() < 0.03 && climbed.x > 2.5,
+        "{:?}",
+        climbed
+    );
+    assert!(world.character_controller_system.is_grounded(&climber));
+
+    let stopped = position(&world, &blocked);
+    assert!(stopped.z < 3.7 && stopped.y.abs() < 0.03, "{:?}", stopped);
+}
+
+#[test]
+fn walks_up_gentle_slopes() {
+    let mut world = World::new();
+    floor(&mut world);
+    ramp(&mut world, 2.5, -0.4);
+    let character = character(&mut world, Vec3::new(0.0, 0.0, 0.0));
+    let mut slope_normal = None;
+
+    step(&mut world, 5);
+    world
+        .character_controller_system
+        .set_movement(&character, Vec3::new(2.0, 0.0, 0.0));
+
+    for _ in 0..120 {
+        step(&mut world, 1);
+
+        if let Some(normal) = world
+            .character_controller_system
+            .get_ground_normal(&character)
+            .filter(|normal| normal.y < 0.99)
+        {
+            slope_normal = Some(normal);
+        }
+    }
+
+    assert!(position(&world, &character).y > 0.3);
+    assert!(world.character_controller_system.is_grounded(&character));
+    assert!((slope_normal.unwrap().y - 0.4f32.cos()).abs() < 0.01);
+}
+
+#[test]
+fn slides_back_down_steep_slopes() {
+    let mut world = World::new();
+    floor(&mut world);
+    ramp(&mut world, -2.5, 1.1);
+    let character = character(&mut world, Vec3::new(0.0, 0.0, 0.0));
+    let mut highest: f32 = 0.0;
+
+    step(&mut world, 5);
+    world
+        .character_controller_system
+        .set_movement(&character, Vec3::new(-2.0, 0.0, 0.0));
+
+    for _ in 0..120 {
+        step(&mut world, 1);
+        highest = highest.max(position(&world, &character).y);
+    }
+
+    assert!(highest < 0.3, "{}", highest);
+}
+
+#[test]
+fn snaps_down_stairs() {
+    let mut world = World::new();
+    floor(&mut world);
+
+    for i in 0..4 {
+        let height = 0.8 - 0.2 * i as f32;
+        block(
+            &mut world,
+            Vec3::new(i as f32, height * 0.5, 0.0),
+            Vec3::new(0.5, height * 0.5, 1.0),
+        );
+    }
+
+    let character = character(&mut world, Vec3::new(0.0, 0.8, 0.0));
+    step(&mut world, 5);
+    assert!(world.character_controller_system.is_grounded(&character));
+
+    world
+        .character_controller_system
+        .set_movement(&character, Vec3::new(2.0, 0.0, 0.0));
+
+    for _ in 0..120 {
+        step(&mut world, 1);
+        assert!(world.character_controller_system.is_grounded(&character));
+    }
+
+    let bottom = position(&world, &character);
+    assert!(bottom.y.abs() < 0.03 && bottom.x > 3.9, "{:?}", bottom);
+}
+
+#[test]
+fn jumps_shortly_after_walking_off_a_ledge_but_only_once() {
+    let mut world = World::new();
+    block(
+        &mut world,
+        Vec3::new(0.0, -0.5, 0.0),
+        Vec3::new(1.0, 0.5, 1.0),
+    );
+    let character = character(&mut world, Vec3::new(0.0, 0.0, 0.0));
+
+    step(&mut world, 3);
+    world
+        .character_controller_system
+        .set_movement(&character, Vec3::new(3.0, 0.0, 0.0));
+
+    for _ in 0..120 {
+        step(&mut world, 1);
+
+        if !world.character_controller_system.is_grounded(&character) {
+            break;
+        }
+    }
+
+    assert!(!world.character_controller_system.is_grounded(&character));
+
+    step(&mut world, 3);
+    world.character_controller_system.jump(&character);
+    step(&mut world, 1);
+    let jumped = velocity(&world, &character);
+    assert!(jumped.y > 4.0, "{:?}", jumped);
+
+    // No jumping again in the air.
+    step(&mut world, 2);
+    world.character_controller_system.jump(&character);
+    step(&mut world, 1);
+    assert!(velocity(&world, &character).y < jumped.y - 0.1);
+}
+
+#[test]
+fn jumps_pressed_right_before_landing_are_buffered() {
+    let mut world = World::new();
+    floor(&mut world);
+    let character = character(&mut world, Vec3::new(0.0, 0.3, 0.0));
+    let mut pressed = false;
+
+    for _ in 0..60 {
+        let height = position(&world, &character).y;
+
+        if !pressed
+            && height > 0.0
+            && height < 0.08
+            && !world.character_controller_system.is_grounded(&character)
+        {
+            world.character_controller_system.jump(&character);
+            pressed = true;
+        }
+
+        step(&mut world, 1);
+
+        if pressed && velocity(&world, &character).y > 4.0 {
+            return;
+        }
+    }
+
+    panic!("no buffered jump, pressed: {}", pressed);
+}
+
+#[test]
+fn rides_moving_platforms() {
+    let mut world = World::new();
+    let platform = world
+        .spawn()
+        .with_transformation(TransformationBuilder::new().at_position(Vec3::new(0.0, -0.25, 0.0)))
+        .with_rigid_body(
+            RigidBodyBuilder::new()
+                .with_extents(Vec3::new(2.0, 0.25, 2.0))
+                .with_velocity(Vec3::new(1.0, 0.5, 0.0)),
+        )
+        .build();
+    let character = character(&mut world, Vec3::new(0.0, 0.0, 0.0));
+
+    step(&mut world, 60);
+
+    let feet = position(&world, &character);
+    let top = position(&world, &platform) + Vec3::new(0.0, 0.25, 0.0);
+    assert!(world.character_controller_system.is_grounded(&character));
+    assert!((feet - top).length() < 0.05, "{:?} {:?}", feet, top);
+
+    // Jumping off keeps the velocity of the platform.
+    world.character_controller_system.jump(&character);
+    step(&mut world, 1);
+    assert!((velocity(&world, &character).x - 1.0).abs() < 0.05);
+}
+
+#[test]
+fn ceilings_stop_jumps() {
+    let mut world = World::new();
+    floor(&mut world);
+    block(
+        &mut world,
+        Vec3::new(0.0, 2.5, 0.0),
+        Vec3::new(2.0, 0.25, 2.0),
+    );
+    let character = character(&mut world, Vec3::new(0.0, 0.0, 0.0));
+    let mut highest: f32 = 0.0;
+
+    step(&mut world, 3);
+    world.character_controller_system.jump(&character);
+
+    for _ in 0..60 {
+        step(&mut world, 1);
+        highest = highest.max(position(&world, &character).y);
+    }
+
+    // The ceiling is at 2.25 and the character 1.8 tall.
+    assert!(highest > 0.4 && highest < 0.47, "{}", highest);
+    assert!(world.character_controller_system.is_grounded(&character));
+}
@@ -0,0 +1,142 @@
+use black_grimoire::ecs::command_buffer::CommandBuffer;
+use black_grimoire::ecs::components::character_controller::CharacterControllerBuilder;
+use black_grimoire::ecs::components::health::HealthBuilder;
+use black_grimoire::ecs::components::name::NameBuilder;
+use black_grimoire::ecs::components::rigid_body::RigidBodyBuilder;
+use black_grimoire::ecs::components::transformation::TransformationBuilder;
+use black_grimoire::ecs::world::World;
+use black_grimoire::gamemath::Vec3;
+
+#[test]
+fn applies_deferred_spawns_and_despawns() {
+    let mut world = World::new();
+    let mut commands = CommandBuffer::new();
+    let spawned = commands.create_entity(&world.entity_manager);
+    let despawned = commands.create_entity(&world.entity_manager);
+    commands.add_transformation(
+        &spawned,
+        TransformationBuilder::new().at_position(Vec3::new(1.0, 2.0, 3.0)),
+    );
+
+    // Reserved handles are never handed out twice, even when spawning directly in between.
+    let direct = world
+        .spawn()
+        .with_transformation(TransformationBuilder::new())
+        .build();
+    assert!(direct != spawned && direct != despawned);
+
+    commands.destroy_entity(&despawned);
+    commands.apply(&mut world, None);
+
+    assert!(world.entity_manager.entity_is_active(&spawned));
+    assert!(!world.entity_manager.entity_is_alive(&despawned));
+    assert_eq!(
+        world.transformation_system.get_position(&spawned),
+        Some(Vec3::new(1.0, 2.0, 3.0))
+    );
+}
+
+#[test]
+fn commands_for_dead_entities_leave_no_rows() {
+    let mut world = World::new();
+    let killed = world
+        .spawn()
+        .with_transformation(TransformationBuilder::new())
+        .build();
+    let despawned = world
+        .spawn()
+        .with_transformation(TransformationBuilder::new())
+        .build();
+    let mut commands = CommandBuffer::new();
+
+    commands.add_health(&killed, HealthBuilder::new());
+    commands.add_rigid_body(&killed, RigidBodyBuilder::new());
+    commands.add_name(&killed, NameBuilder::new().with_name("killed"));
+
+    // Destroyed by an earlier command of the same buffer.
+    commands.destroy_entity(&despawned);
+    commands.add_health(&despawned, HealthBuilder::new());
+    commands.add_transformation(&despawned, TransformationBuilder::new());
+
+    world.despawn(&killed).unwrap();
+    commands.apply(&mut world, None);
+
+    assert!(world.health_system.components().is_empty());
+    assert!(world.rigid_body_system.components().is_empty());
+    assert!(world.find_entity_by_name("killed").is_none());
+    assert!(!world
+        .transformation_system
+        .entity_has_transformation(&despawned));
+    assert!(world.transformation_system.components().is_empty());
+}
+
+#[test]
+fn clearing_releases_the_entities_it_would_have_created() {
+    let mut world = World::new();
+    let mut commands = CommandBuffer::new();
+    let reserved: Vec<_> = (0..3)
+        .map(|_| commands.create_entity(&world.entity_manager))
+        .collect();
+    commands.add_transformation(&reserved[0], TransformationBuilder::new());
+
+    commands.clear(&mut world.entity_manager);
+    world.remove_destroyed_entities();
+
+    assert!(commands.is_empty());
+    assert!(reserved
+        .iter()
+        .all(|e| !world.entity_manager.entity_is_alive(e)));
+    assert!(world.transformation_system.components().is_empty());
+}
+
+// Dropping a buffer without applying or clearing it can't give the reserved slots back, they
+// stay taken by inactive entities without components.
+#[test]
+fn dropping_keeps_the_entities_it_would_have_created_inactive() {
+    let mut world = World::new();
+    let mut commands = CommandBuffer::new();
+    let reserved = commands.create_entity(&world.entity_manager);
+    commands.add_transformation(&reserved, TransformationBuilder::new());
+    drop(commands);
+
+    let spawned = world.spawn().build();
+
+    assert!(spawned != reserved);
+    assert!(world.entity_manager.entity_is_alive(&reserved));
+    assert!(!world.entity_manager.entity_is_active(&reserved));
+    assert!(!world
+        .transformation_system
+        .entity_has_transformation(&reserved));
+}
+
+#[test]
+fn adds_and_removes_character_controllers() {
+    let mut world = World::new();
+    let mut commands = CommandBuffer::new();
+    let player = commands.create_entity(&world.entity_manager);
+    let killed = world
+        .spawn()
+        .with_transformation(TransformationBuilder::new())
+        .build();
+
+    commands.add_transformation(&player, TransformationBuilder::new());
+    commands.add_character_controller(&player, CharacterControllerBuilder::new());
+    commands.add_character_controller(&killed, CharacterControllerBuilder::new());
+    world.despawn(&killed).unwrap();
+    commands.apply(&mut world, None);
+
+    assert!(world
+        .character_controller_system
+        .entity_has_character_controller(&player));
+    assert_eq!(world.character_controller_system.components().len(), 1);
+
+    commands.remove_character_controller(&player);
+    commands.apply(&mut world, None);
+
+    assert!(!world
+        .character_controller_system
+        .entity_has_character_controller(&player));
+    assert!(world
+        .transformation_system
+        .entity_has_transformation(&player));
+}
@@ -0,0 +1,102 @@
+use black_grimoire::ecs::components::rigid_body::config::PhysicsConfig;
+use black_grimoire::ecs::components::rigid_body::RigidBodyBuilder;
+use black_grimoire::ecs::components::transformation::TransformationBuilder;
+use black_grimoire::ecs::world::World;
+use black_grimoire::gamemath::Vec3;
+
+fn assert_interpolation_factor(world: &World, expected: f32) {
+    let factor = world.rigid_body_system.get_interpolation_factor();
+    assert!(
+        (factor - expected).abs() < 1.0e-4,
+        "{} {}",
+        factor,
+        expected
+    );
+}
+
+#[test]
+fn substeps_are_capped_and_gravity_is_scaled() {
+    let mut world = World::new();
+    let body = world
+        .spawn()
+        .with_transformation(TransformationBuilder::new())
+        .with_rigid_body(RigidBodyBuilder::new().with_mass(1.0))
+        .build();
+    let floating = world
+        .spawn()
+        .with_transformation(TransformationBuilder::new().at_position(Vec3::new(5.0, 0.0, 0.0)))
+        .with_rigid_body(
+            RigidBodyBuilder::new()
+                .with_mass(1.0)
+                .with_gravity_scale(0.5),
+        )
+        .build();
+
+    world.rigid_body_system.set_config(PhysicsConfig {
+        gravity: Vec3::new(0.0, -10.0, 0.0),
+        fixed_step: 0.125,
+        max_substeps: 2,
+        ..PhysicsConfig::default()
+    });
+    world
+        .rigid_body_system
+        .update(1.05, &mut world.transformation_system);
+
+    // More than a second passed, but only two steps of 0.125 were taken.
+    let position = world.transformation_system.get_position(&body).unwrap();
+    assert!((position.y + 0.46875).abs() < 1.0e-4, "{:?}", position);
+
+    let position = world.transformation_system.get_position(&floating).unwrap();
+    assert!((position.y + 0.234375).abs() < 1.0e-4, "{:?}", position);
+
+    // Of the time that couldn't be simulated only the part of a step is kept.
+    assert_interpolation_factor(&world, (1.05 % 0.125) / 0.125);
+
+    // Had the rest been carried over, the next updates would take steps right away.
+    world
+        .rigid_body_system
+        .update(0.05, &mut world.transformation_system);
+    assert_interpolation_factor(&world, 0.1 / 0.125);
+
+    let position = world.transformation_system.get_position(&body).unwrap();
+    assert!((position.y + 0.46875).abs() < 1.0e-4, "{:?}", position);
+
+    world
+        .rigid_body_system
+        .update(0.05, &mut world.transformation_system);
+    assert_interpolation_factor(&world, 0.025 / 0.125);
+
+    let position = world.transformation_system.get_position(&body).unwrap();
+    assert!((position.y + 0.9375).abs() < 1.0e-4, "{:?}", position);
+}
+
+#[test]
+fn linear_damping_slows_bodies_down() {
+    let mut world = World::new();
+    let body = world
+        .spawn()
+        .with_transformation(TransformationBuilder::new())
+        .with_rigid_body(
+            RigidBodyBuilder::new()
+                .with_mass(1.0)
+                .with_velocity(Vec3::new(0.0, -3.0, 0.0)),
+        )
+        .build();
+
+    let mut config = *world.rigid_body_system.get_config();
+    config.gravity = Vec3::new(0.0, 0.0, 0.0);
+    config.fixed_step = 0.1;
+    config.linear_damping = 1.0;
+    world.rigid_body_system.set_config(config);
+    world
+        .rigid_body_system
+        .update(0.1, &mut world.transformation_system);
+
+    let velocity = world
+        .rigid_body_system
+        .components()
+        .get(&body)
+        .unwrap()
+        .velocity;
+    assert!((velocity.y + 3.0 / 1.1).abs() < 1.0e-4, "{:?}", velocity);
+}
@@ -0,0 +1,77 @@
+use black_grimoire::ecs::components::health::HealthBuilder;
+use black_grimoire::ecs::components::rigid_body::RigidBodyBuilder;
+use black_grimoire::ecs::components::transformation::TransformationBuilder;
+use black_grimoire::ecs::query::query;
+use black_grimoire::ecs::world::World;
+use black_grimoire::gamemath::Vec3;
+
+const STEP: f32 = 1.0 / 60.0;
+
+#[test]
+fn killed_entity_stops_colliding() {
+    let mut world = World::new();
+    let floor = world
+        .spawn()
+        .with_transformation(TransformationBuilder::new())
+        .with_rigid_body(RigidBodyBuilder::new().with_extents(Vec3::new(5.0, 0.5, 5.0)))
+        .with_health(HealthBuilder::new())
+        .build();
+    let body = world
+        .spawn()
+        .with_transformation(TransformationBuilder::new().at_position(Vec3::new(0.0, 1.2, 0.0)))
+        .with_rigid_body(RigidBodyBuilder::new().with_mass(1.0))
+        .build();
+
+    world.health_system.kill_entity(&floor);
+    world.health_system.update(&mut world.entity_manager);
+    world.remove_destroyed_entities();
+
+    assert!(!world.entity_manager.entity_is_alive(&floor));
+    assert!(!world.rigid_body_system.entity_has_rigid_body(&floor));
+    assert!(!world.health_system.entity_has_health(&floor));
+    assert!(!world
+        .transformation_system
+        .entity_has_transformation(&floor));
+
+    for _ in 0..60 {
+        world
+            .rigid_body_system
+            .update(STEP, &mut world.transformation_system);
+    }
+
+    assert!(world.transformation_system.get_position(&body).unwrap().y < 0.0);
+    assert!(world
+        .rigid_body_system
+        .get_collision_events()
+        .iter()
+        .all(|e| e.get_other(&body) != Some(floor)));
+}
+
+// Drawables need a GL context, but they are drawn by joining them with the transformations, so
+// an entity without a transformation row can't produce render jobs.
+#[test]
+fn despawned_entity_leaves_no_rows_to_draw() {
+    let mut world = World::new();
+    let kept = world
+        .spawn()
+        .with_transformation(TransformationBuilder::new())
+        .build();
+    let despawned = world
+        .spawn()
+        .with_transformation(TransformationBuilder::new())
+        .with_rigid_body(RigidBodyBuilder::new().with_mass(1.0))
+        .build();
+
+    world.despawn(&despawned).unwrap();
+
+    let drawn: Vec<_> = query(
+        &world.entity_manager,
+        (world.transformation_system.components(),),
+    )
+    .map(|(entity, _)| entity)
+    .collect();
+
+    assert_eq!(drawn, vec![kept]);
+    assert!(!world.rigid_body_system.entity_has_rigid_body(&despawned));
+    assert!(world.despawn(&despawned).is_err());
+}
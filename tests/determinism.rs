@@ -0,0 +1,129 @@
+use black_grimoire::ecs::components::rigid_body::config::PhysicsConfig;
+use black_grimoire::ecs::components::rigid_body::joint::{JointBuilder, JointKind};
+use black_grimoire::ecs::components::rigid_body::RigidBodyBuilder;
+use black_grimoire::ecs::components::transformation::TransformationBuilder;
+use black_grimoire::ecs::world::World;
+use black_grimoire::gamemath::Vec3;
+use black_grimoire::utilities::{portable_atan2, portable_sin_cos};
+
+const STEP: f32 = 1.0 / 60.0;
+
+fn body(i: usize) -> RigidBodyBuilder {
+    let builder = match i % 2 {
+        0 => RigidBodyBuilder::new().with_sphere(0.5),
+        _ => RigidBodyBuilder::new().with_oriented_extents(Vec3::new(0.4, 0.4, 0.4)),
+    };
+
+    builder
+        .with_mass(1.0)
+        .with_angular_velocity(Vec3::new(0.3 * (i % 5) as f32, 1.0, 0.0))
+        .with_velocity(Vec3::new((i % 3) as f32 - 1.0, 0.0, 0.5))
+}
+
+// Three layers of spinning bodies dropped on a floor, two of them joined by a hinge. Shuffling
+// removes and adds back every seventh body, which moves the rows around without changing the
+// bodies. Returns the state hash after every step.
+fn run(shuffle: bool) -> Vec<u64> {
+    let mut world = World::new();
+    world.rigid_body_system.set_config(PhysicsConfig {
+        deterministic: true,
+        ..PhysicsConfig::default()
+    });
+    world
+        .spawn()
+        .with_transformation(TransformationBuilder::new().at_position(Vec3::new(0.0, -0.5, 0.0)))
+        .with_rigid_body(RigidBodyBuilder::new().with_extents(Vec3::new(50.0, 0.5, 50.0)))
+        .build();
+
+    let bodies: Vec<_> = (0..300)
+        .map(|i| {
+            let position = Vec3::new(
+                (i % 10) as f32 * 0.9 - 4.0,
+                (i / 100) as f32 * 1.2 + 0.6 + (i % 3) as f32 * 0.05,
+                ((i / 10) % 10) as f32 * 0.9 - 4.0,
+            );
+
+            world
+                .spawn()
+                .with_transformation(TransformationBuilder::new().at_position(position))
+                .with_rigid_body(body(i))
+                .build()
+        })
+        .collect();
+
+    world
+        .rigid_body_system
+        .add_joint(
+            &bodies[0],
+            Some(&bodies[1]),
+            JointBuilder::new(JointKind::Hinge(Vec3::new(0.0, 0.0, 1.0))).with_limits(-0.3, 0.3),
+            &world.transformation_system,
+        )
+        .unwrap();
+
+    if shuffle {
+        for i in (7..bodies.len()).step_by(7) {
+            world
+                .rigid_body_system
+                .remove_rigid_body_from_entity(&bodies[i]);
+            world.rigid_body_system.add_rigid_body_to_entity(
+                &bodies[i],
+                body(i),
+                &world.transformation_system,
+            );
+        }
+    }
+
+    let mut hashes = Vec::new();
+
+    for _ in 0..120 {
+        world
+            .rigid_body_system
+            .update(STEP, &mut world.transformation_system);
+        hashes.extend_from_slice(world.rigid_body_system.get_step_hashes());
+    }
+
+    hashes
+}
+
+#[test]
+fn portable_math_matches_std() {
+    for i in -20000..20000 {
+        let x = i as f32 * 0.0037;
+        let (sin, cos) = portable_sin_cos(x);
+
+        assert!((sin - x.sin()).abs() < 1.0e-6, "sin({})", x);
+        assert!((cos - x.cos()).abs() < 1.0e-6, "cos({})", x);
+
+        for j in -30..30 {
+            let y = j as f32 * 0.37;
+
+            assert!(
+                (portable_atan2(y, x) - y.atan2(x)).abs() < 1.0e-6,
+                "atan2({}, {})",
+                y,
+                x
+            );
+        }
+    }
+
+    assert_eq!(portable_atan2(0.0, 0.0), 0.0);
+}
+
+#[test]
+fn same_hashes_every_run() {
+    let hashes = run(false);
+
+    assert_eq!(hashes.len(), 120);
+    assert_eq!(hashes, run(false));
+
+    // The state changes from step to step, so the hashes do too.
+    let mut unique = hashes.clone();
+    unique.dedup();
+    assert!(unique.len() > 100);
+}
+
+#[test]
+fn same_hashes_whatever_order_bodies_were_added_in() {
+    assert_eq!(run(false), run(true));
+}
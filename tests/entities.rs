@@ -0,0 +1,71 @@
+use black_grimoire::ecs::{Entity, EntityError, EntityManager};
+
+#[test]
+fn reused_slots_keep_stale_handles_dead() {
+    let mut entity_manager = EntityManager::new();
+    let first = entity_manager.create_new_entity();
+    let mut stale = vec![first];
+
+    // Far more reuses than the old 8-bit generation allowed.
+    for _ in 0..5000 {
+        entity_manager
+            .destroy_entity(stale.last().unwrap())
+            .unwrap();
+        stale.push(entity_manager.create_new_entity());
+    }
+
+    let current = stale.pop().unwrap();
+    assert!(entity_manager.entity_is_alive(&current));
+    assert!(stale.iter().all(|e| !entity_manager.entity_is_alive(e)));
+}
+
+#[test]
+fn forged_retired_generation_is_dead() {
+    let mut entity_manager = EntityManager::new();
+    let entity = entity_manager.create_new_entity();
+    let forged = Entity::from_bits((entity.to_bits() & 0xFFFF_FFFF) | (0xFFFF_FFFF << 32));
+
+    assert_eq!(
+        entity_manager.validate_entity(&forged),
+        Err(EntityError::Dead)
+    );
+    assert_eq!(
+        entity_manager.destroy_entity(&forged),
+        Err(EntityError::Dead)
+    );
+    assert!(entity_manager.entity_is_alive(&entity));
+}
+
+#[test]
+fn foreign_and_dead_handles_fail_without_panicking() {
+    let mut entity_manager = EntityManager::new();
+    let mut other = EntityManager::new();
+
+    for _ in 0..10 {
+        other.create_new_entity();
+    }
+
+    let foreign = other.create_new_entity();
+    assert!(!entity_manager.entity_is_alive(&foreign));
+    assert!(!entity_manager.entity_is_active(&foreign));
+    assert_eq!(
+        entity_manager.destroy_entity(&foreign),
+        Err(EntityError::Unknown)
+    );
+    assert!(!entity_manager.entity_is_active(&Entity::from_bits(u64::MAX)));
+
+    let entity = entity_manager.create_new_entity();
+    assert_eq!(entity_manager.destroy_entity(&entity), Ok(()));
+    assert_eq!(
+        entity_manager.destroy_entity(&entity),
+        Err(EntityError::Dead)
+    );
+    assert_eq!(
+        entity_manager.destroy_entity(&Entity::null()),
+        Err(EntityError::Null)
+    );
+    assert_eq!(entity_manager.get_destroyed_entities(), &[entity]);
+
+    let reserved = entity_manager.reserve_entity();
+    assert!(!entity_manager.entity_is_alive(&reserved));
+}
@@ -0,0 +1,65 @@
+use black_grimoire::ecs::components::health::HealthBuilder;
+use black_grimoire::ecs::components::rigid_body::event::CollisionEventKind;
+use black_grimoire::ecs::components::rigid_body::RigidBodyBuilder;
+use black_grimoire::ecs::components::transformation::TransformationBuilder;
+use black_grimoire::ecs::world::World;
+use black_grimoire::gamemath::Vec3;
+
+const STEP: f32 = 1.0 / 60.0;
+
+#[test]
+fn falling_through_a_trigger_begins_stays_and_ends() {
+    let mut world = World::new();
+    let zone = world
+        .spawn()
+        .with_transformation(TransformationBuilder::new().at_position(Vec3::new(0.0, 5.0, 0.0)))
+        .with_rigid_body(
+            RigidBodyBuilder::new()
+                .with_extents(Vec3::new(2.0, 1.0, 2.0))
+                .is_trigger()
+                .dealing_damage(1.0),
+        )
+        .build();
+    world
+        .spawn()
+        .with_transformation(TransformationBuilder::new())
+        .with_rigid_body(RigidBodyBuilder::new().with_extents(Vec3::new(10.0, 0.5, 10.0)))
+        .build();
+    let body = world
+        .spawn()
+        .with_transformation(TransformationBuilder::new().at_position(Vec3::new(0.0, 8.0, 0.0)))
+        .with_rigid_body(RigidBodyBuilder::new().with_mass(1.0))
+        .with_health(HealthBuilder::new().with_hitpoints((100.0, 100.0)))
+        .build();
+    let mut kinds = Vec::new();
+
+    for _ in 0..180 {
+        world
+            .rigid_body_system
+            .update(STEP, &mut world.transformation_system);
+        world
+            .health_system
+            .apply_collision_damage(&world.rigid_body_system);
+
+        for event in world.rigid_body_system.get_collision_events() {
+            if event.get_other(&body) == Some(zone) {
+                assert!(event.is_trigger());
+                kinds.push(event.get_kind());
+            }
+        }
+    }
+
+    // Triggers don't stop the body, it comes to rest on the floor below.
+    let position = world.transformation_system.get_position(&body).unwrap();
+    assert!((position.y - 1.0).abs() < 0.05, "{:?}", position);
+
+    assert_eq!(kinds.first(), Some(&CollisionEventKind::Begin));
+    assert_eq!(kinds.last(), Some(&CollisionEventKind::End));
+    assert!(kinds[1..kinds.len() - 1]
+        .iter()
+        .all(|kind| *kind == CollisionEventKind::Stay));
+
+    // Damage is dealt on every step inside the zone, but not when leaving it.
+    let hitpoints = world.health_system.get_hitpoints(&body).unwrap().0;
+    assert_eq!(hitpoints, 100.0 - (kinds.len() - 1) as f32);
+}
@@ -0,0 +1,66 @@
+use black_grimoire::ecs::components::rigid_body::RigidBodyBuilder;
+use black_grimoire::ecs::components::transformation::TransformationBuilder;
+use black_grimoire::ecs::world::World;
+use black_grimoire::gamemath::Vec3;
+
+const STEP: f32 = 1.0 / 60.0;
+
+fn moving_body(world: &mut World) -> black_grimoire::ecs::Entity {
+    world
+        .spawn()
+        .with_transformation(TransformationBuilder::new())
+        .with_rigid_body(
+            RigidBodyBuilder::new()
+                .with_mass(1.0)
+                .is_gravity_immune()
+                .with_velocity(Vec3::new(60.0, 0.0, 0.0)),
+        )
+        .build()
+}
+
+#[test]
+fn blends_between_the_last_two_steps() {
+    let mut world = World::new();
+    let entity = moving_body(&mut world);
+
+    // One step of a metre, with a quarter of a step left in the accumulator.
+    world
+        .rigid_body_system
+        .update(STEP * 1.25, &mut world.transformation_system);
+    let factor = world.rigid_body_system.get_interpolation_factor();
+    assert!((factor - 0.25).abs() < 1.0e-3);
+
+    let position = world
+        .transformation_system
+        .get_interpolated_position(&entity, factor)
+        .unwrap();
+    assert!((position.x - 0.25).abs() < 1.0e-3);
+
+    // Half a step later, still without stepping.
+    world
+        .rigid_body_system
+        .update(STEP * 0.5, &mut world.transformation_system);
+    let factor = world.rigid_body_system.get_interpolation_factor();
+    let position = world
+        .transformation_system
+        .get_interpolated_position(&entity, factor)
+        .unwrap();
+    assert!((position.x - 0.75).abs() < 1.0e-3);
+}
+
+#[test]
+fn reset_snaps_to_the_current_pose() {
+    let mut world = World::new();
+    let entity = moving_body(&mut world);
+
+    world
+        .rigid_body_system
+        .update(STEP * 1.25, &mut world.transformation_system);
+    world.transformation_system.reset_interpolation(&entity);
+
+    let position = world
+        .transformation_system
+        .get_interpolated_position(&entity, 0.25)
+        .unwrap();
+    assert!((position.x - 1.0).abs() < 1.0e-3);
+}
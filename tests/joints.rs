@@ -0,0 +1,234 @@
+use black_grimoire::ecs::components::rigid_body::joint::{JointBuilder, JointKind};
+use black_grimoire::ecs::components::rigid_body::RigidBodyBuilder;
+use black_grimoire::ecs::components::transformation::TransformationBuilder;
+use black_grimoire::ecs::world::World;
+use black_grimoire::ecs::Entity;
+use black_grimoire::gamemath::Vec3;
+
+const STEP: f32 = 1.0 / 60.0;
+
+fn body(world: &mut World, position: Vec3<f32>) -> Entity {
+    world
+        .spawn()
+        .with_transformation(TransformationBuilder::new().at_position(position))
+        .with_rigid_body(
+            RigidBodyBuilder::new()
+                .with_oriented_extents(Vec3::new(0.2, 0.2, 0.2))
+                .with_mass(1.0)
+                .with_collision_mask(0),
+        )
+        .build()
+}
+
+fn step(world: &mut World, steps: usize) {
+    for _ in 0..steps {
+        world
+            .rigid_body_system
+            .update(STEP, &mut world.transformation_system);
+    }
+}
+
+#[test]
+fn distance_joint_keeps_pendulum_length() {
+    let mut world = World::new();
+    let bob = body(&mut world, Vec3::new(2.0, 5.0, 0.0));
+    let handle = world.rigid_body_system.add_joint(
+        &bob,
+        None,
+        JointBuilder::new(JointKind::Distance)
+            .with_anchors(Vec3::new(2.0, 5.0, 0.0), Vec3::new(0.0, 5.0, 0.0)),
+        &world.transformation_system,
+    );
+    assert!(handle.is_some());
+    let mut min_y = 5.0f32;
+    for _ in 0..240 {
+        step(&mut world, 1);
+        let position = world.transformation_system.get_position(&bob).unwrap();
+        let length = (position - Vec3::new(0.0, 5.0, 0.0)).length();
+        assert!((length - 2.0).abs() < 0.05, "{}", length);
+        min_y = min_y.min(position.y);
+    }
+    assert!(min_y < 3.1);
+}
+
+#[test]
+fn chain_hangs_without_coming_apart() {
+    let mut world = World::new();
+    let mut links: Vec<Entity> = Vec::new();
+
+    // The first link hangs from a fixed point, each other link from the previous one.
+    for i in 0..5 {
+        let link = body(&mut world, Vec3::new(0.5 * (i + 1) as f32, 10.0, 0.0));
+        let anchor = match i {
+            0 => Vec3::new(0.0, 10.0, 0.0),
+            _ => Vec3::new(0.5 * i as f32 + 0.25, 10.0, 0.0),
+        };
+        let handle = world.rigid_body_system.add_joint(
+            &link,
+            links.last(),
+            JointBuilder::new(JointKind::Ball).at_anchor(anchor),
+            &world.transformation_system,
+        );
+
+        assert!(handle.is_some());
+        links.push(link);
+    }
+
+    step(&mut world, 600);
+
+    for pair in links.windows(2) {
+        let distance = (world.transformation_system.get_position(&pair[1]).unwrap()
+            - world.transformation_system.get_position(&pair[0]).unwrap())
+        .length();
+
+        assert!((distance - 0.5).abs() < 0.05, "{}", distance);
+    }
+
+    let last = world.transformation_system.get_position(&links[4]).unwrap();
+    assert!(last.y < 8.0, "{:?}", last);
+}
+
+#[test]
+fn hinge_limits_how_far_a_door_opens() {
+    let mut world = World::new();
+    let door = world
+        .spawn()
+        .with_transformation(TransformationBuilder::new().at_position(Vec3::new(0.5, 1.0, 0.0)))
+        .with_rigid_body(
+            RigidBodyBuilder::new()
+                .with_oriented_extents(Vec3::new(0.5, 1.0, 0.05))
+                .with_mass(10.0)
+                .is_gravity_immune()
+                .is_never_sleeping(),
+        )
+        .build();
+    world.rigid_body_system.add_joint(
+        &door,
+        None,
+        JointBuilder::new(JointKind::Hinge(Vec3::new(0.0, 1.0, 0.0)))
+            .at_anchor(Vec3::new(0.0, 1.0, 0.0))
+            .with_limits(-1.0, 1.0),
+        &world.transformation_system,
+    );
+    world.rigid_body_system.apply_impulse_at_point(
+        &door,
+        Vec3::new(0.0, 0.0, -5.0),
+        Vec3::new(1.0, 1.0, 0.0),
+        &world.transformation_system,
+    );
+    step(&mut world, 120);
+    let position = world.transformation_system.get_position(&door).unwrap();
+    let angle = position.z.atan2(position.x);
+    assert!(((position - Vec3::new(0.0, 1.0, 0.0)).length() - 0.5).abs() < 0.03);
+    assert!((position.y - 1.0).abs() < 0.02);
+    // Turning around +y moves +x towards -z.
+    assert!(angle < 0.0 && angle > -1.1, "{}", angle);
+}
+
+#[test]
+fn hinge_motor_drives_wheel() {
+    let mut world = World::new();
+    let wheel = body(&mut world, Vec3::new(0.0, 1.0, 0.0));
+    let handle = world
+        .rigid_body_system
+        .add_joint(
+            &wheel,
+            None,
+            JointBuilder::new(JointKind::Hinge(Vec3::new(0.0, 0.0, 1.0))).with_motor(3.0, 100.0),
+            &world.transformation_system,
+        )
+        .unwrap();
+    step(&mut world, 60);
+    let angular_velocity = world
+        .rigid_body_system
+        .get_angular_velocity(&wheel)
+        .unwrap();
+    assert!((angular_velocity.z - 3.0).abs() < 0.05 && angular_velocity.x.abs() < 0.05);
+    world.rigid_body_system.set_joint_motor(handle, None);
+    assert!(world
+        .rigid_body_system
+        .get_joint(handle)
+        .unwrap()
+        .get_motor()
+        .is_none());
+}
+
+#[test]
+fn slider_limits_travel_along_its_axis() {
+    let mut world = World::new();
+    let slider = body(&mut world, Vec3::new(0.0, 5.0, 0.0));
+    world.rigid_body_system.add_joint(
+        &slider,
+        None,
+        JointBuilder::new(JointKind::Slider(Vec3::new(1.0, 1.0, 0.0)))
+            .at_anchor(Vec3::new(0.0, 5.0, 0.0))
+            .with_limits(-1.0, 1.0),
+        &world.transformation_system,
+    );
+    step(&mut world, 180);
+    let position = world.transformation_system.get_position(&slider).unwrap();
+    let along =
+        (position - Vec3::new(0.0, 5.0, 0.0)).dot(Vec3::new(1.0, 1.0, 0.0) * (0.5f32).sqrt());
+    assert!((along + 1.0).abs() < 0.05, "{}", along);
+    assert!((position.x - position.y + 5.0).abs() < 0.05);
+}
+
+#[test]
+fn fixed_joint_holds_until_it_breaks() {
+    let mut world = World::new();
+    let a = body(&mut world, Vec3::new(0.0, 5.0, 0.0));
+    let b = body(&mut world, Vec3::new(1.0, 5.0, 0.0));
+    world.rigid_body_system.add_joint(
+        &a,
+        None,
+        JointBuilder::new(JointKind::Fixed),
+        &world.transformation_system,
+    );
+    let handle = world
+        .rigid_body_system
+        .add_joint(
+            &b,
+            Some(&a),
+            JointBuilder::new(JointKind::Fixed).breaking_at(50.0),
+            &world.transformation_system,
+        )
+        .unwrap();
+    step(&mut world, 60);
+    let position = world.transformation_system.get_position(&b).unwrap();
+    assert!((position - Vec3::new(1.0, 5.0, 0.0)).length() < 0.05);
+    assert!(world.rigid_body_system.get_joint(handle).is_some());
+    world
+        .rigid_body_system
+        .apply_impulse(&b, Vec3::new(0.0, -10.0, 0.0));
+    let mut broke = false;
+    for _ in 0..5 {
+        step(&mut world, 1);
+        for event in world.rigid_body_system.get_joint_break_events() {
+            assert_eq!(event.get_joint(), handle);
+            assert_eq!(event.get_entities(), (b, Some(a)));
+            broke = true;
+        }
+    }
+    assert!(broke);
+    assert!(world.rigid_body_system.get_joint(handle).is_none());
+    step(&mut world, 30);
+    assert!(world.transformation_system.get_position(&b).unwrap().y < 4.5);
+}
+
+#[test]
+fn removing_a_body_removes_its_joints() {
+    let mut world = World::new();
+    let a = body(&mut world, Vec3::new(0.0, 5.0, 0.0));
+    let handle = world
+        .rigid_body_system
+        .add_joint(
+            &a,
+            None,
+            JointBuilder::new(JointKind::Ball),
+            &world.transformation_system,
+        )
+        .unwrap();
+    world.rigid_body_system.remove_rigid_body_from_entity(&a);
+    assert!(world.rigid_body_system.get_joint(handle).is_none());
+    assert!(!world.rigid_body_system.remove_joint(handle));
+}
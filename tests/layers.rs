@@ -0,0 +1,66 @@
+use black_grimoire::ecs::components::rigid_body::RigidBodyBuilder;
+use black_grimoire::ecs::components::transformation::TransformationBuilder;
+use black_grimoire::ecs::world::World;
+use black_grimoire::ecs::Entity;
+use black_grimoire::gamemath::Vec3;
+
+const STEP: f32 = 1.0 / 60.0;
+
+#[test]
+fn masked_out_layers_pass_through_each_other() {
+    let mut world = World::new();
+    let floor = world
+        .spawn()
+        .with_transformation(TransformationBuilder::new())
+        .with_rigid_body(
+            RigidBodyBuilder::new()
+                .with_extents(Vec3::new(10.0, 0.5, 10.0))
+                .with_collision_layer(2),
+        )
+        .build();
+    let ghost = world
+        .spawn()
+        .with_transformation(TransformationBuilder::new().at_position(Vec3::new(0.0, 2.0, 0.0)))
+        .with_rigid_body(
+            RigidBodyBuilder::new()
+                .with_mass(1.0)
+                .with_collision_mask(1),
+        )
+        .build();
+    let crate_ = world
+        .spawn()
+        .with_transformation(TransformationBuilder::new().at_position(Vec3::new(3.0, 2.0, 0.0)))
+        .with_rigid_body(RigidBodyBuilder::new().with_mass(1.0))
+        .build();
+
+    for _ in 0..120 {
+        world
+            .rigid_body_system
+            .update(STEP, &mut world.transformation_system);
+    }
+
+    let position = world.transformation_system.get_position(&ghost).unwrap();
+    assert!(position.y < -5.0, "{:?}", position);
+
+    let position = world.transformation_system.get_position(&crate_).unwrap();
+    assert!((position.y - 1.0).abs() < 0.05, "{:?}", position);
+
+    let ray = (Vec3::new(0.0, 5.0, 0.0), Vec3::new(0.0, -1.0, 0.0));
+    let hit = world.rigid_body_system.ray_cast(
+        ray,
+        100.0,
+        &world.transformation_system,
+        Entity::null(),
+        2,
+    );
+    assert_eq!(hit.map(|h| h.get_entity()), Some(floor));
+
+    let hit = world.rigid_body_system.ray_cast(
+        ray,
+        100.0,
+        &world.transformation_system,
+        Entity::null(),
+        4,
+    );
+    assert!(hit.is_none());
+}
@@ -0,0 +1,91 @@
+use black_grimoire::ecs::components::rigid_body::RigidBodyBuilder;
+use black_grimoire::ecs::components::transformation::{TransformationBuilder, TransformationData};
+use black_grimoire::ecs::world::World;
+use black_grimoire::gamemath::{Mat4, Quat, Vec3};
+use black_grimoire::renderer::build_model_matrix;
+
+fn expected_matrix(d: &TransformationData) -> Mat4 {
+    build_model_matrix(
+        d.get_world_scale(),
+        d.get_pivot(),
+        d.get_world_rotation(),
+        d.get_world_position(),
+    )
+}
+
+#[test]
+fn cached_matrices_are_rebuilt_only_when_moved() {
+    let mut world = World::new();
+    let parent = world
+        .spawn()
+        .with_transformation(
+            TransformationBuilder::new()
+                .at_position(Vec3::new(1.0, 0.0, 0.0))
+                .with_rotation(Quat::rotation(1.0, Vec3::new(0.0, 0.0, 1.0))),
+        )
+        .build();
+    let child = world
+        .spawn()
+        .with_transformation(
+            TransformationBuilder::new()
+                .at_position(Vec3::new(1.0, 0.0, 0.0))
+                .with_pivot(Vec3::new(0.0, 0.5, 0.0))
+                .with_parent(parent),
+        )
+        .build();
+
+    let ts = &mut world.transformation_system;
+    ts.update();
+    let d = ts.get_transformation_data(&child).unwrap();
+    assert!(!d.is_dirty());
+    assert_eq!(d.get_model_matrix(), expected_matrix(d));
+
+    ts.set_position(&parent, Vec3::new(1.0, 0.0, 0.0));
+    assert!(!ts.get_transformation_data(&parent).unwrap().is_dirty());
+
+    ts.apply_movement(&parent, Vec3::new(0.0, 3.0, 0.0));
+    assert!(ts.get_transformation_data(&parent).unwrap().is_dirty());
+    assert!(!ts.get_transformation_data(&child).unwrap().is_dirty());
+
+    // Until the next update only the system sees that the parent moved.
+    let moved = build_model_matrix(
+        Vec3::new(1.0, 1.0, 1.0),
+        Vec3::new(0.0, 0.5, 0.0),
+        ts.get_world_rotation(&child).unwrap(),
+        ts.get_world_position(&child).unwrap(),
+    );
+    assert_eq!(ts.get_model_matrix(&child), Some(moved));
+
+    ts.update();
+    let d = ts.get_transformation_data(&child).unwrap();
+    assert!(!d.is_dirty());
+    assert_eq!(ts.get_model_matrix(&child), Some(d.get_model_matrix()));
+    assert!((d.get_world_position().y - (3.0 - 1.0f32.sin())).abs() < 1.0e-5);
+    assert_eq!(d.get_model_matrix(), expected_matrix(d));
+}
+
+#[test]
+fn physics_leaves_resting_geometry_clean() {
+    let mut world = World::new();
+    let floor = world
+        .spawn()
+        .with_transformation(TransformationBuilder::new())
+        .with_rigid_body(RigidBodyBuilder::new().with_extents(Vec3::new(5.0, 0.5, 5.0)))
+        .build();
+    let falling = world
+        .spawn()
+        .with_transformation(TransformationBuilder::new().at_position(Vec3::new(0.0, 5.0, 0.0)))
+        .with_rigid_body(RigidBodyBuilder::new().with_mass(1.0))
+        .build();
+
+    for _ in 0..20 {
+        world.transformation_system.update();
+        world
+            .rigid_body_system
+            .update(1.0 / 60.0, &mut world.transformation_system);
+
+        let ts = &world.transformation_system;
+        assert!(!ts.get_transformation_data(&floor).unwrap().is_dirty());
+        assert!(ts.get_transformation_data(&falling).unwrap().is_dirty());
+    }
+}
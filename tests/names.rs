@@ -0,0 +1,51 @@
+use black_grimoire::ecs::components::name::NameBuilder;
+use black_grimoire::ecs::world::World;
+use black_grimoire::ecs::Entity;
+
+fn tagged(world: &World, tag: &str) -> Vec<Entity> {
+    world.get_entities_with_tag(tag).collect()
+}
+
+#[test]
+fn finds_entities_by_name_and_tag() {
+    let mut world = World::new();
+    let player = world
+        .spawn()
+        .with_name(NameBuilder::new().with_name("player").with_tag("hero"))
+        .build();
+    let first = world
+        .spawn()
+        .with_name(NameBuilder::new().with_tag("enemy"))
+        .build();
+    let second = world
+        .spawn()
+        .with_name(NameBuilder::new().with_name("player").with_tag("enemy"))
+        .build();
+
+    assert_eq!(world.find_entity_by_name("player"), Some(player));
+    assert_eq!(tagged(&world, "enemy"), vec![first, second]);
+
+    world.name_system.add_tag(&player, "enemy");
+    world.name_system.remove_tag(&first, "enemy");
+    assert_eq!(tagged(&world, "enemy"), vec![second, player]);
+
+    world.despawn(&player).unwrap();
+    assert_eq!(world.find_entity_by_name("player"), None);
+    assert_eq!(tagged(&world, "enemy"), vec![second]);
+    assert!(tagged(&world, "hero").is_empty());
+    assert!(world.name_system.set_name(&second, "player"));
+}
+
+#[test]
+fn destroyed_entities_stop_resolving_right_away() {
+    let mut world = World::new();
+    let boss = world
+        .spawn()
+        .with_name(NameBuilder::new().with_name("boss").with_tag("enemy"))
+        .build();
+
+    world.entity_manager.destroy_entity(&boss).unwrap();
+
+    assert_eq!(world.find_entity_by_name("boss"), None);
+    assert!(tagged(&world, "enemy").is_empty());
+}
@@ -0,0 +1,117 @@
+use black_grimoire::ecs::query::query;
+use black_grimoire::ecs::storage::{ComponentStorage, ComponentsMut};
+use black_grimoire::ecs::{Entity, EntityManager};
+
+// Six entities, all with a position, every even one with a velocity and every third one with a
+// name.
+fn scene() -> (
+    EntityManager,
+    Vec<Entity>,
+    ComponentStorage<f32>,
+    ComponentStorage<f32>,
+    ComponentStorage<String>,
+) {
+    let mut entity_manager = EntityManager::new();
+    let entities: Vec<_> = (0..6).map(|_| entity_manager.create_new_entity()).collect();
+    let mut positions = ComponentStorage::new();
+    let mut velocities = ComponentStorage::new();
+    let mut names = ComponentStorage::new();
+
+    for (i, entity) in entities.iter().enumerate() {
+        positions.insert(entity, i as f32);
+
+        if i % 2 == 0 {
+            velocities.insert(entity, 10.0 * i as f32);
+        }
+
+        if i % 3 == 0 {
+            names.insert(entity, format!("entity {}", i));
+        }
+    }
+
+    (entity_manager, entities, positions, velocities, names)
+}
+
+#[test]
+fn joins_only_entities_with_every_component() {
+    let (entity_manager, entities, positions, velocities, names) = scene();
+
+    let moving: Vec<_> = query(&entity_manager, (&positions, &velocities))
+        .map(|(entity, (p, v))| (entity, *p, *v))
+        .collect();
+
+    assert_eq!(
+        moving,
+        vec![
+            (entities[0], 0.0, 0.0),
+            (entities[2], 2.0, 20.0),
+            (entities[4], 4.0, 40.0),
+        ]
+    );
+
+    let named: Vec<_> = query(&entity_manager, (&velocities, &names, &positions))
+        .map(|(entity, (_, name, _))| (entity, name.clone()))
+        .collect();
+
+    assert_eq!(named, vec![(entities[0], String::from("entity 0"))]);
+}
+
+// All the mutable components are held at once before any of them is written, so handing out a
+// component must not invalidate the ones handed out before it.
+#[test]
+fn hands_out_mutable_components_that_stay_valid() {
+    let (entity_manager, entities, mut positions, velocities, _) = scene();
+
+    let moving: Vec<_> = query(&entity_manager, (&mut positions, &velocities)).collect();
+
+    for (_, (p, v)) in moving {
+        *p += *v;
+    }
+
+    let mut views: Vec<_> = query(
+        &entity_manager,
+        (ComponentsMut::new(&mut positions), &velocities),
+    )
+    .map(|(_, (p, _))| p)
+    .collect();
+
+    for p in views.iter_mut() {
+        **p *= 2.0;
+    }
+
+    let expected = [0.0, 1.0, 44.0, 3.0, 88.0, 5.0];
+
+    for (entity, expected) in entities.iter().zip(expected) {
+        assert_eq!(positions.get(entity), Some(&expected));
+    }
+}
+
+#[test]
+fn skips_inactive_entities_unless_asked_not_to() {
+    let (mut entity_manager, entities, positions, velocities, _) = scene();
+
+    entity_manager
+        .set_entity_is_active(&entities[2], false)
+        .unwrap();
+    entity_manager
+        .set_entity_is_active(&entities[5], false)
+        .unwrap();
+
+    let active: Vec<_> = query(&entity_manager, (&positions,))
+        .map(|(entity, _)| entity)
+        .collect();
+    let moving: Vec<_> = query(&entity_manager, (&positions, &velocities))
+        .map(|(entity, _)| entity)
+        .collect();
+    let all: Vec<_> = query(&entity_manager, (&positions, &velocities))
+        .including_inactive()
+        .map(|(entity, _)| entity)
+        .collect();
+
+    assert_eq!(
+        active,
+        vec![entities[0], entities[1], entities[3], entities[4]]
+    );
+    assert_eq!(moving, vec![entities[0], entities[4]]);
+    assert_eq!(all, vec![entities[0], entities[2], entities[4]]);
+}
@@ -0,0 +1,199 @@
+use black_grimoire::ecs::components::rigid_body::RigidBodyBuilder;
+use black_grimoire::ecs::components::transformation::TransformationBuilder;
+use black_grimoire::ecs::world::World;
+use black_grimoire::ecs::Entity;
+use black_grimoire::gamemath::{Quat, Vec3};
+use std::f32::consts::{FRAC_PI_2, FRAC_PI_4};
+
+const ALL: u32 = u32::MAX;
+
+struct Scene {
+    world: World,
+    aabb: Entity,
+    sphere: Entity,
+    capsule: Entity,
+    obb: Entity,
+}
+
+// One of each shape in a row along the x axis, at a height of 5.
+fn scene() -> Scene {
+    let mut world = World::new();
+    let aabb = world
+        .spawn()
+        .with_transformation(TransformationBuilder::new().at_position(Vec3::new(10.0, 0.0, 0.0)))
+        .with_rigid_body(
+            RigidBodyBuilder::new()
+                .with_extents(Vec3::new(1.0, 1.0, 1.0))
+                .with_offset(Vec3::new(0.0, 5.0, 0.0)),
+        )
+        .build();
+    let sphere = world
+        .spawn()
+        .with_transformation(TransformationBuilder::new().at_position(Vec3::new(20.0, 5.0, 0.0)))
+        .with_rigid_body(RigidBodyBuilder::new().with_sphere(2.0))
+        .build();
+    let capsule = world
+        .spawn()
+        .with_transformation(
+            TransformationBuilder::new()
+                .at_position(Vec3::new(30.0, 5.0, 0.0))
+                .with_rotation(Quat::rotation(FRAC_PI_2, Vec3::new(0.0, 0.0, 1.0))),
+        )
+        .with_rigid_body(RigidBodyBuilder::new().with_capsule(0.5, 2.0))
+        .build();
+    let obb = world
+        .spawn()
+        .with_transformation(
+            TransformationBuilder::new()
+                .at_position(Vec3::new(40.0, 5.0, 0.0))
+                .with_rotation(Quat::rotation(FRAC_PI_4, Vec3::new(0.0, 0.0, 1.0))),
+        )
+        .with_rigid_body(RigidBodyBuilder::new().with_oriented_extents(Vec3::new(1.0, 1.0, 1.0)))
+        .build();
+
+    Scene {
+        world,
+        aabb,
+        sphere,
+        capsule,
+        obb,
+    }
+}
+
+fn close(a: Vec3<f32>, b: Vec3<f32>) -> bool {
+    (a - b).length() < 1.0e-3
+}
+
+#[test]
+fn ray_hits_the_closest_body() {
+    let scene = scene();
+    let rigid_body_system = &scene.world.rigid_body_system;
+    let transformation_system = &scene.world.transformation_system;
+    let ray = (Vec3::new(0.0, 5.0, 0.0), Vec3::new(2.0, 0.0, 0.0));
+
+    let hit = rigid_body_system
+        .ray_cast(ray, 100.0, transformation_system, Entity::null(), ALL)
+        .unwrap();
+    assert_eq!(hit.get_entity(), scene.aabb);
+    assert!((hit.get_distance() - 9.0).abs() < 1.0e-4);
+    assert!(close(hit.get_normal(), Vec3::new(-1.0, 0.0, 0.0)));
+    assert!(close(hit.get_point(), Vec3::new(9.0, 5.0, 0.0)));
+
+    // Passing below every body, and stopping short of the first one.
+    assert!(rigid_body_system
+        .ray_cast(
+            (Vec3::new(0.0, 0.0, 0.0), ray.1),
+            100.0,
+            transformation_system,
+            Entity::null(),
+            ALL
+        )
+        .is_none());
+    assert!(rigid_body_system
+        .ray_cast(ray, 8.0, transformation_system, Entity::null(), ALL)
+        .is_none());
+
+    // Starting inside a body.
+    let hit = rigid_body_system
+        .ray_cast(
+            (Vec3::new(20.0, 5.0, 0.0), ray.1),
+            100.0,
+            transformation_system,
+            Entity::null(),
+            ALL,
+        )
+        .unwrap();
+    assert_eq!(hit.get_entity(), scene.sphere);
+    assert_eq!(hit.get_distance(), 0.0);
+}
+
+#[test]
+fn ray_cast_all_sorts_hits_by_distance() {
+    let scene = scene();
+    let hits = scene.world.rigid_body_system.ray_cast_all(
+        (Vec3::new(0.0, 5.0, 0.0), Vec3::new(1.0, 0.0, 0.0)),
+        100.0,
+        &scene.world.transformation_system,
+        Entity::null(),
+        ALL,
+    );
+    let entities: Vec<_> = hits.iter().map(|hit| hit.get_entity()).collect();
+
+    assert_eq!(
+        entities,
+        vec![scene.aabb, scene.sphere, scene.capsule, scene.obb]
+    );
+    assert!((hits[1].get_distance() - 18.0).abs() < 1.0e-4);
+
+    // The capsule lies along the x axis, its end cap reaches 2.5 towards the ray.
+    assert!((hits[2].get_distance() - 27.5).abs() < 1.0e-3);
+    assert!(close(hits[2].get_normal(), Vec3::new(-1.0, 0.0, 0.0)));
+
+    // The box is turned by 45 degrees, its corner faces the ray.
+    assert!((hits[3].get_distance() - (40.0 - 2.0f32.sqrt())).abs() < 1.0e-3);
+
+    // The side of the lying capsule, from above.
+    let hit = scene
+        .world
+        .rigid_body_system
+        .ray_cast(
+            (Vec3::new(30.5, 10.0, 0.0), Vec3::new(0.0, -1.0, 0.0)),
+            100.0,
+            &scene.world.transformation_system,
+            Entity::null(),
+            ALL,
+        )
+        .unwrap();
+    assert_eq!(hit.get_entity(), scene.capsule);
+    assert!((hit.get_distance() - 4.5).abs() < 1.0e-3);
+    assert!(close(hit.get_normal(), Vec3::new(0.0, 1.0, 0.0)));
+}
+
+#[test]
+fn shape_casts_stop_at_the_first_touch() {
+    let scene = scene();
+    let rigid_body_system = &scene.world.rigid_body_system;
+    let transformation_system = &scene.world.transformation_system;
+    let direction = Vec3::new(1.0, 0.0, 0.0);
+
+    let hit = rigid_body_system
+        .sphere_cast(
+            0.5,
+            (Vec3::new(0.0, 5.0, 0.0), direction),
+            100.0,
+            transformation_system,
+            Entity::null(),
+            ALL,
+        )
+        .unwrap();
+    assert_eq!(hit.get_entity(), scene.aabb);
+    assert!((hit.get_distance() - 8.5).abs() < 1.0e-3);
+    assert!(close(hit.get_normal(), Vec3::new(-1.0, 0.0, 0.0)));
+
+    let hit = rigid_body_system
+        .sphere_cast(
+            0.5,
+            (Vec3::new(20.0, 15.0, 0.0), Vec3::new(0.0, -1.0, 0.0)),
+            100.0,
+            transformation_system,
+            Entity::null(),
+            ALL,
+        )
+        .unwrap();
+    assert_eq!(hit.get_entity(), scene.sphere);
+    assert!((hit.get_distance() - 7.5).abs() < 1.0e-3);
+
+    // Ignoring the first box, the cast box touches the sphere off center.
+    let hit = rigid_body_system
+        .box_cast(
+            (Vec3::new(0.5, 0.5, 0.5), Quat::identity()),
+            (Vec3::new(0.0, 6.2, 0.0), direction),
+            100.0,
+            transformation_system,
+            scene.aabb,
+            ALL,
+        )
+        .unwrap();
+    assert_eq!(hit.get_entity(), scene.sphere);
+    assert!(hit.get_distance() > 17.0 && hit.get_distance() < 18.5);
+}
@@ -0,0 +1,202 @@
+use black_grimoire::ecs::components::rigid_body::RigidBodyBuilder;
+use black_grimoire::ecs::components::transformation::TransformationBuilder;
+use black_grimoire::ecs::world::World;
+use black_grimoire::gamemath::{Quat, Vec3};
+use black_grimoire::utilities::{quat_integrate, quat_rotate_vector};
+
+const STEP: f32 = 1.0 / 60.0;
+
+#[test]
+fn torque_spins_bodies_up() {
+    let mut world = World::new();
+    let body = world
+        .spawn()
+        .with_transformation(TransformationBuilder::new())
+        .with_rigid_body(
+            RigidBodyBuilder::new()
+                .with_sphere(1.0)
+                .with_mass(2.5)
+                .is_gravity_immune()
+                .is_never_sleeping(),
+        )
+        .build();
+    // The inertia of the sphere is 0.4 * 2.5 * 1^2 = 1.
+    world
+        .rigid_body_system
+        .apply_torque(&body, Vec3::new(0.0, 6.0, 0.0));
+    world
+        .rigid_body_system
+        .update(STEP * 5.0 + 1e-4, &mut world.transformation_system);
+    let angular_velocity = world.rigid_body_system.get_angular_velocity(&body).unwrap();
+    assert!((angular_velocity.y - 0.5).abs() < 1e-3);
+    world
+        .rigid_body_system
+        .update(1.0, &mut world.transformation_system);
+    let later = world.rigid_body_system.get_angular_velocity(&body).unwrap();
+    assert!(
+        (later.y - 0.5).abs() < 1e-3,
+        "torque should be cleared after a step"
+    );
+    let rotation = world
+        .transformation_system
+        .get_transformation_data(&body)
+        .unwrap()
+        .get_rotation();
+    let right = quat_rotate_vector(&rotation, Vec3::new(1.0, 0.0, 0.0));
+    // Turning around +y right handed moves +x towards -z.
+    assert!(right.z < -0.05);
+}
+
+#[test]
+fn off_center_impulses_add_spin() {
+    let mut world = World::new();
+    let body = world
+        .spawn()
+        .with_transformation(TransformationBuilder::new())
+        .with_rigid_body(
+            RigidBodyBuilder::new()
+                .with_oriented_extents(Vec3::new(1.0, 1.0, 1.0))
+                .with_mass(1.0)
+                .is_gravity_immune(),
+        )
+        .build();
+    world.rigid_body_system.apply_impulse_at_point(
+        &body,
+        Vec3::new(0.0, 0.0, 1.0),
+        Vec3::new(1.0, 0.0, 0.0),
+        &world.transformation_system,
+    );
+    let angular_velocity = world.rigid_body_system.get_angular_velocity(&body).unwrap();
+    // The lever arm crossed with the impulse is (0, -1, 0), with an inertia of 2/3.
+    assert!(
+        (angular_velocity.y + 1.5).abs() < 1e-4,
+        "{:?}",
+        angular_velocity
+    );
+    let velocity = world
+        .rigid_body_system
+        .components()
+        .get(&body)
+        .unwrap()
+        .velocity;
+    assert!((velocity.z - 1.0).abs() < 1e-5);
+}
+
+#[test]
+fn oriented_boxes_tumble_off_edges() {
+    let mut world = World::new();
+    world
+        .spawn()
+        .with_transformation(TransformationBuilder::new())
+        .with_rigid_body(RigidBodyBuilder::new().with_extents(Vec3::new(2.0, 0.5, 2.0)))
+        .build();
+    let obb = world
+        .spawn()
+        .with_transformation(TransformationBuilder::new().at_position(Vec3::new(2.3, 1.0, 0.0)))
+        .with_rigid_body(
+            RigidBodyBuilder::new()
+                .with_oriented_extents(Vec3::new(0.5, 0.5, 0.5))
+                .with_mass(1.0)
+                .with_friction(0.6, 0.4),
+        )
+        .build();
+    let aabb = world
+        .spawn()
+        .with_transformation(TransformationBuilder::new().at_position(Vec3::new(-2.3, 1.0, 0.0)))
+        .with_rigid_body(
+            RigidBodyBuilder::new()
+                .with_mass(1.0)
+                .with_friction(0.6, 0.4),
+        )
+        .build();
+    let mut max_tilt: f32 = 0.0;
+    for step in 0..120 {
+        world
+            .rigid_body_system
+            .update(STEP, &mut world.transformation_system);
+        let rotation = world
+            .transformation_system
+            .get_transformation_data(&obb)
+            .unwrap()
+            .get_rotation();
+        let up = quat_rotate_vector(&rotation, Vec3::new(0.0, 1.0, 0.0));
+        max_tilt = max_tilt.max(1.0 - up.y);
+        if step < 30 {
+            assert!(up.x >= -1e-3, "tipped the wrong way {:?}", up);
+        }
+    }
+    let rotation = world
+        .transformation_system
+        .get_transformation_data(&aabb)
+        .unwrap()
+        .get_rotation();
+    assert!(rotation == Quat::identity());
+    let position = world.transformation_system.get_position(&obb).unwrap();
+    assert!(max_tilt > 0.2);
+    assert!(position.y < 0.0);
+    assert!((world.transformation_system.get_position(&aabb).unwrap().y - 1.0).abs() < 0.01);
+}
+
+#[test]
+fn tumbling_box_settles_flat() {
+    let mut world = World::new();
+    world
+        .spawn()
+        .with_transformation(TransformationBuilder::new())
+        .with_rigid_body(
+            RigidBodyBuilder::new()
+                .with_extents(Vec3::new(10.0, 0.5, 10.0))
+                .with_friction(0.6, 0.4),
+        )
+        .build();
+    let obb = world
+        .spawn()
+        .with_transformation(
+            TransformationBuilder::new()
+                .at_position(Vec3::new(0.0, 3.0, 0.0))
+                .with_rotation(Quat::rotation(0.5, Vec3::new(0.287, 0.0, 0.958))),
+        )
+        .with_rigid_body(
+            RigidBodyBuilder::new()
+                .with_oriented_extents(Vec3::new(0.5, 0.5, 0.5))
+                .with_mass(1.0)
+                .with_friction(0.6, 0.4),
+        )
+        .build();
+    let mut slept = false;
+    for _ in 0..900 {
+        world
+            .rigid_body_system
+            .update(STEP, &mut world.transformation_system);
+        slept |= world.rigid_body_system.is_sleeping(&obb);
+    }
+    let rotation = world
+        .transformation_system
+        .get_transformation_data(&obb)
+        .unwrap()
+        .get_rotation();
+    let position = world.transformation_system.get_position(&obb).unwrap();
+    let up = quat_rotate_vector(&rotation, Vec3::new(0.0, 1.0, 0.0));
+    assert!(slept);
+    assert!(up.y.abs() > 0.999 || up.x.abs() > 0.999 || up.z.abs() > 0.999);
+    assert!((position.y - 1.0).abs() < 0.1);
+}
+
+#[test]
+fn integration_turns_vectors_by_angular_velocity() {
+    let angular_velocity = Vec3::new(0.3, -0.7, 1.1);
+    let rotation = Quat::rotation(0.5, Vec3::new(1.0, 2.0, 0.5));
+    let v = quat_rotate_vector(&rotation, Vec3::new(0.2, 1.0, -0.4));
+    let dt = 0.001;
+
+    let turned = quat_integrate(&rotation, angular_velocity, dt);
+    let expected = v + angular_velocity.cross(v) * dt;
+    let actual = quat_rotate_vector(&turned, Vec3::new(0.2, 1.0, -0.4));
+
+    assert!(
+        (actual - expected).length() < 1.0e-5,
+        "{:?} {:?}",
+        actual,
+        expected
+    );
+}
@@ -0,0 +1,166 @@
+use black_grimoire::ecs::components::rigid_body::shape::{ColliderShape, CollisionManifold};
+use black_grimoire::ecs::components::rigid_body::RigidBodyBuilder;
+use black_grimoire::ecs::components::transformation::TransformationBuilder;
+use black_grimoire::ecs::world::World;
+use black_grimoire::gamemath::{Quat, Vec3};
+use std::f32::consts::{FRAC_PI_2, FRAC_PI_4};
+
+const STEP: f32 = 1.0 / 60.0;
+
+fn at(x: f32, y: f32, z: f32) -> (Vec3<f32>, Quat) {
+    (Vec3::new(x, y, z), Quat::identity())
+}
+
+fn assert_manifold(manifold: Option<CollisionManifold>, normal: Vec3<f32>, penetration: f32) {
+    let manifold = manifold.expect("shapes should be colliding");
+
+    assert!(
+        (manifold.get_normal() - normal).length() < 1.0e-3,
+        "normal {:?}",
+        manifold.get_normal()
+    );
+    assert!(
+        (manifold.get_penetration() - penetration).abs() < 1.0e-3,
+        "penetration {}",
+        manifold.get_penetration()
+    );
+}
+
+#[test]
+fn boxes() {
+    let aabb = ColliderShape::Box(Vec3::new(1.0, 1.0, 1.0));
+    let obb = ColliderShape::OrientedBox(Vec3::new(1.0, 1.0, 1.0));
+
+    assert_manifold(
+        aabb.colliding(at(0.0, 0.0, 0.0), &aabb, at(0.0, 1.5, 0.0)),
+        Vec3::new(0.0, 1.0, 0.0),
+        0.5,
+    );
+
+    // Without a rotation oriented boxes behave like axis aligned ones.
+    assert_manifold(
+        obb.colliding(at(0.0, 0.0, 0.0), &obb, at(0.0, 1.5, 0.0)),
+        Vec3::new(0.0, 1.0, 0.0),
+        0.5,
+    );
+
+    // Turned by 45 degrees the corner of the box reaches up to sqrt(2).
+    let turned = (
+        Vec3::new(0.0, 0.0, 0.0),
+        Quat::rotation(FRAC_PI_4, Vec3::new(0.0, 0.0, 1.0)),
+    );
+    assert!(obb.colliding(turned, &aabb, at(0.0, 2.3, 0.0)).is_some());
+    assert!(obb
+        .colliding(at(0.0, 0.0, 0.0), &aabb, at(0.0, 2.3, 0.0))
+        .is_none());
+}
+
+#[test]
+fn spheres() {
+    let aabb = ColliderShape::Box(Vec3::new(1.0, 1.0, 1.0));
+    let sphere = ColliderShape::Sphere(0.5);
+
+    assert_manifold(
+        sphere.colliding(at(0.0, 0.0, 0.0), &sphere, at(0.8, 0.0, 0.0)),
+        Vec3::new(1.0, 0.0, 0.0),
+        0.2,
+    );
+
+    // Close to the corner of the box, but not touching it.
+    assert!(sphere
+        .colliding(at(1.4, 1.4, 0.0), &aabb, at(0.0, 0.0, 0.0))
+        .is_none());
+
+    assert_manifold(
+        sphere.colliding(at(0.0, 1.3, 0.0), &aabb, at(0.0, 0.0, 0.0)),
+        Vec3::new(0.0, -1.0, 0.0),
+        0.2,
+    );
+
+    // The center of the sphere inside the box.
+    assert_manifold(
+        aabb.colliding(at(0.0, 0.0, 0.0), &sphere, at(0.0, 0.8, 0.0)),
+        Vec3::new(0.0, 1.0, 0.0),
+        0.7,
+    );
+}
+
+#[test]
+fn capsules() {
+    let aabb = ColliderShape::Box(Vec3::new(1.0, 1.0, 1.0));
+    let sphere = ColliderShape::Sphere(0.5);
+    let capsule = ColliderShape::Capsule {
+        radius: 0.5,
+        half_height: 1.0,
+    };
+
+    // Standing on a box.
+    assert_manifold(
+        aabb.colliding(at(0.0, 0.0, 0.0), &capsule, at(0.3, 2.4, 0.0)),
+        Vec3::new(0.0, 1.0, 0.0),
+        0.1,
+    );
+
+    // Against the side of a box.
+    assert_manifold(
+        capsule.colliding(at(1.4, 0.0, 0.0), &aabb, at(0.0, 0.0, 0.0)),
+        Vec3::new(-1.0, 0.0, 0.0),
+        0.1,
+    );
+
+    assert_manifold(
+        capsule.colliding(at(0.0, 0.0, 0.0), &capsule, at(0.9, 0.5, 0.0)),
+        Vec3::new(1.0, 0.0, 0.0),
+        0.1,
+    );
+
+    // A sphere touching the end cap.
+    assert_manifold(
+        capsule.colliding(at(0.0, 0.0, 0.0), &sphere, at(0.0, 1.9, 0.0)),
+        Vec3::new(0.0, 1.0, 0.0),
+        0.1,
+    );
+
+    // Lying on its side, sunk into a box.
+    let lying = (
+        Vec3::new(0.0, 0.9, 0.0),
+        Quat::rotation(FRAC_PI_2, Vec3::new(0.0, 0.0, 1.0)),
+    );
+    assert_manifold(
+        aabb.colliding(at(0.0, 0.0, 0.0), &capsule, lying),
+        Vec3::new(0.0, 1.0, 0.0),
+        0.6,
+    );
+}
+
+#[test]
+fn capsule_comes_to_rest_on_turned_box() {
+    let mut world = World::new();
+    world
+        .spawn()
+        .with_transformation(
+            TransformationBuilder::new()
+                .with_rotation(Quat::rotation(0.3, Vec3::new(0.0, 1.0, 0.0))),
+        )
+        .with_rigid_body(RigidBodyBuilder::new().with_oriented_extents(Vec3::new(10.0, 0.5, 10.0)))
+        .build();
+    let capsule = world
+        .spawn()
+        .with_transformation(TransformationBuilder::new().at_position(Vec3::new(0.0, 3.0, 0.0)))
+        .with_rigid_body(
+            RigidBodyBuilder::new()
+                .with_mass(1.0)
+                .with_capsule(0.5, 0.5),
+        )
+        .build();
+
+    for _ in 0..240 {
+        world
+            .rigid_body_system
+            .update(STEP, &mut world.transformation_system);
+    }
+
+    let position = world.transformation_system.get_position(&capsule).unwrap();
+    assert!((position.y - 1.5).abs() < 0.05, "{:?}", position);
+    assert!(world.rigid_body_system.entity_has_foothold(&capsule));
+}
@@ -0,0 +1,214 @@
+use black_grimoire::ecs::components::character_controller::CharacterControllerBuilder;
+use black_grimoire::ecs::components::rigid_body::joint::{JointBuilder, JointKind};
+use black_grimoire::ecs::components::rigid_body::snapshot::{PhysicsSnapshot, SnapshotHistory};
+use black_grimoire::ecs::components::rigid_body::RigidBodyBuilder;
+use black_grimoire::ecs::components::transformation::TransformationBuilder;
+use black_grimoire::ecs::world::World;
+use black_grimoire::ecs::Entity;
+use black_grimoire::gamemath::Vec3;
+
+const STEP: f32 = 1.0 / 60.0;
+
+// A hundred spinning bodies dropped on a floor, two of them joined together.
+fn pile() -> World {
+    let mut world = World::new();
+    world
+        .spawn()
+        .with_transformation(TransformationBuilder::new().at_position(Vec3::new(0.0, -0.5, 0.0)))
+        .with_rigid_body(RigidBodyBuilder::new().with_extents(Vec3::new(50.0, 0.5, 50.0)))
+        .build();
+
+    let bodies: Vec<_> = (0..100)
+        .map(|i| {
+            let position = Vec3::new(
+                (i % 5) as f32 * 0.9 - 2.0,
+                (i / 25) as f32 * 1.2 + 0.6 + (i % 3) as f32 * 0.05,
+                ((i / 5) % 5) as f32 * 0.9 - 2.0,
+            );
+            let builder = match i % 2 {
+                0 => RigidBodyBuilder::new().with_sphere(0.5),
+                _ => RigidBodyBuilder::new().with_oriented_extents(Vec3::new(0.4, 0.4, 0.4)),
+            };
+
+            world
+                .spawn()
+                .with_transformation(TransformationBuilder::new().at_position(position))
+                .with_rigid_body(
+                    builder
+                        .with_mass(1.0)
+                        .with_angular_velocity(Vec3::new(0.3 * (i % 5) as f32, 1.0, 0.0))
+                        .with_velocity(Vec3::new((i % 3) as f32 - 1.0, 0.0, 0.5)),
+                )
+                .build()
+        })
+        .collect();
+
+    world
+        .rigid_body_system
+        .add_joint(
+            &bodies[0],
+            Some(&bodies[1]),
+            JointBuilder::new(JointKind::Distance),
+            &world.transformation_system,
+        )
+        .unwrap();
+
+    world
+}
+
+fn step(world: &mut World) -> u64 {
+    world
+        .rigid_body_system
+        .update(STEP, &mut world.transformation_system);
+    world.character_controller_system.update(
+        STEP,
+        &mut world.transformation_system,
+        &world.rigid_body_system,
+    );
+    world
+        .rigid_body_system
+        .get_state_hash(&world.transformation_system)
+}
+
+fn record(world: &World, history: &mut SnapshotHistory) {
+    history.record(
+        &world.rigid_body_system,
+        &world.character_controller_system,
+        &world.transformation_system,
+    );
+}
+
+fn rewind(world: &mut World, history: &mut SnapshotHistory, steps_back: usize) -> bool {
+    history.rewind(
+        steps_back,
+        &mut world.rigid_body_system,
+        &mut world.character_controller_system,
+        &mut world.transformation_system,
+    )
+}
+
+// The position, velocity and ground contact of a character.
+fn character_state(world: &World, character: &Entity) -> (Vec3<f32>, Vec3<f32>, Option<Entity>) {
+    let controller = world
+        .character_controller_system
+        .components()
+        .get(character)
+        .unwrap();
+
+    (
+        world.transformation_system.get_position(character).unwrap(),
+        controller.get_velocity(),
+        controller.get_ground_entity(),
+    )
+}
+
+#[test]
+fn rewinds_and_replays_identically() {
+    let mut world = pile();
+    let mut history = SnapshotHistory::new(50);
+    let mut hashes = Vec::new();
+
+    for _ in 0..120 {
+        hashes.push(step(&mut world));
+        record(&world, &mut history);
+    }
+
+    assert_eq!(history.len(), 50);
+    assert!(history.get(50).is_none());
+    assert!(!rewind(&mut world, &mut history, 50));
+
+    // Back to the state after the 90th step.
+    assert!(rewind(&mut world, &mut history, 30));
+    assert_eq!(history.len(), 20);
+    assert_eq!(
+        world
+            .rigid_body_system
+            .get_state_hash(&world.transformation_system),
+        hashes[89]
+    );
+
+    for (i, hash) in hashes.iter().enumerate().skip(90) {
+        assert_eq!(step(&mut world), *hash, "step {}", i);
+    }
+}
+
+#[test]
+fn restoring_a_snapshot_repeats_the_next_step() {
+    let mut world = pile();
+    let mut snapshot = PhysicsSnapshot::new();
+
+    for _ in 0..30 {
+        step(&mut world);
+    }
+
+    world
+        .rigid_body_system
+        .save_snapshot(&world.transformation_system, &mut snapshot);
+    assert_eq!(snapshot.get_body_count(), 101);
+
+    let next = step(&mut world);
+
+    for _ in 0..10 {
+        step(&mut world);
+    }
+
+    world
+        .rigid_body_system
+        .restore_snapshot(&snapshot, &mut world.transformation_system);
+    assert_eq!(step(&mut world), next);
+}
+
+#[test]
+fn rewinds_characters_with_their_timers_and_platforms() {
+    let mut world = World::new();
+    let platform = world
+        .spawn()
+        .with_transformation(TransformationBuilder::new().at_position(Vec3::new(0.0, -0.25, 0.0)))
+        .with_rigid_body(
+            RigidBodyBuilder::new()
+                .with_extents(Vec3::new(20.0, 0.25, 20.0))
+                .with_velocity(Vec3::new(1.0, 0.0, 0.0)),
+        )
+        .build();
+    let character = world
+        .spawn()
+        .with_transformation(TransformationBuilder::new().at_position(Vec3::new(0.0, 0.5, 0.0)))
+        .with_character_controller(
+            CharacterControllerBuilder::new().with_offset(Vec3::new(0.0, 0.9, 0.0)),
+        )
+        .build();
+    let mut history = SnapshotHistory::new(120);
+    let mut states = Vec::new();
+
+    world
+        .character_controller_system
+        .set_movement(&character, Vec3::new(0.0, 0.0, 2.0));
+
+    for i in 0..90 {
+        // Jumping while still falling onto the platform is buffered until landing.
+        if i == 14 {
+            world.character_controller_system.jump(&character);
+        }
+
+        step(&mut world);
+        record(&world, &mut history);
+        states.push(character_state(&world, &character));
+    }
+
+    assert_eq!(history.get(0).unwrap().get_character_count(), 1);
+    assert!(states.iter().any(|state| state.1.y > 0.0));
+    assert_eq!(states[89].2, Some(platform));
+
+    // Back to the step the jump was pressed in, before it was taken, and with another walking
+    // direction that the rewind has to undo.
+    world
+        .character_controller_system
+        .set_movement(&character, Vec3::new(-2.0, 0.0, 0.0));
+    assert!(rewind(&mut world, &mut history, 75));
+    assert_eq!(character_state(&world, &character), states[14]);
+
+    for (i, state) in states.iter().enumerate().skip(15) {
+        step(&mut world);
+        assert_eq!(character_state(&world, &character), *state, "step {}", i);
+    }
+}
@@ -0,0 +1,123 @@
+use black_grimoire::ecs::components::rigid_body::RigidBodyBuilder;
+use black_grimoire::ecs::components::transformation::TransformationBuilder;
+use black_grimoire::ecs::world::World;
+use black_grimoire::ecs::Entity;
+use black_grimoire::gamemath::Vec3;
+
+const STEP: f32 = 1.0 / 60.0;
+
+fn floor(world: &mut World) {
+    world
+        .spawn()
+        .with_transformation(TransformationBuilder::new())
+        .with_rigid_body(
+            RigidBodyBuilder::new()
+                .with_extents(Vec3::new(50.0, 0.5, 10.0))
+                .with_friction(0.6, 0.4),
+        )
+        .build();
+}
+
+// Five slightly offset boxes, each dropped a little above the one below it.
+fn stack(world: &mut World) -> Vec<Entity> {
+    (0..5)
+        .map(|i| {
+            world
+                .spawn()
+                .with_transformation(TransformationBuilder::new().at_position(Vec3::new(
+                    0.02 * i as f32,
+                    1.05 + 1.05 * i as f32,
+                    0.0,
+                )))
+                .with_rigid_body(
+                    RigidBodyBuilder::new()
+                        .with_mass(1.0)
+                        .with_friction(0.6, 0.4),
+                )
+                .build()
+        })
+        .collect()
+}
+
+#[test]
+fn stack_of_five_boxes_settles_and_sleeps() {
+    let mut world = World::new();
+    floor(&mut world);
+    let boxes = stack(&mut world);
+    let mut asleep_at = None;
+
+    for step in 0..600 {
+        world
+            .rigid_body_system
+            .update(STEP, &mut world.transformation_system);
+
+        if asleep_at.is_none() && boxes.iter().all(|b| world.rigid_body_system.is_sleeping(b)) {
+            asleep_at = Some(step);
+        }
+    }
+
+    assert!(asleep_at.is_some_and(|step| step < 300), "{:?}", asleep_at);
+
+    for (i, b) in boxes.iter().enumerate() {
+        let position = world.transformation_system.get_position(b).unwrap();
+
+        assert!(world.rigid_body_system.is_sleeping(b));
+        assert!(
+            (position.y - (1.0 + i as f32)).abs() < 0.04,
+            "box {} at {:?}",
+            i,
+            position
+        );
+    }
+}
+
+#[test]
+fn stacks_settle_the_same_way_every_run() {
+    let positions: Vec<Vec<Vec3<f32>>> = (0..2)
+        .map(|_| {
+            let mut world = World::new();
+            floor(&mut world);
+            let boxes = stack(&mut world);
+
+            for _ in 0..300 {
+                world
+                    .rigid_body_system
+                    .update(STEP, &mut world.transformation_system);
+            }
+
+            boxes
+                .iter()
+                .map(|b| world.transformation_system.get_position(b).unwrap())
+                .collect()
+        })
+        .collect();
+
+    assert_eq!(positions[0], positions[1]);
+}
+
+#[test]
+fn friction_stops_a_sliding_box() {
+    let mut world = World::new();
+    floor(&mut world);
+    let sliding = world
+        .spawn()
+        .with_transformation(TransformationBuilder::new().at_position(Vec3::new(0.0, 1.0, 0.0)))
+        .with_rigid_body(
+            RigidBodyBuilder::new()
+                .with_mass(1.0)
+                .with_friction(0.6, 0.4)
+                .with_velocity(Vec3::new(5.0, 0.0, 0.0)),
+        )
+        .build();
+
+    for _ in 0..300 {
+        world
+            .rigid_body_system
+            .update(STEP, &mut world.transformation_system);
+    }
+
+    // Slides v^2 / (2 * mu * g) with the dynamic friction, about 3.18 metres.
+    let position = world.transformation_system.get_position(&sliding).unwrap();
+    assert!((position.x - 3.18).abs() < 0.2, "{:?}", position);
+    assert!(world.rigid_body_system.is_sleeping(&sliding));
+}
@@ -0,0 +1,89 @@
+use black_grimoire::ecs::storage::ComponentStorage;
+use black_grimoire::ecs::{Entity, EntityManager};
+
+// Every row is where the index map says it is, and the owners line up with the data.
+fn assert_consistent(storage: &ComponentStorage<Entity>) {
+    assert_eq!(storage.entities().len(), storage.len());
+    assert_eq!(storage.as_slice().len(), storage.len());
+
+    for (index, entity) in storage.entities().iter().enumerate() {
+        assert_eq!(storage.index_of(entity), Some(index));
+        assert_eq!(storage.get(entity), Some(entity));
+    }
+}
+
+#[test]
+fn rejects_null_and_duplicate_inserts() {
+    let mut entity_manager = EntityManager::new();
+    let entity = entity_manager.create_new_entity();
+    let mut storage = ComponentStorage::new();
+
+    assert!(!storage.insert(&Entity::null(), Entity::null()));
+    assert!(storage.is_empty());
+
+    assert!(storage.insert(&entity, entity));
+    assert!(!storage.insert(&entity, Entity::null()));
+    assert_eq!(storage.len(), 1);
+    assert_eq!(storage.get(&entity), Some(&entity));
+    assert!(!storage.contains(&Entity::null()));
+}
+
+#[test]
+fn swap_remove_keeps_the_index_map_consistent() {
+    let mut entity_manager = EntityManager::new();
+    let entities: Vec<_> = (0..8).map(|_| entity_manager.create_new_entity()).collect();
+    let mut storage = ComponentStorage::new();
+
+    for entity in entities.iter() {
+        storage.insert(entity, *entity);
+    }
+
+    // The first row is filled by the last one.
+    assert_eq!(storage.remove(&entities[0]), Some(entities[0]));
+    assert_eq!(storage.index_of(&entities[7]), Some(0));
+    assert_consistent(&storage);
+
+    // Removing the last row moves nothing.
+    assert_eq!(storage.remove(&entities[6]), Some(entities[6]));
+    assert_consistent(&storage);
+
+    assert_eq!(storage.remove(&entities[3]), Some(entities[3]));
+    assert_eq!(storage.remove(&entities[3]), None);
+    assert_consistent(&storage);
+
+    assert_eq!(storage.len(), 5);
+    assert!(!storage.contains(&entities[0]));
+
+    // Rows freed by removals can be taken again.
+    assert!(storage.insert(&entities[0], entities[0]));
+    assert_eq!(storage.index_of(&entities[0]), Some(5));
+    assert_consistent(&storage);
+
+    for entity in entities.iter() {
+        storage.remove(entity);
+        assert_consistent(&storage);
+    }
+
+    assert!(storage.is_empty());
+}
+
+#[test]
+fn drains_destroyed_entities() {
+    let mut entity_manager = EntityManager::new();
+    let entities: Vec<_> = (0..5).map(|_| entity_manager.create_new_entity()).collect();
+    let mut storage = ComponentStorage::new();
+
+    for entity in entities.iter() {
+        storage.insert(entity, *entity);
+    }
+
+    entity_manager.destroy_entity(&entities[1]).unwrap();
+    entity_manager.destroy_entity(&entities[4]).unwrap();
+    storage.remove_destroyed(&entity_manager);
+
+    assert_eq!(
+        storage.entities().to_vec(),
+        vec![entities[0], entities[3], entities[2]]
+    );
+    assert_consistent(&storage);
+}
@@ -0,0 +1,85 @@
+use black_grimoire::ecs::components::rigid_body::config::PhysicsConfig;
+use black_grimoire::ecs::components::rigid_body::RigidBodyBuilder;
+use black_grimoire::ecs::components::transformation::TransformationBuilder;
+use black_grimoire::ecs::world::World;
+use black_grimoire::ecs::Entity;
+use black_grimoire::gamemath::Vec3;
+
+const STEP: f32 = 1.0 / 60.0;
+
+// Enough bodies for the work to be spread over several threads.
+fn scene() -> (World, Vec<Entity>) {
+    let mut world = World::new();
+    world
+        .spawn()
+        .with_transformation(TransformationBuilder::new().at_position(Vec3::new(0.0, -0.5, 0.0)))
+        .with_rigid_body(RigidBodyBuilder::new().with_extents(Vec3::new(100.0, 0.5, 100.0)))
+        .build();
+
+    let spheres = (0..2000)
+        .map(|i| {
+            let x = (i % 40) as f32 * 1.1 - 20.0;
+            let y = (i / 400) as f32 * 1.1 + 0.6 + (i % 7) as f32 * 0.01;
+            let z = ((i / 40) % 10) as f32 * 1.1 - 5.0;
+
+            world
+                .spawn()
+                .with_transformation(TransformationBuilder::new().at_position(Vec3::new(x, y, z)))
+                .with_rigid_body(
+                    RigidBodyBuilder::new()
+                        .with_mass(1.0)
+                        .with_sphere(0.5)
+                        .with_velocity(Vec3::new((i % 3) as f32 - 1.0, 0.0, 0.0)),
+                )
+                .build()
+        })
+        .collect();
+
+    (world, spheres)
+}
+
+fn set_threads(world: &mut World, threads: usize) {
+    world.rigid_body_system.set_config(PhysicsConfig {
+        threads,
+        ..PhysicsConfig::default()
+    });
+}
+
+// Runs half a second of the scene, picking the thread count before every step.
+fn run<F: Fn(usize) -> usize>(threads: F) -> Vec<[u32; 3]> {
+    let (mut world, spheres) = scene();
+
+    for step in 0..30 {
+        set_threads(&mut world, threads(step));
+        world
+            .rigid_body_system
+            .update(STEP, &mut world.transformation_system);
+    }
+
+    spheres
+        .iter()
+        .map(|sphere| {
+            let position = world.transformation_system.get_position(sphere).unwrap();
+            [
+                position.x.to_bits(),
+                position.y.to_bits(),
+                position.z.to_bits(),
+            ]
+        })
+        .collect()
+}
+
+#[test]
+fn same_result_for_any_thread_count() {
+    let single = run(|_| 1);
+
+    assert!(single == run(|_| 4));
+    assert!(single == run(|_| 0));
+}
+
+#[test]
+fn thread_count_can_change_between_steps() {
+    let single = run(|_| 1);
+
+    assert!(single == run(|step| [4, 1, 0, 3][step % 4]));
+}
@@ -0,0 +1,126 @@
+use black_grimoire::ecs::components::rigid_body::RigidBodyBuilder;
+use black_grimoire::ecs::components::transformation::TransformationBuilder;
+use black_grimoire::ecs::world::World;
+use black_grimoire::gamemath::{Quat, Vec3};
+
+fn close(a: Vec3<f32>, b: Vec3<f32>) -> bool {
+    (a - b).length() < 1.0e-4
+}
+
+#[test]
+fn children_follow_parents_and_keep_world_pose_when_reparented() {
+    let mut world = World::new();
+    let parent = world
+        .spawn()
+        .with_transformation(
+            TransformationBuilder::new()
+                .at_position(Vec3::new(1.0, 0.0, 0.0))
+                .with_rotation(Quat::rotation(1.0, Vec3::new(0.0, 0.0, 1.0)))
+                .with_scale(Vec3::new(2.0, 2.0, 2.0)),
+        )
+        .build();
+    let child = world
+        .spawn()
+        .with_transformation(
+            TransformationBuilder::new()
+                .at_position(Vec3::new(1.0, 0.0, 0.0))
+                .with_parent(parent),
+        )
+        .build();
+    let grandchild = world
+        .spawn()
+        .with_transformation(
+            TransformationBuilder::new()
+                .at_position(Vec3::new(0.0, 1.0, 0.0))
+                .with_parent(child)
+                .destroyed_with_parent(),
+        )
+        .build();
+
+    let ts = &mut world.transformation_system;
+    ts.update();
+
+    // Cycles are refused.
+    assert!(!ts.set_parent(&parent, Some(&grandchild)));
+
+    let world_position = ts.get_world_position(&grandchild).unwrap();
+    assert!(ts.set_parent(&grandchild, None));
+    assert!(close(
+        ts.get_world_position(&grandchild).unwrap(),
+        world_position
+    ));
+    assert!(ts.set_parent(&grandchild, Some(&child)));
+    ts.update();
+    assert!(close(
+        ts.get_world_position(&grandchild).unwrap(),
+        world_position
+    ));
+
+    let child_position = ts.get_world_position(&child).unwrap();
+    ts.set_position(&parent, Vec3::new(5.0, 0.0, 0.0));
+    ts.update();
+    assert!(close(
+        ts.get_world_position(&child).unwrap(),
+        child_position + Vec3::new(4.0, 0.0, 0.0)
+    ));
+
+    world.despawn(&child).unwrap();
+    assert!(!world.entity_manager.entity_is_alive(&grandchild));
+    assert!(world.transformation_system.get_children(&parent).is_empty());
+    assert_eq!(world.transformation_system.components().len(), 1);
+}
+
+#[test]
+fn data_setters_mark_the_pose_changed() {
+    let mut world = World::new();
+    let entity = world
+        .spawn()
+        .with_transformation(TransformationBuilder::new())
+        .build();
+    world.transformation_system.update();
+
+    let mut components = world.transformation_system.components_mut();
+    let data = components.get_mut(&entity).unwrap();
+    assert!(!data.is_dirty());
+    data.set_position(Vec3::new(1.0, 2.0, 3.0));
+    assert!(data.is_dirty());
+    assert_eq!(data.get_position(), Vec3::new(1.0, 2.0, 3.0));
+}
+
+#[test]
+fn bodies_under_a_translated_parent_simulate_in_world_space() {
+    let mut world = World::new();
+    world
+        .spawn()
+        .with_transformation(TransformationBuilder::new())
+        .with_rigid_body(RigidBodyBuilder::new().with_extents(Vec3::new(10.0, 0.5, 10.0)))
+        .build();
+    let parent = world
+        .spawn()
+        .with_transformation(TransformationBuilder::new().at_position(Vec3::new(2.0, 10.0, 0.0)))
+        .build();
+    let body = world
+        .spawn()
+        .with_transformation(
+            TransformationBuilder::new()
+                .at_position(Vec3::new(0.0, -7.0, 0.0))
+                .with_parent(parent),
+        )
+        .with_rigid_body(RigidBodyBuilder::new().with_mass(1.0))
+        .build();
+
+    for _ in 0..300 {
+        world.simulate(1.0 / 60.0);
+    }
+
+    // The box rests on the floor in world space, and its local position stays relative to the
+    // parent.
+    let ts = &world.transformation_system;
+    let position = ts.get_world_position(&body).unwrap();
+    assert!((position.x - 2.0).abs() < 0.01, "{:?}", position);
+    assert!((position.y - 1.0).abs() < 0.04, "{:?}", position);
+    assert!(close(
+        ts.get_position(&body).unwrap(),
+        position - Vec3::new(2.0, 10.0, 0.0)
+    ));
+}
@@ -0,0 +1,175 @@
+use black_grimoire::ecs::components::character_controller::CharacterControllerBuilder;
+use black_grimoire::ecs::components::health::HealthBuilder;
+use black_grimoire::ecs::components::name::NameBuilder;
+use black_grimoire::ecs::components::rigid_body::RigidBodyBuilder;
+use black_grimoire::ecs::components::transformation::TransformationBuilder;
+use black_grimoire::ecs::world::World;
+use black_grimoire::ecs::EntityError;
+use black_grimoire::gamemath::Vec3;
+
+const STEP: f32 = 1.0 / 60.0;
+
+#[test]
+fn spawned_entity_has_every_component_it_was_built_with() {
+    let mut world = World::new();
+    let entity = world
+        .spawn()
+        .with_transformation(TransformationBuilder::new().at_position(Vec3::new(1.0, 2.0, 3.0)))
+        .with_rigid_body(RigidBodyBuilder::new().with_mass(1.0))
+        .with_health(HealthBuilder::new().with_hitpoints((5.0, 10.0)))
+        .with_name(NameBuilder::new().with_name("crate").with_tag("loot"))
+        .build();
+    let hidden = world
+        .spawn()
+        .with_transformation(TransformationBuilder::new())
+        .inactive()
+        .build();
+
+    assert!(world.entity_manager.entity_is_active(&entity));
+    assert_eq!(
+        world.transformation_system.get_position(&entity),
+        Some(Vec3::new(1.0, 2.0, 3.0))
+    );
+    assert!(world.rigid_body_system.entity_has_rigid_body(&entity));
+    assert_eq!(
+        world.health_system.get_hitpoints(&entity),
+        Some((5.0, 10.0))
+    );
+    assert_eq!(world.find_entity_by_name("crate"), Some(entity));
+    assert_eq!(
+        world.get_entities_with_tag("loot").collect::<Vec<_>>(),
+        vec![entity]
+    );
+
+    assert!(world.entity_manager.entity_is_alive(&hidden));
+    assert!(!world.entity_manager.entity_is_active(&hidden));
+}
+
+// Components are added in call order, so bodies and controllers given before the transformation
+// are left out.
+#[test]
+fn components_needing_a_transformation_are_skipped_without_one() {
+    let mut world = World::new();
+    let entity = world
+        .spawn()
+        .with_rigid_body(RigidBodyBuilder::new().with_mass(1.0))
+        .with_character_controller(CharacterControllerBuilder::new())
+        .with_transformation(TransformationBuilder::new())
+        .build();
+
+    assert!(world
+        .transformation_system
+        .entity_has_transformation(&entity));
+    assert!(!world.rigid_body_system.entity_has_rigid_body(&entity));
+    assert!(!world
+        .character_controller_system
+        .entity_has_character_controller(&entity));
+}
+
+#[test]
+fn despawn_fails_for_dead_and_foreign_handles() {
+    let mut world = World::new();
+    let kept = world
+        .spawn()
+        .with_transformation(TransformationBuilder::new())
+        .build();
+    let despawned = world
+        .spawn()
+        .with_transformation(TransformationBuilder::new())
+        .with_health(HealthBuilder::new())
+        .build();
+
+    let mut other = World::new();
+    let foreign = (0..3).map(|_| other.spawn().build()).last().unwrap();
+
+    assert_eq!(world.despawn(&despawned), Ok(()));
+    assert_eq!(world.despawn(&despawned), Err(EntityError::Dead));
+    assert_eq!(world.despawn(&foreign), Err(EntityError::Unknown));
+
+    assert!(!world.health_system.entity_has_health(&despawned));
+    assert!(world.entity_manager.entity_is_alive(&kept));
+    assert!(world.transformation_system.entity_has_transformation(&kept));
+    assert!(world.entity_manager.get_destroyed_entities().is_empty());
+}
+
+#[test]
+fn collisions_harm_and_kill_within_the_same_update() {
+    let mut world = World::new();
+    world
+        .spawn()
+        .with_transformation(TransformationBuilder::new())
+        .with_rigid_body(
+            RigidBodyBuilder::new()
+                .with_extents(Vec3::new(5.0, 0.5, 5.0))
+                .dealing_damage(10.0),
+        )
+        .build();
+    let body = world
+        .spawn()
+        .with_transformation(TransformationBuilder::new().at_position(Vec3::new(0.0, 1.2, 0.0)))
+        .with_rigid_body(RigidBodyBuilder::new().with_mass(1.0))
+        .with_health(HealthBuilder::new().with_hitpoints((25.0, 25.0)))
+        .build();
+
+    let mut updates = 0;
+
+    while world.entity_manager.entity_is_alive(&body) {
+        world.simulate(STEP);
+        updates += 1;
+
+        assert!(updates < 60);
+    }
+
+    // The body is destroyed by the update that dealt the last of the damage, its components go
+    // once the destroyed entities are removed.
+    assert!(world
+        .entity_manager
+        .get_destroyed_entities()
+        .contains(&body));
+    assert!(world.health_system.get_hitpoints(&body).unwrap().0 <= 0.0);
+
+    world.remove_destroyed_entities();
+
+    assert!(!world.rigid_body_system.entity_has_rigid_body(&body));
+    assert!(!world.health_system.entity_has_health(&body));
+}
+
+#[test]
+fn characters_move_after_the_bodies_they_stand_on() {
+    let mut world = World::new();
+    let platform = world
+        .spawn()
+        .with_transformation(TransformationBuilder::new().at_position(Vec3::new(0.0, -0.25, 0.0)))
+        .with_rigid_body(
+            RigidBodyBuilder::new()
+                .with_extents(Vec3::new(2.0, 0.25, 2.0))
+                .with_velocity(Vec3::new(0.0, 1.0, 0.0)),
+        )
+        .build();
+    let character = world
+        .spawn()
+        .with_transformation(TransformationBuilder::new())
+        .with_character_controller(
+            CharacterControllerBuilder::new().with_offset(Vec3::new(0.0, 0.9, 0.0)),
+        )
+        .build();
+
+    for _ in 0..30 {
+        world.simulate(STEP);
+
+        // Standing on the top of the platform as it is after this update, not the last one.
+        let feet = world
+            .transformation_system
+            .get_position(&character)
+            .unwrap();
+        let top = world
+            .transformation_system
+            .get_position(&platform)
+            .unwrap()
+            .y
+            + 0.25;
+        assert!(feet.y > top && feet.y - top < 0.01, "{} {}", feet.y, top);
+    }
+
+    assert!(world.character_controller_system.is_grounded(&character));
+}